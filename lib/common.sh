@@ -28,10 +28,15 @@ find_freight_root() {
     return 1
 }
 
-# Get the freight directory for the current context
+# Get the freight directory for the current context. A regular file (a
+# top-level file migrated on its own) gets .freight/files/<name>/ beside it
 get_freight_dir() {
     local target_dir="${1:-.}"
-    echo "$target_dir/$FREIGHT_DIR"
+    if [[ -f "$target_dir" ]]; then
+        echo "$(dirname "$target_dir")/$FREIGHT_DIR/files/$(basename "$target_dir")"
+    else
+        echo "$target_dir/$FREIGHT_DIR"
+    fi
 }
 
 # Ensure freight directory exists
@@ -182,4 +187,19 @@ validate_directory() {
     fi
     
     return 0
+}
+
+# Validate a migration unit: a readable directory, or a readable regular file
+validate_unit() {
+    local unit="$1"
+    
+    if [[ -f "$unit" ]]; then
+        if [[ ! -r "$unit" ]]; then
+            log_error "File is not readable: $unit"
+            return 1
+        fi
+        return 0
+    fi
+    
+    validate_directory "$unit" "read"
 }
\ No newline at end of file
@@ -52,12 +52,8 @@ log_message() {
         $LOG_LEVEL_ERROR) color="$RED" ;;
     esac
     
-    # Output to stderr for levels WARN and above, stdout for others
-    if (( level >= LOG_LEVEL_WARN )); then
-        echo -e "${color}${log_entry}${NC}" >&2
-    else
-        echo -e "${color}${log_entry}${NC}"
-    fi
+    # Always stderr: callers capture function results from stdout
+    echo -e "${color}${log_entry}${NC}" >&2
     
     # Also write to log file if specified
     if [[ -n "$LOG_FILE" ]]; then
@@ -3,12 +3,26 @@
 
 # Socket configuration
 SOCKET_PATH="/tmp/freight-daemon.sock"
+# Remote workers set FREIGHT_DAEMON_ADDR=host:port to use the TCP transport
+FREIGHT_DAEMON_ADDR="${FREIGHT_DAEMON_ADDR:-}"
+# Set by the daemon when it listens in the Linux abstract namespace
+FREIGHT_DAEMON_ABSTRACT="${FREIGHT_DAEMON_ABSTRACT:-}"
 SOCKET_RETRY_INTERVAL=10
 MAX_SOCKET_RETRIES=3
+# Minimum gap between PROGRESS messages, set by the daemon from
+# progress_interval_ms; 0 sends every update
+FREIGHT_PROGRESS_INTERVAL_MS="${FREIGHT_PROGRESS_INTERVAL_MS:-250}"
+
+# Progress throttling state
+SOCKET_LAST_PROGRESS_MS=0
+SOCKET_PENDING_PROGRESS=""
 
 # Socket connection state
 SOCKET_CONNECTED=false
 SOCKET_MANUAL_MODE=false
+# Set once STOP has been sent, so cleanup doesn't report a finished run as
+# interrupted
+SOCKET_STOPPED=false
 
 # Initialize socket communication
 socket_init() {
@@ -30,11 +44,29 @@ socket_init() {
     fi
 }
 
+# Write stdin to the daemon over whichever transport is configured,
+# authenticating first when FREIGHT_AUTH_TOKEN is set
+socket_nc() {
+    {
+        if [[ -n "${FREIGHT_AUTH_TOKEN:-}" ]]; then
+            echo "AUTH $FREIGHT_AUTH_TOKEN"
+        fi
+        cat
+    } | if [[ -n "$FREIGHT_DAEMON_ADDR" ]]; then
+        nc -w 1 "${FREIGHT_DAEMON_ADDR%:*}" "${FREIGHT_DAEMON_ADDR##*:}"
+    elif [[ -n "$FREIGHT_DAEMON_ABSTRACT" ]]; then
+        # nc can't address abstract sockets; socat can
+        socat -t 1 - "ABSTRACT-CONNECT:$FREIGHT_DAEMON_ABSTRACT"
+    else
+        nc -U "$SOCKET_PATH" -w 1
+    fi
+}
+
 # Test if socket connection is available
 socket_test_connection() {
-    if [[ -S "$SOCKET_PATH" ]]; then
+    if [[ -n "$FREIGHT_DAEMON_ADDR" || -n "$FREIGHT_DAEMON_ABSTRACT" || -S "$SOCKET_PATH" ]]; then
         # Try to connect and send a test message
-        echo "TEST" | nc -U "$SOCKET_PATH" -w 1 >/dev/null 2>&1
+        echo "TEST" | socket_nc >/dev/null 2>&1
         return $?
     else
         return 1
@@ -53,7 +85,7 @@ socket_send() {
     fi
     
     while (( retry_count < MAX_SOCKET_RETRIES )); do
-        if echo "$message" | nc -U "$SOCKET_PATH" -w 1 >/dev/null 2>&1; then
+        if echo "$message" | socket_nc >/dev/null 2>&1; then
             SOCKET_CONNECTED=true
             log_debug "Socket message sent: $message"
             return 0
@@ -73,6 +105,18 @@ socket_send() {
     done
 }
 
+# Percent-encode a message value so spaces and the like survive the
+# daemon's whitespace split. Mirrors escape_value in src/socket.rs.
+socket_escape() {
+    local value="$1"
+    value="${value//%/%25}"
+    value="${value// /%20}"
+    value="${value//$'\t'/%09}"
+    value="${value//$'\n'/%0A}"
+    value="${value//$'\r'/%0D}"
+    printf '%s' "$value"
+}
+
 # Send hello message
 socket_hello() {
     local tool="$1"
@@ -81,7 +125,8 @@ socket_hello() {
     hostname="$(hostname)"
     local pid="$$"
     
-    local message="HELLO freight/0.1.0 host=$hostname pid=$pid"
+    # tool/dir let the daemon file this under the same id as START
+    local message="HELLO freight/0.1.0 host=$(socket_escape "$hostname") pid=$pid tool=$(socket_escape "$tool") dir=$(socket_escape "$directory")"
     socket_send "$message"
 }
 
@@ -90,26 +135,62 @@ socket_start() {
     local tool="$1"
     local directory="$2"
     
-    local message="START tool=$tool dir=$directory"
+    local message="START tool=$(socket_escape "$tool") dir=$(socket_escape "$directory")"
     socket_send "$message"
 }
 
-# Send progress message
+# Milliseconds since the epoch
+socket_now_ms() {
+    if [[ -n "${EPOCHREALTIME:-}" ]]; then
+        local micros="${EPOCHREALTIME/[.,]/}"
+        echo $((micros / 1000))
+    else
+        echo $(($(date +%s) * 1000))
+    fi
+}
+
+# Send progress message, at most one per FREIGHT_PROGRESS_INTERVAL_MS.
+# Updates inside the window replace each other and the latest goes out
+# with the next one after it, or just before STOP.
 socket_progress() {
     local tool="$1"
     local directory="$2"
     local progress_message="$3"
     local bytes_processed="${4:-}"
     
-    local message="PROGRESS tool=$tool dir=$directory msg=$progress_message"
+    local message="PROGRESS tool=$(socket_escape "$tool") dir=$(socket_escape "$directory") msg=$(socket_escape "$progress_message")"
     
     if [[ -n "$bytes_processed" ]]; then
         message="$message bytes=$bytes_processed"
     fi
     
+    # The daemon counts rsync attempts from these, so none may be dropped
+    if [[ "$progress_message" == Attempt\ * ]]; then
+        socket_flush_progress
+    elif (( FREIGHT_PROGRESS_INTERVAL_MS > 0 )); then
+        local now
+        now="$(socket_now_ms)"
+        if (( now - SOCKET_LAST_PROGRESS_MS < FREIGHT_PROGRESS_INTERVAL_MS )); then
+            SOCKET_PENDING_PROGRESS="$message"
+            return 0
+        fi
+        SOCKET_LAST_PROGRESS_MS="$now"
+        SOCKET_PENDING_PROGRESS=""
+    fi
+    
     socket_send "$message"
 }
 
+# Send the last throttled progress update, if one is still held back
+socket_flush_progress() {
+    if [[ -n "$SOCKET_PENDING_PROGRESS" ]]; then
+        local message="$SOCKET_PENDING_PROGRESS"
+        SOCKET_PENDING_PROGRESS=""
+        SOCKET_LAST_PROGRESS_MS="$(socket_now_ms)"
+        socket_send "$message"
+    fi
+}
+
 # Send stop message
 socket_stop() {
     local tool="$1"
@@ -117,18 +198,42 @@ socket_stop() {
     local status="$3"
     local bytes_processed="${4:-}"
     local final_message="${5:-}"
+    local bytes_total="${6:-}"
+    local files="${7:-}"
     
-    local message="STOP tool=$tool dir=$directory status=$status"
+    # Nothing reported before STOP should be lost to throttling
+    socket_flush_progress
+    
+    local message="STOP tool=$(socket_escape "$tool") dir=$(socket_escape "$directory") status=$(socket_escape "$status")"
     
     if [[ -n "$bytes_processed" ]]; then
         message="$message bytes=$bytes_processed"
     fi
     
+    if [[ -n "$bytes_total" ]]; then
+        message="$message bytes_total=$bytes_total"
+    fi
+    
+    if [[ -n "$files" ]]; then
+        message="$message files=$files"
+    fi
+    
     if [[ -n "$final_message" ]]; then
-        message="$message msg=$final_message"
+        message="$message msg=$(socket_escape "$final_message")"
     fi
     
     socket_send "$message"
+    SOCKET_STOPPED=true
+}
+
+# Tell the daemon this tool is exiting
+socket_bye() {
+    local tool="$1"
+    local directory="$2"
+    
+    socket_send "BYE tool=$(socket_escape "$tool") dir=$(socket_escape "$directory")"
+    # Nothing more to say; also keeps a second cleanup trap quiet
+    SOCKET_CONNECTED=false
 }
 
 # Send custom message
@@ -206,9 +311,12 @@ socket_stop_health_monitor() {
 socket_cleanup() {
     socket_stop_health_monitor
     
-    # Send final disconnect if connected
+    # Report an unfinished run as interrupted, then say goodbye
     if socket_is_connected; then
-        socket_stop "$TOOL_NAME" "$CURRENT_DIRECTORY" "interrupted" "" "Process terminated"
+        if [[ "$SOCKET_STOPPED" != "true" ]]; then
+            socket_stop "$TOOL_NAME" "$CURRENT_DIRECTORY" "interrupted" "" "Process terminated"
+        fi
+        socket_bye "$TOOL_NAME" "$CURRENT_DIRECTORY"
     fi
 }
 
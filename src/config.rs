@@ -1,7 +1,39 @@
+use crate::export::ExportFormat;
+use crate::logging::LogFormat;
+use crate::report::ReportFormat;
+use crate::tui::{ThemeName, DEFAULT_MAX_UPDATES_PER_FRAME, DEFAULT_NAME_TEMPLATE};
+use crate::worker::{
+    ChecksumAlgorithm, DestPermissions, ExistingPolicy, MigrationBackend, RetryBackoff,
+    SchedulePolicy, DEFAULT_MAX_CAPTURED_OUTPUT,
+};
+use crate::socket::{
+    auth_token_from_env, lookup_group_id, lookup_user_id, ConnectionLimits, ListenAddr,
+    SocketAddressMode, SocketPermissions, Transport, WorkerRetention, ABSTRACT_SOCKET_NAME,
+    DEFAULT_BROADCAST_CAPACITY, DEFAULT_MAX_LINE_LENGTH, DEFAULT_TCP_ADDRESS, SOCKET_PATH,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use anyhow::{Result, Context};
+use std::env;
 use std::fs;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing_subscriber::filter::LevelFilter;
+
+/// A shared secret such as `auth_token`, kept out of `Debug` output so
+/// logging a config doesn't leak it.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(pub String);
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("\"<redacted>\"")
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -9,8 +41,332 @@ pub struct Config {
     pub dest_path: PathBuf,
     pub thresholds: Thresholds,
     pub rsync_flags: String,
+    /// rsync flags for specific tools, e.g. `{"migrate": "-aHAX --delete"}`;
+    /// tools not listed use `rsync_flags`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_flags: HashMap<String, String>,
+    /// Destinations for specific source directories, by name, e.g.
+    /// `{"old_team": "archive/old_team"}`; relative paths are under
+    /// `dest_path`. Unlisted directories go to `dest_path/<name>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub dest_map: HashMap<String, PathBuf>,
+    /// Only migrate top-level directories whose name matches one of these
+    /// globs, e.g. `["proj-*"]`; empty means every directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_patterns: Vec<String>,
+    /// Also migrate regular files directly under `source_path`, each as a
+    /// unit of its own; off means only directories
+    #[serde(default)]
+    pub include_top_level_files: bool,
+    /// Top-level directories named with `migrate --only`; when set, nothing
+    /// else is migrated. Never saved to the config file
+    #[serde(skip)]
+    pub only_directories: Vec<String>,
+    /// Plan written by `freight plan`, replayed by `migrate --plan` instead
+    /// of discovering directories. Never saved to the config file
+    #[serde(skip)]
+    pub plan_path: Option<PathBuf>,
+    /// One-off destination from `migrate --dest-override`, used instead of
+    /// `dest_path` for this run only. Never saved to the config file
+    #[serde(skip)]
+    pub dest_override: Option<PathBuf>,
+    /// `migrate --force`: migrate directories even when their manifest shows
+    /// no change since the last migration. Never saved to the config file
+    #[serde(skip)]
+    pub force: bool,
+    #[serde(default)]
+    pub bandwidth_limit: Option<String>,
+    /// Niceness for spawned tools, -20 (highest priority) to 19
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_nice: Option<i32>,
+    /// I/O scheduling class for spawned tools (Linux): `idle`,
+    /// `best-effort` or `realtime`, optionally with a level, e.g. `best-effort:7`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_class: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_path: Option<PathBuf>,
+    #[serde(default)]
+    pub report_format: ReportFormat,
+    /// Format of dashboard exports (`e`) under `.freight/exports/`
+    #[serde(default)]
+    pub export_format: ExportFormat,
     pub retry_attempts: u32,
+    /// How the wait between attempts grows: `fixed`, `linear` or `exponential`
+    #[serde(default)]
+    pub retry_backoff: RetryBackoff,
+    /// Seconds to wait after the first failed attempt
+    #[serde(default = "default_retry_base_delay")]
+    pub retry_base_delay: u64,
+    /// Upper bound in seconds on any single wait
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_max_delay: Option<u64>,
+    /// Randomize each wait to between half and all of it, so workers that
+    /// failed together don't retry together
+    #[serde(default)]
+    pub retry_jitter: bool,
+    /// Fail a worker that goes this many seconds without progress or a STOP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_timeout: Option<u64>,
+    /// Order in which scanned directories are migrated
+    #[serde(default)]
+    pub schedule_policy: SchedulePolicy,
+    /// Start a directory's migrate as soon as its scan finishes, ahead of
+    /// scans still queued, instead of after them
+    #[serde(default)]
+    pub phase_overlap: bool,
+    /// Spread worker spawns over this many milliseconds, so a full set of
+    /// `parallel_workers` doesn't hit the NFS server in the same instant
+    #[serde(default)]
+    pub startup_stagger_ms: u64,
+    /// What to do when a directory's destination already has data
+    #[serde(default)]
+    pub existing_policy: ExistingPolicy,
+    /// Keep partly copied files so an interrupted migrate resumes them
+    /// instead of starting over
+    #[serde(default = "default_resume_partial")]
+    pub resume_partial: bool,
+    /// Concurrent rsyncs per migrate worker, each copying a size-balanced
+    /// share of the directory's top-level entries; rsync backend only
+    #[serde(default = "default_intra_dir_parallelism")]
+    pub intra_dir_parallelism: u32,
+    /// `md5`, `sha1` or `xxhash`, for rsync's `--checksum-choice` and
+    /// freight-verify; unset leaves both to their defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
+    /// Octal mode for each destination directory, e.g. "0750"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dest_mode: Option<String>,
+    /// Owner for each destination directory: `user`, `user:group` or
+    /// `:group`, by name or numeric id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dest_owner: Option<String>,
+    /// Copy tool for the migrate phase: `rsync`, `tar` or `cp`
+    #[serde(default)]
+    pub backend: MigrationBackend,
+    /// Extra ssh arguments for a remote `dest_path` (`[user@]host:/path`),
+    /// e.g. `-i ~/.ssh/migrate -p 2222`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_options: Option<String>,
     pub socket_retry_interval: u64,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default = "default_tcp_address")]
+    pub tcp_address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<Secret>,
+    /// Octal mode for the Unix socket, e.g. "0600" or "0660"
+    #[serde(default = "default_socket_mode")]
+    pub socket_mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_group: Option<String>,
+    /// `path` for a socket file, `abstract` for the Linux abstract namespace
+    #[serde(default)]
+    pub socket_address_mode: SocketAddressMode,
+    /// Messages a lagging dashboard or the migration loop can fall behind
+    /// before some are dropped
+    #[serde(default = "default_broadcast_capacity")]
+    pub broadcast_capacity: usize,
+    /// Longest message line, in bytes, the daemon accepts before dropping
+    /// the connection
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+    /// Bytes of each tool's stderr the daemon keeps for its failure
+    /// message; the rest is only streamed to the log
+    #[serde(default = "default_max_captured_output")]
+    pub max_captured_output: usize,
+    /// Messages per second one connection may send; unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages_per_sec: Option<u32>,
+    /// Drop connections that send lines that aren't valid UTF-8, instead
+    /// of replacing the bad bytes
+    #[serde(default)]
+    pub strict_utf8: bool,
+    /// Minimum gap between a tool's PROGRESS messages; bursts in between
+    /// are coalesced into the latest one. 0 sends every update
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u64,
+    /// How long SIGTERM waits for running workers before giving up on them
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+    /// How long finished workers stay on the board: `"keep"` or
+    /// `{"remove_after": <secs>}`
+    #[serde(default)]
+    pub worker_retention: WorkerRetention,
+    /// Shell command run after each directory migrates successfully
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_complete_hook: Option<String>,
+    /// Shell command run when any worker fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure_hook: Option<String>,
+    /// URL that receives a JSON POST when a directory fails or the migration completes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Port for the Prometheus `/metrics` endpoint; disabled when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_port: Option<u16>,
+    /// Also log to this file, rotated daily; relative to `.freight/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<PathBuf>,
+    /// Record every worker run in this SQLite database, for `freight
+    /// history`; relative to `.freight/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_db: Option<PathBuf>,
+    /// One of error, warn, info, debug, trace
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    /// `pretty` (default) or `json`
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// How often the dashboard checks for input and updates, in milliseconds
+    #[serde(default = "default_tui_tick_rate_ms")]
+    pub tui_tick_rate_ms: u64,
+    /// Failed attempts in a row before the dashboard stops reconnecting to
+    /// the daemon; unset retries forever, every `socket_retry_interval`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tui_reconnect_limit: Option<u32>,
+    /// Ask before `q` quits the dashboard while workers are running
+    #[serde(default = "default_tui_confirm_quit")]
+    pub tui_confirm_quit: bool,
+    /// Seconds without any daemon message, while workers are running,
+    /// before the dashboard flags its numbers as stale; 0 never does
+    #[serde(default = "default_tui_stale_after_secs")]
+    pub tui_stale_after_secs: u64,
+    /// Most daemon updates the dashboard applies between two redraws; a
+    /// burst beyond it is spread over the following frames
+    #[serde(default = "default_tui_max_updates_per_frame")]
+    pub tui_max_updates_per_frame: usize,
+    /// How the dashboard names a worker's directory: `{name}` is its last
+    /// path component, `{parent}` the one above and `{path}` the whole path
+    #[serde(default = "default_tui_name_template")]
+    pub tui_name_template: String,
+    /// Dashboard colors: `dark`, `light` or `mono`
+    #[serde(default)]
+    pub theme: ThemeName,
+}
+
+fn default_tcp_address() -> String {
+    DEFAULT_TCP_ADDRESS.to_string()
+}
+
+fn default_socket_mode() -> String {
+    "0600".to_string()
+}
+
+fn default_broadcast_capacity() -> usize {
+    DEFAULT_BROADCAST_CAPACITY
+}
+
+fn default_max_line_length() -> usize {
+    DEFAULT_MAX_LINE_LENGTH
+}
+
+fn default_max_captured_output() -> usize {
+    DEFAULT_MAX_CAPTURED_OUTPUT
+}
+
+fn default_retry_base_delay() -> u64 {
+    5
+}
+
+fn default_progress_interval_ms() -> u64 {
+    250
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    60
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_resume_partial() -> bool {
+    true
+}
+
+fn default_intra_dir_parallelism() -> u32 {
+    1
+}
+
+fn default_tui_tick_rate_ms() -> u64 {
+    250
+}
+
+fn default_tui_confirm_quit() -> bool {
+    true
+}
+
+fn default_tui_stale_after_secs() -> u64 {
+    60
+}
+
+fn default_tui_max_updates_per_frame() -> usize {
+    DEFAULT_MAX_UPDATES_PER_FRAME
+}
+
+fn default_tui_name_template() -> String {
+    DEFAULT_NAME_TEMPLATE.to_string()
+}
+
+/// Shared defaults for every project: `$XDG_CONFIG_HOME/freight/config.toml`,
+/// falling back to `~/.config/freight/config.toml`.
+pub fn global_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("freight/config.toml"))
+}
+
+/// The global config as JSON-shaped values, ready to merge, or `None` when
+/// there isn't one.
+fn load_global_config() -> Result<Option<serde_json::Value>> {
+    let Some(path) = global_config_path() else {
+        return Ok(None);
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read config from {}", path.display()))
+        }
+    };
+    
+    let global: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+    serde_json::to_value(global)
+        .map(Some)
+        .with_context(|| format!("Failed to parse config from {}", path.display()))
+}
+
+/// The `[user@]host` of an rsync-style remote path like `user@host:/data`,
+/// or `None` for a local path. As with rsync, a colon before any slash
+/// makes the path remote.
+pub fn remote_host(path: &Path) -> Option<&str> {
+    let (host, _) = path.to_str()?.split_once(':')?;
+    (!host.is_empty() && !host.contains('/')).then_some(host)
+}
+
+/// Overlay `overlay` onto `base` key by key, descending into tables, so
+/// anything `overlay` leaves out (or sets to null) keeps the base value.
+pub fn merge_config(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config(existing, value),
+                    None if !value.is_null() => {
+                        base.insert(key, value);
+                    }
+                    None => {}
+                }
+            }
+        }
+        (_, serde_json::Value::Null) => {}
+        (base, overlay) => *base = overlay,
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,20 +383,47 @@ impl Config {
         if config_path.exists() {
             Self::load(&config_path)
         } else {
-            let config = Self::default_with_paths(source, dest);
+            let config = Self::new_project(source, dest)?;
             config.save(&config_path)?;
             Ok(config)
         }
     }
     
+    /// Load a project config, filling in anything it doesn't set from the
+    /// global config.
     pub fn load(path: &Path) -> Result<Self> {
+        Self::load_merged(path).map_err(|e| ConfigError(e).into())
+    }
+    
+    fn load_merged(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read config from {}", path.display()))?;
+        let project: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+        
+        let mut merged = load_global_config()?.unwrap_or(serde_json::Value::Null);
+        merge_config(&mut merged, project);
         
-        serde_json::from_str(&content)
+        serde_json::from_value(merged)
             .with_context(|| format!("Failed to parse config from {}", path.display()))
     }
     
+    /// Defaults for a new project, with the global config applied on top.
+    pub fn new_project(source: &str, dest: &str) -> Result<Self> {
+        let mut config = serde_json::to_value(Self::default_with_paths(source, dest))
+            .context("Failed to serialize config")?;
+        if let Some(global) = load_global_config()? {
+            merge_config(&mut config, global);
+        }
+        
+        let mut config: Self = serde_json::from_value(config)
+            .context("Failed to apply global config")?;
+        // The paths are always the project's own
+        config.source_path = PathBuf::from(source);
+        config.dest_path = PathBuf::from(dest);
+        Ok(config)
+    }
+    
     pub fn save(&self, path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
@@ -50,11 +433,11 @@ impl Config {
         let content = serde_json::to_string_pretty(self)
             .context("Failed to serialize config")?;
         
-        fs::write(path, content)
+        write_atomic(path, content.as_bytes())
             .with_context(|| format!("Failed to write config to {}", path.display()))
     }
     
-    fn default_with_paths(source: &str, dest: &str) -> Self {
+    pub fn default_with_paths(source: &str, dest: &str) -> Self {
         Self {
             source_path: PathBuf::from(source),
             dest_path: PathBuf::from(dest),
@@ -63,17 +446,493 @@ impl Config {
                 parallel_workers: 5,
             },
             rsync_flags: "-avxHAX --numeric-ids --compress".to_string(),
+            tool_flags: HashMap::new(),
+            dest_map: HashMap::new(),
+            include_patterns: Vec::new(),
+            include_top_level_files: false,
+            only_directories: Vec::new(),
+            plan_path: None,
+            dest_override: None,
+            force: false,
+            bandwidth_limit: None,
+            cpu_nice: None,
+            io_class: None,
+            dry_run: false,
+            report_path: None,
+            report_format: ReportFormat::default(),
+            export_format: ExportFormat::default(),
             retry_attempts: 3,
+            retry_backoff: RetryBackoff::default(),
+            retry_base_delay: default_retry_base_delay(),
+            retry_max_delay: None,
+            retry_jitter: false,
+            worker_timeout: None,
+            schedule_policy: SchedulePolicy::default(),
+            phase_overlap: false,
+            startup_stagger_ms: 0,
+            existing_policy: ExistingPolicy::default(),
+            resume_partial: default_resume_partial(),
+            intra_dir_parallelism: default_intra_dir_parallelism(),
+            checksum_algorithm: None,
+            dest_mode: None,
+            dest_owner: None,
+            backend: MigrationBackend::default(),
+            ssh_options: None,
             socket_retry_interval: 10,
+            transport: Transport::default(),
+            tcp_address: default_tcp_address(),
+            auth_token: None,
+            socket_mode: default_socket_mode(),
+            socket_group: None,
+            socket_address_mode: SocketAddressMode::default(),
+            broadcast_capacity: default_broadcast_capacity(),
+            max_line_length: default_max_line_length(),
+            max_captured_output: default_max_captured_output(),
+            strict_utf8: false,
+            max_messages_per_sec: None,
+            progress_interval_ms: default_progress_interval_ms(),
+            drain_timeout_secs: default_drain_timeout_secs(),
+            worker_retention: WorkerRetention::default(),
+            on_complete_hook: None,
+            on_failure_hook: None,
+            webhook_url: None,
+            metrics_port: None,
+            log_file: None,
+            history_db: None,
+            log_level: default_log_level(),
+            log_format: LogFormat::default(),
+            tui_tick_rate_ms: default_tui_tick_rate_ms(),
+            tui_reconnect_limit: None,
+            tui_confirm_quit: default_tui_confirm_quit(),
+            tui_stale_after_secs: default_tui_stale_after_secs(),
+            tui_max_updates_per_frame: default_tui_max_updates_per_frame(),
+            tui_name_template: default_tui_name_template(),
+            theme: ThemeName::default(),
+        }
+    }
+    
+    /// Check the config for values that would only fail once workers start.
+    pub fn validate(&self) -> Result<()> {
+        self.check_fields().map_err(|e| ConfigError(e).into())
+    }
+    
+    fn check_fields(&self) -> Result<()> {
+        if self.thresholds.parallel_workers == 0 {
+            anyhow::bail!("thresholds.parallel_workers must be at least 1");
+        }
+        
+        if self.broadcast_capacity == 0 {
+            anyhow::bail!("broadcast_capacity must be at least 1");
+        }
+        
+        if self.max_line_length < 1024 {
+            anyhow::bail!("max_line_length must be at least 1024 bytes");
+        }
+        
+        if self.max_captured_output < 1024 {
+            anyhow::bail!("max_captured_output must be at least 1024 bytes");
         }
+        
+        if self.max_messages_per_sec == Some(0) {
+            anyhow::bail!("max_messages_per_sec must be at least 1");
+        }
+        
+        if self.retry_attempts == 0 {
+            anyhow::bail!("retry_attempts must be at least 1");
+        }
+        
+        if self.intra_dir_parallelism == 0 {
+            anyhow::bail!("intra_dir_parallelism must be at least 1");
+        }
+        
+        if self.worker_timeout == Some(0) {
+            anyhow::bail!("worker_timeout must be at least 1 second");
+        }
+        
+        if self.rsync_flags.trim().is_empty() {
+            anyhow::bail!("rsync_flags must not be empty");
+        }
+        
+        // Only rsync knows how to leave existing files alone
+        if self.backend != MigrationBackend::Rsync
+            && self.existing_policy == ExistingPolicy::SkipExisting
+        {
+            anyhow::bail!(
+                "existing_policy skip_existing needs the rsync backend, not {}",
+                self.backend.as_str()
+            );
+        }
+        
+        if let Some(algorithm) = self.checksum_algorithm {
+            if self.backend == MigrationBackend::Rsync && algorithm.rsync_choice().is_none() {
+                anyhow::bail!(
+                    "checksum_algorithm {} isn't supported by rsync's --checksum-choice; \
+                     use md5 or xxhash, or another backend",
+                    algorithm.as_str()
+                );
+            }
+        }
+        
+        for (name, dest) in &self.dest_map {
+            if dest.as_os_str().is_empty() {
+                anyhow::bail!("dest_map.{} must not be empty", name);
+            }
+        }
+        
+        for (tool, flags) in &self.tool_flags {
+            if flags.trim().is_empty() {
+                anyhow::bail!("tool_flags.{} must not be empty", tool);
+            }
+        }
+        
+        parse_size(&self.thresholds.large_directory_size)
+            .context("Invalid thresholds.large_directory_size")?;
+        self.bwlimit_kbps().context("Invalid bandwidth_limit")?;
+        self.io_priority().context("Invalid io_class")?;
+        self.include_set()?;
+        
+        for name in &self.only_directories {
+            let is_top_level = matches!(
+                Path::new(name).components().collect::<Vec<_>>().as_slice(),
+                [std::path::Component::Normal(_)]
+            );
+            let path = self.source_path.join(name);
+            let is_unit = path.is_dir() || (self.include_top_level_files && path.is_file());
+            if !is_top_level || !is_unit {
+                anyhow::bail!(
+                    "--only {}: no such directory directly under {}",
+                    name,
+                    self.source_path.display()
+                );
+            }
+        }
+        
+        if let Some(dest) = &self.dest_override {
+            if !dest.is_absolute() {
+                anyhow::bail!("--dest-override {} must be an absolute path", dest.display());
+            }
+            if dest.exists() && !dest.is_dir() {
+                anyhow::bail!("--dest-override {} is not a directory", dest.display());
+            }
+            if !dest.exists() && !dest.parent().is_some_and(Path::is_dir) {
+                anyhow::bail!(
+                    "--dest-override {}: parent directory does not exist",
+                    dest.display()
+                );
+            }
+        }
+        
+        if self.dest_is_remote() {
+            self.check_remote_dest()?;
+        } else {
+            self.check_overlap()?;
+        }
+        
+        if let Some(nice) = self.cpu_nice {
+            if !(-20..=19).contains(&nice) {
+                anyhow::bail!("cpu_nice must be between -20 and 19, got {}", nice);
+            }
+        }
+        self.socket_permissions()?;
+        self.dest_permissions()?;
+        self.log_level_filter()?;
+        
+        if self.tui_tick_rate_ms < 10 {
+            anyhow::bail!("tui_tick_rate_ms must be at least 10");
+        }
+        if self.tui_max_updates_per_frame == 0 {
+            anyhow::bail!("tui_max_updates_per_frame must be at least 1");
+        }
+        if self.tui_name_template.trim().is_empty() {
+            anyhow::bail!("tui_name_template must not be empty");
+        }
+        
+        Ok(())
+    }
+    
+    pub fn connection_limits(&self) -> ConnectionLimits {
+        ConnectionLimits {
+            max_line_length: self.max_line_length,
+            max_messages_per_sec: self.max_messages_per_sec,
+            strict_utf8: self.strict_utf8,
+        }
+    }
+    
+    /// Resolve `dest_mode` and `dest_owner`; users and groups are looked up
+    /// now so a typo fails before anything migrates.
+    pub fn dest_permissions(&self) -> Result<DestPermissions> {
+        let mode = match &self.dest_mode {
+            Some(dest_mode) => {
+                let mode = u32::from_str_radix(dest_mode.trim_start_matches("0o"), 8)
+                    .ok()
+                    .filter(|mode| *mode <= 0o7777)
+                    .with_context(|| format!("Invalid dest_mode: {}", dest_mode))?;
+                // The copy has to be able to write into it
+                if mode & 0o700 != 0o700 {
+                    anyhow::bail!("dest_mode {} must give the owner full access", dest_mode);
+                }
+                Some(mode)
+            }
+            None => None,
+        };
+        
+        let (uid, gid) = match self.dest_owner.as_deref() {
+            Some(owner) => {
+                let (user, group) = owner.split_once(':').unwrap_or((owner, ""));
+                if user.is_empty() && group.is_empty() {
+                    anyhow::bail!("dest_owner must name a user, a group or both");
+                }
+                (
+                    (!user.is_empty()).then(|| lookup_user_id(user)).transpose()?,
+                    (!group.is_empty()).then(|| lookup_group_id(group)).transpose()?,
+                )
+            }
+            None => (None, None),
+        };
+        
+        Ok(DestPermissions { mode, uid, gid })
+    }
+    
+    pub fn socket_permissions(&self) -> Result<SocketPermissions> {
+        let mode = u32::from_str_radix(self.socket_mode.trim_start_matches("0o"), 8)
+            .with_context(|| format!("Invalid socket_mode: {}", self.socket_mode))?;
+        
+        if mode & 0o007 != 0 {
+            anyhow::bail!("socket_mode {} must not grant access to other users", self.socket_mode);
+        }
+        if mode & 0o600 != 0o600 {
+            anyhow::bail!("socket_mode {} must allow the owner to read and write", self.socket_mode);
+        }
+        
+        Ok(SocketPermissions {
+            mode,
+            group: self.socket_group.clone(),
+        })
+    }
+    
+    /// The rsync flags `tool` should run with.
+    pub fn rsync_flags_for(&self, tool: &str) -> &str {
+        self.tool_flags
+            .get(tool)
+            .map_or(self.rsync_flags.as_str(), String::as_str)
+    }
+    
+    /// The compiled `include_patterns`, or `None` to include everything.
+    pub fn include_set(&self) -> Result<Option<GlobSet>> {
+        if self.include_patterns.is_empty() {
+            return Ok(None);
+        }
+        
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.include_patterns {
+            let glob = Glob::new(pattern)
+                .with_context(|| format!("Invalid include_patterns entry: {}", pattern))?;
+            builder.add(glob);
+        }
+        builder
+            .build()
+            .map(Some)
+            .context("Invalid include_patterns")
+    }
+    
+    /// What a remote destination can't do: anything freight would have to
+    /// do to the destination itself rather than through rsync.
+    fn check_remote_dest(&self) -> Result<()> {
+        if self.backend != MigrationBackend::Rsync {
+            anyhow::bail!(
+                "remote dest_path {} needs the rsync backend, not {}",
+                self.dest_path.display(),
+                self.backend.as_str()
+            );
+        }
+        if self.dest_mode.is_some() || self.dest_owner.is_some() {
+            anyhow::bail!("dest_mode and dest_owner can't be applied to a remote dest_path");
+        }
+        if self.existing_policy == ExistingPolicy::ErrorIfPresent {
+            anyhow::bail!(
+                "existing_policy error_if_present can't check a remote dest_path; \
+                 use skip_existing or overwrite"
+            );
+        }
+        Ok(())
+    }
+    
+    /// Refuse a destination inside the source or the other way round, where
+    /// rsync would copy its own output. Symlinks and `..` are resolved
+    /// first, as far as the paths exist.
+    fn check_overlap(&self) -> Result<()> {
+        let source = resolve_path(&self.source_path);
+        let dest = resolve_path(self.dest_root());
+        
+        let relation = if source == dest {
+            "is the same directory as"
+        } else if dest.starts_with(&source) {
+            "is inside"
+        } else if source.starts_with(&dest) {
+            "contains"
+        } else {
+            return Ok(());
+        };
+        anyhow::bail!(
+            "destination {} {} source {}; rsync would copy its own output",
+            dest.display(),
+            relation,
+            source.display()
+        )
+    }
+    
+    /// The destination root for this run: `--dest-override` if given,
+    /// otherwise `dest_path`.
+    pub fn dest_root(&self) -> &Path {
+        self.dest_override.as_deref().unwrap_or(&self.dest_path)
+    }
+    
+    /// Whether the destination is on another host, reached over ssh.
+    pub fn dest_is_remote(&self) -> bool {
+        remote_host(self.dest_root()).is_some()
+    }
+    
+    /// Where `directory` should be migrated to.
+    pub fn dest_dir_for(&self, directory: &Path) -> PathBuf {
+        let name = directory.file_name().unwrap_or(directory.as_os_str());
+        let root = self.dest_root();
+        match name.to_str().and_then(|name| self.dest_map.get(name)) {
+            // An absolute mapping stays on the remote host
+            Some(mapped) if mapped.is_absolute() => match remote_host(root) {
+                Some(host) => PathBuf::from(format!("{}:{}", host, mapped.display())),
+                None => mapped.clone(),
+            },
+            Some(mapped) => root.join(mapped),
+            None => root.join(name),
+        }
+    }
+    
+    /// Bandwidth cap in rsync `--bwlimit` units (KB/s), if one is configured.
+    pub fn bwlimit_kbps(&self) -> Result<Option<u64>> {
+        self.bandwidth_limit
+            .as_deref()
+            .map(parse_bandwidth_limit)
+            .transpose()
+    }
+    
+    /// The `ioprio_set` value for `io_class`, if one is configured.
+    pub fn io_priority(&self) -> Result<Option<i32>> {
+        self.io_class.as_deref().map(parse_io_class).transpose()
+    }
+    
+    pub fn log_level_filter(&self) -> Result<LevelFilter> {
+        LevelFilter::from_str(&self.log_level)
+            .with_context(|| format!("Invalid log_level: {}", self.log_level))
+    }
+    
+    pub fn tui_tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tui_tick_rate_ms)
+    }
+    
+    /// Seconds freight-migrate waits after each failed attempt but the last.
+    pub fn retry_delays(&self) -> Vec<u64> {
+        (1..self.retry_attempts)
+            .map(|attempt| {
+                let mut delay = self.retry_backoff.delay_secs(self.retry_base_delay, attempt);
+                if let Some(max) = self.retry_max_delay {
+                    delay = delay.min(max);
+                }
+                if self.retry_jitter && delay > 0 {
+                    let half = delay / 2;
+                    let random = uuid::Uuid::new_v4().as_u128() as u64;
+                    delay = delay - half + random % (half + 1);
+                }
+                delay
+            })
+            .collect()
+    }
+    
+    /// How long the dashboard waits for news before flagging stale data.
+    pub fn tui_stale_after(&self) -> Option<Duration> {
+        (self.tui_stale_after_secs > 0).then(|| Duration::from_secs(self.tui_stale_after_secs))
+    }
+    
+    pub fn resolved_log_file(&self) -> Option<PathBuf> {
+        self.log_file.as_ref().map(|path| self.freight_dir().join(path))
+    }
+    
+    pub fn resolved_history_db(&self) -> Option<PathBuf> {
+        self.history_db.as_ref().map(|path| self.freight_dir().join(path))
+    }
+    
+    pub fn listen_addr(&self) -> ListenAddr {
+        match self.transport {
+            Transport::Unix if self.socket_address_mode == SocketAddressMode::Abstract
+                && cfg!(target_os = "linux") =>
+            {
+                ListenAddr::Abstract(ABSTRACT_SOCKET_NAME.to_string())
+            }
+            Transport::Unix => ListenAddr::Unix(PathBuf::from(SOCKET_PATH)),
+            Transport::Tcp => ListenAddr::Tcp(self.tcp_address.clone()),
+        }
+    }
+    
+    /// The configured token, falling back to `FREIGHT_AUTH_TOKEN`.
+    pub fn resolved_auth_token(&self) -> Option<String> {
+        self.auth_token
+            .clone()
+            .map(|token| token.0)
+            .filter(|token| !token.is_empty())
+            .or_else(auth_token_from_env)
     }
     
     pub fn freight_dir(&self) -> PathBuf {
         self.source_path.join(".freight")
     }
     
+    pub fn config_path(&self) -> PathBuf {
+        self.freight_dir().join("config.json")
+    }
+    
+    /// Whether `source` already holds a freight project config.
+    pub fn is_initialized(source: &Path) -> bool {
+        source.join(".freight/config.json").exists()
+    }
+    
     pub fn init_project(source_path: &str) -> Result<()> {
+        // Always create config.json with placeholder destination
+        let config = Self::new_project(source_path, "/path/to/destination")?;
+        Self::init_project_with(source_path, &config)
+    }
+    
+    /// Make sure `source` can hold a project before anything is written,
+    /// so a bad path fails with a clear message instead of a raw I/O error.
+    pub fn check_init_target(source: &Path) -> Result<()> {
+        let metadata = match fs::metadata(source) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                anyhow::bail!("cannot initialize: {} does not exist", source.display());
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("cannot initialize: failed to read {}", source.display()));
+            }
+        };
+        if !metadata.is_dir() {
+            anyhow::bail!("cannot initialize: {} is not a directory", source.display());
+        }
+        
+        // Permission bits lie on NFS with root squash, so actually try a write
+        let probe = source.join(format!(".freight-init-{}", std::process::id()));
+        match fs::write(&probe, "") {
+            Ok(()) => {
+                let _ = fs::remove_file(&probe);
+                Ok(())
+            }
+            Err(e) => Err(e).with_context(|| {
+                format!("cannot initialize: {} is not a writable directory", source.display())
+            }),
+        }
+    }
+    
+    pub fn init_project_with(source_path: &str, config: &Config) -> Result<()> {
         let source = PathBuf::from(source_path);
+        Self::check_init_target(&source)?;
         let freight_dir = source.join(".freight");
         
         // Create .freight directory
@@ -85,11 +944,437 @@ impl Config {
         fs::write(&freight_root, "")
             .with_context(|| format!("Failed to create .freight-root marker at {}", freight_root.display()))?;
         
-        // Always create config.json with placeholder destination
-        let config = Self::default_with_paths(source_path, "/path/to/destination");
         let config_path = freight_dir.join("config.json");
         config.save(&config_path)?;
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Canonicalize the longest existing prefix of `path` and append the rest,
+/// so destinations that don't exist yet can still be compared.
+fn resolve_path(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return rest.iter().rev().fold(resolved, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// A config that can't be read, parsed or validated. Displays the same as
+/// the error it wraps; the type is what `exit::code_for` looks for.
+#[derive(Debug)]
+pub struct ConfigError(pub anyhow::Error);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Replace `path` with `content` without ever leaving it half-written: the
+/// data goes to a temp file in the same directory, is synced, and is then
+/// renamed over the original. A failure at any point leaves the old file
+/// as it was.
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+    
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".tmp.{}", std::process::id()));
+    let temp_path = dir.join(temp_name);
+    
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+    
+    // Persist the rename itself; not every filesystem allows syncing a
+    // directory, and the data is already safe either way
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Parse a bandwidth string such as "50MB" or "512KB" into KB/s for rsync's
+/// `--bwlimit`. A bare number is taken as KB/s, matching rsync itself.
+pub fn parse_bandwidth_limit(limit: &str) -> Result<u64> {
+    let limit = limit.trim();
+    let split = limit
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(limit.len());
+    let (number, unit) = limit.split_at(split);
+    
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid bandwidth limit: {}", limit))?;
+    
+    let kbps = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "K" | "KB" => Some(number),
+        "B" => Some(number.div_ceil(1024)),
+        "M" | "MB" => number.checked_mul(1024),
+        "G" | "GB" => number.checked_mul(1024 * 1024),
+        other => anyhow::bail!("Unknown bandwidth unit '{}' in {}", other, limit),
+    };
+    
+    kbps.with_context(|| format!("Bandwidth limit too large: {}", limit))
+}
+
+/// Parse an I/O class such as "idle" or "best-effort:7" into the value
+/// `ioprio_set` takes: the class in the top bits, the level (0-7, lower is
+/// higher priority) below. Levels default to 4, as in ionice.
+pub fn parse_io_class(io_class: &str) -> Result<i32> {
+    const IOPRIO_CLASS_SHIFT: i32 = 13;
+    
+    let (class, level) = match io_class.trim().split_once(':') {
+        Some((class, level)) => {
+            let level: i32 = level
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid I/O priority level in {}", io_class))?;
+            (class.trim(), Some(level))
+        }
+        None => (io_class.trim(), None),
+    };
+    
+    let class_value = match class {
+        "realtime" => 1,
+        "best-effort" => 2,
+        "idle" => 3,
+        other => anyhow::bail!(
+            "Unknown I/O class '{}'; expected idle, best-effort or realtime",
+            other
+        ),
+    };
+    let level = match (class_value, level) {
+        // The idle class has no levels
+        (3, Some(_)) => anyhow::bail!("The idle I/O class takes no level: {}", io_class),
+        (3, None) => 0,
+        (_, Some(level)) if !(0..=7).contains(&level) => {
+            anyhow::bail!("I/O priority level must be between 0 and 7: {}", io_class)
+        }
+        (_, level) => level.unwrap_or(4),
+    };
+    
+    Ok((class_value << IOPRIO_CLASS_SHIFT) | level)
+}
+
+/// Parse a size string such as "3GB" into bytes. A bare number is bytes,
+/// matching `parse_size_to_bytes` in the bash tools.
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid size: {}", size))?;
+    
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        "T" | "TB" => 1024 * 1024 * 1024 * 1024,
+        other => anyhow::bail!("Unknown size unit '{}' in {}", other, size),
+    };
+    
+    number
+        .checked_mul(multiplier)
+        .with_context(|| format!("Size too large: {}", size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bandwidth_limits_translate_to_kbps() {
+        for (limit, kbps) in [
+            ("512", 512),
+            ("512KB", 512),
+            ("512k", 512),
+            ("50MB", 50 * 1024),
+            (" 2 G ", 2 * 1024 * 1024),
+            ("1B", 1),
+            ("1025B", 2),
+        ] {
+            assert_eq!(parse_bandwidth_limit(limit).unwrap(), kbps, "{}", limit);
+        }
+    }
+
+    #[test]
+    fn bad_bandwidth_limits_are_rejected() {
+        for limit in ["", "fast", "10XB", "-5MB", &format!("{}GB", u64::MAX)] {
+            assert!(parse_bandwidth_limit(limit).is_err(), "{}", limit);
+        }
+        let mut config = Config::default_with_paths("/src", "/dst");
+        assert_eq!(config.bwlimit_kbps().unwrap(), None);
+        config.bandwidth_limit = Some("10XB".to_string());
+        let error = config.validate().unwrap_err();
+        assert!(format!("{:#}", error).contains("Invalid bandwidth_limit"), "{:#}", error);
+    }
+
+    #[test]
+    fn debug_output_redacts_the_auth_token() {
+        let mut config = Config::default_with_paths("/src", "/dest");
+        config.auth_token = Some(Secret("hunter2".to_string()));
+        
+        let debug = format!("{:?}", config);
+        
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains("auth_token: Some(\"<redacted>\")"));
+        assert_eq!(config.resolved_auth_token().as_deref(), Some("hunter2"));
+    }
+    
+    #[test]
+    fn auth_token_serializes_as_a_plain_string() {
+        let mut config = Config::default_with_paths("/src", "/dest");
+        config.auth_token = Some(Secret("hunter2".to_string()));
+        
+        let value = serde_json::to_value(&config).unwrap();
+        
+        assert_eq!(value["auth_token"], "hunter2");
+        let loaded: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(loaded.auth_token, Some(Secret("hunter2".to_string())));
+    }
+
+    #[test]
+    fn socket_mode_must_keep_others_out_and_the_owner_in() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        assert_eq!(config.socket_permissions().unwrap().mode, 0o600);
+        for (mode, parsed) in [("0660", 0o660), ("0o640", 0o640), ("600", 0o600)] {
+            config.socket_mode = mode.to_string();
+            assert_eq!(config.socket_permissions().unwrap().mode, parsed, "{}", mode);
+        }
+        for mode in ["0666", "0640x", "0400", "0066"] {
+            config.socket_mode = mode.to_string();
+            assert!(config.socket_permissions().is_err(), "{}", mode);
+        }
+    }
+
+    #[test]
+    fn tool_flags_override_rsync_flags_for_their_tool_only() {
+        let mut value = serde_json::to_value(Config::default_with_paths("/src", "/dst")).unwrap();
+        value["rsync_flags"] = "-a".into();
+        value["tool_flags"] = serde_json::json!({ "migrate": "-aHAX --delete" });
+        let config: Config = serde_json::from_value(value).unwrap();
+
+        assert_eq!(config.rsync_flags_for("migrate"), "-aHAX --delete");
+        assert_eq!(config.rsync_flags_for("scan"), "-a");
+
+        let mut config = Config::default_with_paths("/src", "/dst");
+        assert_eq!(config.rsync_flags_for("migrate"), config.rsync_flags);
+        config.tool_flags.insert("scan".to_string(), " ".to_string());
+        let error = config.validate().unwrap_err();
+        assert!(format!("{:#}", error).contains("tool_flags.scan"), "{:#}", error);
+    }
+
+    #[test]
+    fn abstract_address_mode_picks_the_abstract_name_on_linux() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        assert_eq!(config.listen_addr(), ListenAddr::Unix(PathBuf::from(SOCKET_PATH)));
+
+        config.socket_address_mode = SocketAddressMode::Abstract;
+        let expected = if cfg!(target_os = "linux") {
+            ListenAddr::Abstract(ABSTRACT_SOCKET_NAME.to_string())
+        } else {
+            ListenAddr::Unix(PathBuf::from(SOCKET_PATH))
+        };
+        assert_eq!(config.listen_addr(), expected);
+    }
+
+    #[test]
+    fn merge_keeps_base_values_the_overlay_leaves_out() {
+        let mut base = serde_json::json!({
+            "rsync_flags": "-aHAX",
+            "bandwidth_limit": "50MB",
+            "thresholds": {"large_directory_size": "10GB", "parallel_workers": 2},
+        });
+        let overlay = serde_json::json!({
+            "rsync_flags": "-a",
+            "bandwidth_limit": null,
+            "thresholds": {"parallel_workers": 8},
+            "retry_attempts": 5,
+        });
+
+        merge_config(&mut base, overlay);
+
+        assert_eq!(
+            base,
+            serde_json::json!({
+                "rsync_flags": "-a",
+                "bandwidth_limit": "50MB",
+                "thresholds": {"large_directory_size": "10GB", "parallel_workers": 8},
+                "retry_attempts": 5,
+            })
+        );
+    }
+
+    #[test]
+    fn merging_onto_nothing_takes_the_overlay() {
+        let mut base = serde_json::Value::Null;
+        merge_config(&mut base, serde_json::json!({"retry_attempts": 5}));
+        assert_eq!(base, serde_json::json!({"retry_attempts": 5}));
+    }
+
+    #[test]
+    fn dest_map_routes_mapped_directories_and_joins_the_rest() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.dest_map.insert("old_team".to_string(), PathBuf::from("archive/old_team"));
+        config.dest_map.insert("scratch".to_string(), PathBuf::from("/bulk/scratch"));
+
+        let dest = |config: &Config, name: &str| config.dest_dir_for(&Path::new("/src").join(name));
+        assert_eq!(dest(&config, "old_team"), Path::new("/dst/archive/old_team"));
+        assert_eq!(dest(&config, "scratch"), Path::new("/bulk/scratch"));
+        assert_eq!(dest(&config, "home"), Path::new("/dst/home"));
+
+        // An absolute mapping stays on a remote destination's host
+        config.dest_path = PathBuf::from("backup@nas:/vol");
+        assert_eq!(dest(&config, "scratch"), Path::new("backup@nas:/bulk/scratch"));
+        assert_eq!(dest(&config, "home"), Path::new("backup@nas:/vol/home"));
+    }
+
+    #[test]
+    fn failed_save_leaves_the_old_config_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let temp_path = dir.path().join(format!(".config.json.tmp.{}", std::process::id()));
+
+        // Something in the way of the temporary file makes the write fail
+        fs::create_dir(&temp_path).unwrap();
+        config.retry_attempts = 7;
+        assert!(config.save(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+        fs::remove_dir(&temp_path).unwrap();
+
+        // As does a half-written one left by a crash, until the next save
+        let retry_attempts = || {
+            serde_json::from_str::<Config>(&fs::read_to_string(&path).unwrap())
+                .unwrap()
+                .retry_attempts
+        };
+        fs::write(&temp_path, &saved[..saved.len() / 2]).unwrap();
+        assert_eq!(retry_attempts(), 3);
+        config.save(&path).unwrap();
+        assert_eq!(retry_attempts(), 7);
+        assert!(!temp_path.exists());
+    }
+
+    #[test]
+    fn init_refuses_paths_that_cannot_hold_a_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        let missing = dir.path().join("missing");
+        let init_error = |path: &Path| {
+            format!("{:#}", Config::init_project(path.to_str().unwrap()).unwrap_err())
+        };
+
+        let expected = format!("cannot initialize: {} does not exist", missing.display());
+        assert!(init_error(&missing).starts_with(&expected));
+        assert!(!missing.exists());
+        let expected = format!("cannot initialize: {} is not a directory", file.display());
+        assert!(init_error(&file).starts_with(&expected));
+        // Not even root can create files in /proc
+        #[cfg(target_os = "linux")]
+        assert!(init_error(Path::new("/proc"))
+            .starts_with("cannot initialize: /proc is not a writable directory"));
+
+        Config::init_project(dir.path().to_str().unwrap()).unwrap();
+        assert!(dir.path().join(".freight/config.json").exists());
+    }
+
+    #[test]
+    fn retry_delays_follow_the_backoff_strategy() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.retry_attempts = 6;
+        config.retry_base_delay = 5;
+        let delays = |backoff, max| {
+            let mut config = config.clone();
+            config.retry_backoff = backoff;
+            config.retry_max_delay = max;
+            config.retry_delays()
+        };
+
+        assert_eq!(delays(RetryBackoff::Fixed, None), [5, 5, 5, 5, 5]);
+        assert_eq!(delays(RetryBackoff::Linear, None), [5, 10, 15, 20, 25]);
+        assert_eq!(delays(RetryBackoff::Exponential, None), [5, 10, 20, 40, 80]);
+        assert_eq!(delays(RetryBackoff::Exponential, Some(30)), [5, 10, 20, 30, 30]);
+        assert_eq!(RetryBackoff::Exponential.delay_secs(5, 200), u64::MAX);
+
+        config.retry_attempts = 1;
+        assert!(config.retry_delays().is_empty());
+    }
+
+    #[test]
+    fn retry_jitter_stays_within_half_the_delay() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.retry_attempts = 4;
+        config.retry_base_delay = 10;
+        config.retry_backoff = RetryBackoff::Exponential;
+        config.retry_jitter = true;
+
+        for _ in 0..100 {
+            let delays = config.retry_delays();
+            for (delay, full) in delays.iter().zip([10, 20, 40]) {
+                assert!((full - full / 2..=full).contains(delay), "{} from {}", delay, full);
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_source_and_dest_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        fs::create_dir_all(source.join("inner")).unwrap();
+        let error = |dest: PathBuf| {
+            let mut config = Config::default_with_paths("/src", "/dst");
+            config.source_path = source.clone();
+            config.dest_path = dest;
+            config.validate().err().map(|e| format!("{:#}", e))
+        };
+
+        let nested = error(source.join("inner/dest")).unwrap();
+        assert!(nested.contains("is inside source"), "{}", nested);
+        assert!(nested.contains(&source.join("inner/dest").display().to_string()), "{}", nested);
+        // `..` doesn't hide the overlap
+        let identical = error(source.join("inner/..")).unwrap();
+        assert!(identical.contains("is the same directory as"), "{}", identical);
+        let parent = error(dir.path().to_path_buf()).unwrap();
+        assert!(parent.contains("contains source"), "{}", parent);
+        assert_eq!(error(dir.path().join("dest")), None);
+        // A shared name prefix is not nesting
+        assert_eq!(error(dir.path().join("source-copy")), None);
+    }
+}
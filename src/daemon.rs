@@ -1,20 +1,284 @@
-use crate::{Config, SocketServer, WorkerManager};
+use crate::history::HistoryDb;
+use crate::report::MigrationReport;
+use crate::worker::{check_rsync_flags, ControlMessage};
+use crate::{metrics, Config, SocketServer, WorkerManager, WorkerMessage};
+use crate::socket::{
+    self, auth_token_from_env, claim_socket_path, AlreadyRunning, ListenAddr, NoticeLevel,
+    DEFAULT_BROADCAST_CAPACITY,
+};
 use anyhow::Result;
-use tracing::{info, error};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn, error};
 use tokio::signal;
+use tokio::signal::unix::SignalKind;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 
-pub async fn start_daemon() -> Result<()> {
-    info!("Starting freight daemon");
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// How often finished workers are checked against `worker_retention`.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Consecutive socket server failures tolerated before the daemon gives up.
+const SOCKET_RESTART_LIMIT: u32 = 5;
+const SOCKET_RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+/// A server that stayed up this long before failing starts a fresh budget.
+const SOCKET_STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// Returns how many workers were restored.
+async fn restore_state(socket_server: &SocketServer, listen_addr: &ListenAddr) -> usize {
+    let state_path = socket::state_path(listen_addr);
+    if !state_path.exists() {
+        return 0;
+    }
     
-    let (socket_server, message_rx) = SocketServer::new();
+    match socket_server.load_state(&state_path).await {
+        Ok(count) => {
+            info!("Restored {} workers from {}", count, state_path.display());
+            count
+        }
+        Err(e) => {
+            warn!("Failed to restore worker state: {}", e);
+            0
+        }
+    }
+}
+
+/// What `freight daemon --foreground` prints once it is listening. `config`
+/// is the project config found in the working directory, if any.
+pub fn startup_banner(
+    listen_addr: &ListenAddr,
+    auth: bool,
+    restored: usize,
+    config: Option<&Config>,
+) -> String {
+    let transport = match listen_addr {
+        ListenAddr::Unix(_) => "Unix socket",
+        ListenAddr::Abstract(_) => "abstract Unix socket",
+        ListenAddr::Tcp(_) => "TCP",
+    };
+    let project = match config {
+        Some(config) => format!(
+            "\x20 Config        {}\n\
+             \x20 Workers       {} parallel\n\
+             \x20 Source        {}\n\
+             \x20 Destination   {}\n",
+            config.config_path().display(),
+            config.thresholds.parallel_workers,
+            config.source_path.display(),
+            config.dest_path.display(),
+        ),
+        None => "\x20 Config        none\n".to_string(),
+    };
+    format!(
+        "freight daemon {}\n\
+         \x20 Listening on  {} ({})\n\
+         \x20 Auth          {}\n\
+         \x20 State file    {} ({} workers restored)\n\
+         {}\
+         Ready for workers",
+        env!("CARGO_PKG_VERSION"),
+        listen_addr,
+        transport,
+        if auth { "token required" } else { "off" },
+        socket::state_path(listen_addr).display(),
+        restored,
+        project,
+    )
+}
+
+async fn persist_state(socket_server: &SocketServer, listen_addr: &ListenAddr) {
+    if let Err(e) = socket_server.save_state(&socket::state_path(listen_addr)).await {
+        error!("Failed to persist worker state: {}", e);
+    }
+}
+
+/// Claim the listen address up front. Only Unix sockets leave a file behind
+/// that a second daemon could steal; TCP conflicts surface at bind time.
+async fn claim_listen_addr(listen_addr: &ListenAddr) -> Result<()> {
+    match listen_addr {
+        ListenAddr::Unix(path) => claim_socket_path(path).await,
+        // Abstract names vanish with their owner, so a live listener is the
+        // only thing that can be in the way
+        ListenAddr::Abstract(_) => {
+            if socket::connect(listen_addr).await.is_ok() {
+                return Err(AlreadyRunning(listen_addr.to_string()).into());
+            }
+            Ok(())
+        }
+        ListenAddr::Tcp(_) => Ok(()),
+    }
+}
+
+fn release_listen_addr(listen_addr: &ListenAddr) {
+    if let ListenAddr::Unix(path) = listen_addr {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Resolves on Ctrl-C or SIGTERM, so `systemctl stop` takes the same
+/// graceful path as an interactive interrupt.
+async fn shutdown_signal() -> &'static str {
+    let mut sigterm = match signal::unix::signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(e) => {
+            warn!("Failed to install SIGTERM handler: {}", e);
+            let _ = signal::ctrl_c().await;
+            return "SIGINT";
+        }
+    };
+    
+    tokio::select! {
+        _ = signal::ctrl_c() => "SIGINT",
+        _ = sigterm.recv() => "SIGTERM",
+    }
+}
+
+/// The next SIGHUP, or never when `sighup` isn't being handled.
+async fn next_hangup(sighup: &mut Option<signal::unix::Signal>) {
+    match sighup {
+        Some(sighup) => {
+            sighup.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Re-read and validate the config file. A config that fails to load or
+/// validate, or whose rsync flags rsync rejects, is rejected so the daemon
+/// keeps running on the old one, and subscribers are told why.
+fn reload_config(path: &Path, socket_server: &SocketServer) -> Option<Config> {
+    let config = Config::load(path)
+        .and_then(|config| config.validate().map(|_| config))
+        .and_then(|config| check_rsync_flags(&config).map(|_| config));
+    match config {
+        Ok(config) => Some(config),
+        Err(e) => {
+            error!("Rejected config reload from {}: {:#}", path.display(), e);
+            socket_server.notify(
+                NoticeLevel::Error,
+                format!("Rejected config reload from {}: {:#}", path.display(), e),
+            );
+            None
+        }
+    }
+}
+
+/// Errors that restarting the socket server can't fix: another daemon owns
+/// the address.
+fn is_fatal_socket_error(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause.is::<AlreadyRunning>()
+            || cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|io| io.kind() == std::io::ErrorKind::AddrInUse)
+    })
+}
+
+/// Run the socket server, restarting it with exponential backoff after
+/// transient failures. Returns once it fails fatally or too many times in
+/// a row, which takes the daemon down with it.
+fn spawn_socket_server(server: Arc<SocketServer>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut failures = 0;
+        loop {
+            let started = Instant::now();
+            let Err(e) = server.start().await else { return };
+            
+            if is_fatal_socket_error(&e) {
+                error!("Socket server error: {:#}", e);
+                return;
+            }
+            
+            if started.elapsed() >= SOCKET_STABLE_RUN {
+                failures = 0;
+            }
+            failures += 1;
+            if failures >= SOCKET_RESTART_LIMIT {
+                error!("Socket server failed {} times in a row, giving up: {:#}", failures, e);
+                return;
+            }
+            
+            let delay = SOCKET_RESTART_BASE_DELAY * 2u32.pow(failures - 1);
+            warn!(
+                "Socket server failed ({:#}), restarting in {:?} ({}/{})",
+                e, delay, failures, SOCKET_RESTART_LIMIT
+            );
+            tokio::time::sleep(delay).await;
+        }
+    })
+}
+
+fn spawn_state_persister(
+    socket_server: Arc<SocketServer>,
+    listen_addr: ListenAddr,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(STATE_SAVE_INTERVAL);
+        // The first tick completes immediately; skip it so we don't clobber
+        // the state file before any worker has reported in.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            persist_state(&socket_server, &listen_addr).await;
+        }
+    })
+}
+
+fn spawn_pruner(socket_server: Arc<SocketServer>, grace: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let pruned = socket_server.prune_finished(grace).await;
+            if pruned > 0 {
+                info!("Pruned {} finished workers", pruned);
+            }
+        }
+    })
+}
+
+/// Run a daemon for standalone tools. With `banner`, print a summary to
+/// stdout once the socket is listening, including what `config` (the
+/// project config in the working directory, if any) would migrate.
+pub async fn start_daemon(
+    listen_addr: ListenAddr,
+    banner: bool,
+    config: Option<&Config>,
+) -> Result<()> {
+    info!("Starting freight daemon on {}", listen_addr);
+    
+    // Refuse to start before touching any shared state, so a second daemon
+    // can't clobber the first one's socket or state file on its way out
+    claim_listen_addr(&listen_addr).await?;
+    
+    let (mut socket_server, message_rx) = SocketServer::new(DEFAULT_BROADCAST_CAPACITY);
+    socket_server.set_listen_addr(listen_addr.clone());
+    let auth_token = auth_token_from_env();
+    let auth = auth_token.is_some();
+    socket_server.set_auth_token(auth_token);
+    let socket_server = Arc::new(socket_server);
     let worker_manager = WorkerManager::new();
     
+    let restored = restore_state(&socket_server, &listen_addr).await;
+    let persist_handle = spawn_state_persister(Arc::clone(&socket_server), listen_addr.clone());
+    
     // Start socket server
-    let socket_handle = tokio::spawn(async move {
-        if let Err(e) = socket_server.start().await {
-            error!("Socket server error: {}", e);
+    let mut socket_handle = spawn_socket_server(Arc::clone(&socket_server));
+    if banner {
+        tokio::select! {
+            _ = socket_server.wait_listening() => {
+                println!("{}", startup_banner(&listen_addr, auth, restored, config));
+            }
+            // Nothing of ours to clean up yet, and the address may well
+            // belong to another daemon
+            _ = &mut socket_handle => {
+                persist_handle.abort();
+                anyhow::bail!("Socket server failed to start on {}", listen_addr);
+            }
         }
-    });
+    }
     
     // Start worker manager
     let worker_handle = tokio::spawn(async move {
@@ -23,10 +287,10 @@ pub async fn start_daemon() -> Result<()> {
     
     // Wait for shutdown signal
     tokio::select! {
-        _ = signal::ctrl_c() => {
-            info!("Received shutdown signal");
+        signal_name = shutdown_signal() => {
+            info!("Received {}, shutting down", signal_name);
         }
-        _ = socket_handle => {
+        _ = &mut socket_handle => {
             error!("Socket server terminated unexpectedly");
         }
         _ = worker_handle => {
@@ -35,56 +299,233 @@ pub async fn start_daemon() -> Result<()> {
     }
     
     // Cleanup
-    let _ = std::fs::remove_file(crate::socket::SOCKET_PATH);
+    persist_handle.abort();
+    persist_state(&socket_server, &listen_addr).await;
+    release_listen_addr(&listen_addr);
     info!("Freight daemon stopped");
     
     Ok(())
 }
 
-pub async fn start_migration_daemon(config: Config) -> Result<()> {
+/// Run a migration with signal handling, returning its final report as
+/// `run_migration_daemon` does.
+pub async fn start_migration_daemon(config: Config) -> Result<Option<MigrationReport>> {
+    let message_tx = broadcast::channel(config.broadcast_capacity).0;
+    run_migration_daemon(config, message_tx, true).await
+}
+
+/// Run a migration and its daemon until every worker has finished, or a
+/// shutdown signal has drained them. Worker messages are broadcast on
+/// `message_tx` as well as to socket subscribers. Only with `signals` are
+/// SIGINT/SIGTERM (drain) and SIGHUP (reload) handled; a program embedding
+/// the run keeps its own handlers otherwise. Returns the final report, or
+/// `None` if the migration was cut short without one.
+pub async fn run_migration_daemon(
+    config: Config,
+    message_tx: broadcast::Sender<WorkerMessage>,
+    signals: bool,
+) -> Result<Option<MigrationReport>> {
     info!("Starting freight migration daemon with config: {:?}", config);
     
-    let (socket_server, message_rx) = SocketServer::new();
+    let listen_addr = config.listen_addr();
+    claim_listen_addr(&listen_addr).await?;
+    
+    // Unlike a standalone daemon, a migration run starts from a clean slate:
+    // workers left over from an earlier run would skew its report
+    let (mut socket_server, message_rx) = SocketServer::with_sender(message_tx);
+    socket_server.set_listen_addr(listen_addr.clone());
+    socket_server.set_auth_token(config.resolved_auth_token());
+    socket_server.set_socket_permissions(config.socket_permissions()?);
+    socket_server.set_connection_limits(config.connection_limits());
+    let (control_tx, control_rx) = mpsc::unbounded_channel();
+    socket_server.set_control_sender(control_tx.clone());
+    let socket_server = Arc::new(socket_server);
     let mut worker_manager = WorkerManager::new();
     
+    let drain_timeout = Duration::from_secs(config.drain_timeout_secs);
+    let config_path = config.config_path();
+    let metrics_port = config.metrics_port;
+    let retention_grace = config.worker_retention.grace();
+    
+    // Like the report, history is best effort and never stops a migration
+    if let Some(path) = config.resolved_history_db() {
+        match HistoryDb::open(&path) {
+            Ok(history) => worker_manager.set_history(history),
+            Err(e) => error!("{:#}; not recording history for this run", e),
+        }
+    }
+    
     // Set migration config
     worker_manager.set_config(config);
+    worker_manager.set_socket_server(Arc::clone(&socket_server));
     
-    // Start socket server
-    let socket_handle = tokio::spawn(async move {
-        if let Err(e) = socket_server.start().await {
-            error!("Socket server error: {}", e);
-        }
+    let prune_handle =
+        retention_grace.map(|grace| spawn_pruner(Arc::clone(&socket_server), grace));
+    let metrics_handle = metrics_port.map(|port| {
+        let workers = socket_server.workers_handle();
+        let counters = socket_server.counters_handle();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve_metrics(port, workers, counters).await {
+                error!("{:#}", e);
+            }
+        })
     });
     
+    // Start socket server
+    let mut socket_handle = spawn_socket_server(Arc::clone(&socket_server));
+    
     // Start worker manager with migration
-    let worker_handle = tokio::spawn(async move {
-        worker_manager.start_migration(message_rx).await;
+    let mut worker_handle = tokio::spawn(async move {
+        let result = worker_manager.start_migration(message_rx, control_rx).await;
+        result.map(|()| worker_manager.report())
     });
     
-    // Wait for shutdown signal or completion
-    tokio::select! {
-        _ = signal::ctrl_c() => {
-            info!("Received shutdown signal");
-        }
-        _ = socket_handle => {
-            error!("Socket server terminated unexpectedly");
-        }
-        _ = worker_handle => {
-            info!("Migration completed");
+    let mut sighup = if signals {
+        Some(signal::unix::signal(SignalKind::hangup())?)
+    } else {
+        None
+    };
+    let mut migration_result = Ok(None);
+    
+    // Wait for shutdown signal or completion, reloading config on SIGHUP
+    loop {
+        tokio::select! {
+            signal_name = shutdown_signal(), if signals => {
+                info!("Received {}, draining workers for up to {:?}", signal_name, drain_timeout);
+                // The socket stays up while draining so in-flight workers can
+                // still report STOP; the manager just stops spawning new ones
+                let _ = control_tx.send(ControlMessage::Drain);
+                match tokio::time::timeout(drain_timeout, &mut worker_handle).await {
+                    Ok(Ok(result)) => migration_result = result,
+                    Ok(Err(e)) => error!("Migration task failed: {}", e),
+                    Err(_) => {
+                        warn!("Drain timed out, terminating remaining workers");
+                        worker_handle.abort();
+                    }
+                }
+                break;
+            }
+            _ = next_hangup(&mut sighup) => {
+                info!("Received SIGHUP, reloading {}", config_path.display());
+                if let Some(new_config) = reload_config(&config_path, &socket_server) {
+                    let _ = control_tx.send(ControlMessage::Reload(Box::new(new_config)));
+                }
+            }
+            _ = &mut socket_handle => {
+                error!("Socket server terminated unexpectedly");
+                break;
+            }
+            result = &mut worker_handle => {
+                match result {
+                    Ok(Ok(report)) => {
+                        info!("Migration completed");
+                        migration_result = Ok(report);
+                    }
+                    Ok(Err(e)) => {
+                        error!("Migration aborted: {:#}", e);
+                        socket_server.notify(NoticeLevel::Error, format!("Migration aborted: {:#}", e));
+                        migration_result = Err(e);
+                    }
+                    Err(e) => error!("Migration task failed: {}", e),
+                }
+                break;
+            }
         }
     }
     
-    // Cleanup
-    let _ = std::fs::remove_file(crate::socket::SOCKET_PATH);
+    // Cleanup, including the socket server: an embedding program outlives
+    // the run, and its subscribers should see the broadcast close
+    socket_handle.abort();
+    for handle in [prune_handle, metrics_handle].into_iter().flatten() {
+        handle.abort();
+    }
+    release_listen_addr(&listen_addr);
     info!("Freight migration daemon stopped");
     
-    Ok(())
+    migration_result
 }
 
-pub async fn daemonize_and_start() -> Result<()> {
+pub async fn daemonize_and_start(listen_addr: ListenAddr) -> Result<()> {
     // For now, just run in foreground
     // In a full implementation, this would fork and detach
     info!("Daemonizing freight (running in foreground for now)");
-    start_daemon().await
-}
\ No newline at end of file
+    start_daemon(listen_addr, false, None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banner_shows_the_loaded_config() {
+        let mut config = Config::default_with_paths("/srv/source", "/srv/dest");
+        config.thresholds.parallel_workers = 6;
+        let listen_addr = ListenAddr::Tcp("127.0.0.1:7420".to_string());
+
+        let banner = startup_banner(&listen_addr, true, 2, Some(&config));
+
+        let lines: Vec<&str> = banner.lines().collect();
+        assert_eq!(lines[0], format!("freight daemon {}", env!("CARGO_PKG_VERSION")));
+        assert_eq!(
+            &lines[1..],
+            [
+                "  Listening on  tcp://127.0.0.1:7420 (TCP)",
+                "  Auth          token required",
+                &format!(
+                    "  State file    {} (2 workers restored)",
+                    socket::state_path(&listen_addr).display()
+                ),
+                "  Config        /srv/source/.freight/config.json",
+                "  Workers       6 parallel",
+                "  Source        /srv/source",
+                "  Destination   /srv/dest",
+                "Ready for workers",
+            ]
+        );
+    }
+
+    #[test]
+    fn banner_without_a_config_says_none() {
+        let banner = startup_banner(&ListenAddr::Unix("/tmp/f.sock".into()), false, 0, None);
+
+        assert!(banner.contains("  Listening on  /tmp/f.sock (Unix socket)\n"));
+        assert!(banner.contains("  Auth          off\n"));
+        assert!(banner.contains("  Config        none\nReady for workers"));
+        assert!(!banner.contains("Workers"));
+    }
+
+    #[tokio::test]
+    async fn socket_server_is_restarted_after_a_transient_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_dir = dir.path().join("run");
+        let (mut server, _) = SocketServer::new(16);
+        // Binding fails until the directory exists
+        server.set_listen_addr(ListenAddr::Unix(socket_dir.join("freight.sock")));
+        let server = Arc::new(server);
+
+        let handle = spawn_socket_server(Arc::clone(&server));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!handle.is_finished(), "daemon gave up after one failure");
+        std::fs::create_dir(&socket_dir).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(10), server.wait_listening())
+            .await
+            .expect("socket server was not restarted");
+        assert!(!handle.is_finished());
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn socket_server_gives_up_when_the_address_is_taken() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let (mut server, _) = SocketServer::new(16);
+        server.set_listen_addr(ListenAddr::Tcp(taken.local_addr().unwrap().to_string()));
+
+        let handle = spawn_socket_server(Arc::new(server));
+
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("kept retrying an address another process owns")
+            .unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+use crate::logging::use_color;
+use crate::socket::SOCKET_PATH;
+use crate::worker::{check_rsync_flags, MIGRATE_BINARY, SCAN_BINARY};
+use crate::Config;
+use crossterm::style::Stylize;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: true,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run every preflight check for the project rooted at `source`.
+pub fn run_checks(source: &Path) -> Vec<CheckResult> {
+    let mut results = vec![
+        check_binary(SCAN_BINARY),
+        check_binary(MIGRATE_BINARY),
+        check_binary("rsync"),
+        check_socket_dir(Path::new(SOCKET_PATH)),
+    ];
+
+    let config_path = source.join(".freight/config.json");
+    results.push(check_config(&config_path));
+
+    // Path checks only make sense once we know where the config points
+    if let Ok(config) = Config::load(&config_path) {
+        results.push(check_readable(&config.source_path));
+        if config.dest_is_remote() {
+            results.push(CheckResult::pass(
+                "destination writable",
+                format!("{} (remote, not checked)", config.dest_path.display()),
+            ));
+        } else {
+            results.push(check_writable(&config.dest_path));
+        }
+        results.push(check_flags(&config));
+    }
+
+    results
+}
+
+/// Print each check with its verdict; with `quiet`, only the failures.
+pub fn print_checklist(results: &[CheckResult], quiet: bool) {
+    let color = use_color(&std::io::stdout());
+    for result in results.iter().filter(|result| !quiet || !result.passed) {
+        let marker = match (result.passed, color) {
+            (true, true) => "[ OK ]".green().to_string(),
+            (false, true) => "[FAIL]".red().to_string(),
+            (true, false) => "[ OK ]".to_string(),
+            (false, false) => "[FAIL]".to_string(),
+        };
+        println!("{} {}: {}", marker, result.name, result.detail);
+    }
+}
+
+/// Locate an executable on PATH, as the shell would.
+pub fn find_on_path(binary: &str) -> Option<PathBuf> {
+    let path_var = env::var_os("PATH")?;
+    env::split_paths(&path_var)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+pub fn check_binary(binary: &str) -> CheckResult {
+    let name = format!("{} on PATH", binary);
+    match find_on_path(binary) {
+        Some(path) => CheckResult::pass(name, path.display().to_string()),
+        None => CheckResult::fail(name, "not found"),
+    }
+}
+
+pub fn check_readable(path: &Path) -> CheckResult {
+    let name = "source readable";
+    match fs::read_dir(path) {
+        Ok(_) => CheckResult::pass(name, path.display().to_string()),
+        Err(e) => CheckResult::fail(name, format!("{}: {}", path.display(), e)),
+    }
+}
+
+pub fn check_writable(path: &Path) -> CheckResult {
+    let name = "destination writable";
+    if !path.is_dir() {
+        return CheckResult::fail(name, format!("{}: not a directory", path.display()));
+    }
+
+    // Permission bits lie on NFS with root squash, so actually try a write
+    let probe = path.join(format!(".freight-doctor-{}", std::process::id()));
+    match fs::write(&probe, "") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            CheckResult::pass(name, path.display().to_string())
+        }
+        Err(e) => CheckResult::fail(name, format!("{}: {}", path.display(), e)),
+    }
+}
+
+pub fn check_socket_dir(socket_path: &Path) -> CheckResult {
+    let name = "socket directory";
+    match socket_path.parent() {
+        Some(dir) if dir.is_dir() => CheckResult::pass(name, dir.display().to_string()),
+        Some(dir) => CheckResult::fail(name, format!("{}: does not exist", dir.display())),
+        None => CheckResult::fail(name, format!("{}: no parent directory", socket_path.display())),
+    }
+}
+
+pub fn check_flags(config: &Config) -> CheckResult {
+    let name = "rsync flags";
+    match check_rsync_flags(config) {
+        Ok(()) => CheckResult::pass(name, config.rsync_flags_for("migrate")),
+        Err(e) => CheckResult::fail(name, format!("{:#}", e)),
+    }
+}
+
+pub fn check_config(config_path: &Path) -> CheckResult {
+    let name = "config valid";
+    match Config::load(config_path).and_then(|config| config.validate()) {
+        Ok(()) => CheckResult::pass(name, config_path.display().to_string()),
+        Err(e) => CheckResult::fail(name, format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_check_finds_executables_on_path() {
+        let found = check_binary("sh");
+        assert!(found.passed);
+        assert_eq!(found.name, "sh on PATH");
+        assert!(found.detail.ends_with("/sh"), "{}", found.detail);
+
+        let missing = check_binary("freight-no-such-tool");
+        assert!(!missing.passed);
+        assert_eq!(missing.detail, "not found");
+    }
+
+    #[test]
+    fn readable_check_needs_a_listable_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_readable(dir.path()).passed);
+
+        let missing = check_readable(&dir.path().join("missing"));
+        assert!(!missing.passed);
+        assert!(missing.detail.contains("missing"), "{}", missing.detail);
+    }
+
+    #[test]
+    fn writable_check_probes_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_writable(dir.path()).passed);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        let result = check_writable(&file);
+        assert!(!result.passed);
+        assert!(result.detail.ends_with("not a directory"), "{}", result.detail);
+    }
+
+    #[test]
+    fn socket_dir_check_needs_the_parent_to_exist() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_socket_dir(&dir.path().join("freight.sock")).passed);
+
+        let result = check_socket_dir(&dir.path().join("missing/freight.sock"));
+        assert!(!result.passed);
+        assert!(result.detail.ends_with("does not exist"), "{}", result.detail);
+    }
+
+    #[test]
+    fn config_check_loads_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        assert!(!check_config(&path).passed);
+
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.save(&path).unwrap();
+        assert!(check_config(&path).passed);
+
+        config.retry_attempts = 0;
+        config.save(&path).unwrap();
+        let result = check_config(&path);
+        assert!(!result.passed);
+        assert!(result.detail.contains("retry_attempts"), "{}", result.detail);
+    }
+
+    #[test]
+    fn run_checks_skips_path_checks_without_a_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let names: Vec<_> = run_checks(dir.path()).into_iter().map(|result| result.name).collect();
+
+        assert!(names.contains(&"config valid".to_string()));
+        assert!(!names.contains(&"source readable".to_string()));
+        assert!(!names.contains(&"destination writable".to_string()));
+    }
+}
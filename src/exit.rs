@@ -0,0 +1,71 @@
+use crate::config::ConfigError;
+use crate::socket::DaemonUnreachable;
+
+/// Process exit codes, so scripts can tell why freight failed.
+pub const SUCCESS: i32 = 0;
+/// Anything not covered by a more specific code
+pub const FAILURE: i32 = 1;
+/// The config couldn't be read, parsed or validated
+pub const CONFIG_ERROR: i32 = 2;
+/// No daemon answered on the configured address
+pub const DAEMON_NOT_RUNNING: i32 = 3;
+/// The run finished but some workers ended `failed`
+pub const MIGRATION_FAILED: i32 = 4;
+
+/// Workers that ended `failed`, reported once the dashboard closes.
+#[derive(Debug)]
+pub struct MigrationFailed(pub usize);
+
+impl std::fmt::Display for MigrationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            1 => write!(f, "1 worker failed"),
+            n => write!(f, "{} workers failed", n),
+        }
+    }
+}
+
+impl std::error::Error for MigrationFailed {}
+
+/// The exit code for an error, by what kind of failure caused it.
+pub fn code_for(error: &anyhow::Error) -> i32 {
+    // downcast_ref also finds causes attached with `.context()`
+    if error.downcast_ref::<ConfigError>().is_some() {
+        CONFIG_ERROR
+    } else if error.downcast_ref::<DaemonUnreachable>().is_some() {
+        DAEMON_NOT_RUNNING
+    } else if error.downcast_ref::<MigrationFailed>().is_some() {
+        MIGRATION_FAILED
+    } else {
+        FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn errors_map_to_the_code_for_their_cause() {
+        let config = anyhow::Error::from(ConfigError(anyhow::anyhow!("bad retry_attempts")));
+        assert_eq!(code_for(&config), CONFIG_ERROR);
+        let daemon = anyhow::Error::from(DaemonUnreachable("127.0.0.1:1".into()));
+        assert_eq!(code_for(&daemon), DAEMON_NOT_RUNNING);
+        assert_eq!(code_for(&MigrationFailed(2).into()), MIGRATION_FAILED);
+        assert_eq!(code_for(&anyhow::anyhow!("disk on fire")), FAILURE);
+
+        // Context added on the way up doesn't hide the cause
+        let wrapped = Err::<(), _>(std::io::Error::other("refused"))
+            .context(DaemonUnreachable("127.0.0.1:1".into()))
+            .context("Failed to query workers")
+            .unwrap_err();
+        assert_eq!(code_for(&wrapped), DAEMON_NOT_RUNNING);
+    }
+
+    #[test]
+    fn migration_failures_count_their_workers() {
+        assert_eq!(MigrationFailed(1).to_string(), "1 worker failed");
+        assert_eq!(MigrationFailed(3).to_string(), "3 workers failed");
+    }
+}
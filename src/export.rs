@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// One worker row as the dashboard showed it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportRow {
+    pub host: String,
+    pub tool: String,
+    pub directory: String,
+    pub status: String,
+    pub elapsed_secs: Option<u64>,
+    pub bytes: Option<u64>,
+    pub bytes_total: Option<u64>,
+    pub files: Option<u64>,
+    /// Percent complete, 0-100
+    pub progress: Option<u8>,
+    pub message: Option<String>,
+}
+
+const CSV_HEADER: &str =
+    "host,tool,directory,status,elapsed_secs,bytes,bytes_total,files,progress,message";
+
+pub fn render(rows: &[ExportRow], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(rows).context("Failed to serialize dashboard export")
+        }
+        ExportFormat::Csv => Ok(to_csv(rows)),
+    }
+}
+
+fn to_csv(rows: &[ExportRow]) -> String {
+    fn number(value: Option<impl ToString>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for row in rows {
+        let fields = [
+            escape_csv(&row.host),
+            escape_csv(&row.tool),
+            escape_csv(&row.directory),
+            escape_csv(&row.status),
+            number(row.elapsed_secs),
+            number(row.bytes),
+            number(row.bytes_total),
+            number(row.files),
+            number(row.progress),
+            escape_csv(row.message.as_deref().unwrap_or("")),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a field if it holds a comma, quote or line break (RFC 4180).
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write the rows to `<dir>/<timestamp>.<ext>`, returning the path.
+pub fn write_export(dir: &Path, rows: &[ExportRow], format: ExportFormat) -> Result<PathBuf> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create export directory {}", dir.display()))?;
+
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let path = dir.join(format!("{}.{}", timestamp, format.extension()));
+    fs::write(&path, render(rows, format)?)
+        .with_context(|| format!("Failed to write export to {}", path.display()))?;
+    Ok(path)
+}
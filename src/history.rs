@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Row};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::tui::format_bytes;
+
+/// One finished worker, as recorded in `history_db`.
+#[derive(Debug, Clone)]
+pub struct HistoryRun {
+    pub directory: PathBuf,
+    pub tool: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub bytes: Option<u64>,
+    pub status: String,
+    pub retries: u32,
+    pub error: Option<String>,
+}
+
+impl HistoryRun {
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.finished_at
+            .map(|end| (end - self.started_at).num_milliseconds() as f64 / 1000.0)
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            directory: PathBuf::from(row.get::<_, String>("directory")?),
+            tool: row.get("tool")?,
+            started_at: row.get("started_at")?,
+            finished_at: row.get("finished_at")?,
+            bytes: row.get::<_, Option<i64>>("bytes")?.map(|bytes| bytes as u64),
+            status: row.get("status")?,
+            retries: row.get("retries")?,
+            error: row.get("error")?,
+        })
+    }
+}
+
+/// SQLite store of every worker run, kept across migrations for auditing.
+pub struct HistoryDb {
+    // Connection isn't Sync, and the worker manager is borrowed across awaits
+    conn: Mutex<Connection>,
+}
+
+impl HistoryDb {
+    /// Open the database, creating it and its parent directory if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create history directory {}", parent.display())
+            })?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open history database {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                 id INTEGER PRIMARY KEY,
+                 directory TEXT NOT NULL,
+                 tool TEXT NOT NULL,
+                 started_at TEXT NOT NULL,
+                 finished_at TEXT,
+                 bytes INTEGER,
+                 status TEXT NOT NULL,
+                 retries INTEGER NOT NULL,
+                 error TEXT
+             );
+             CREATE INDEX IF NOT EXISTS runs_directory ON runs (directory);",
+        )
+        .with_context(|| format!("Failed to set up history database {}", path.display()))?;
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    pub fn record(&self, run: &HistoryRun) -> Result<()> {
+        self.connection()
+            .execute(
+                "INSERT INTO runs
+                     (directory, tool, started_at, finished_at, bytes, status, retries, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    run.directory.to_string_lossy(),
+                    run.tool,
+                    run.started_at,
+                    run.finished_at,
+                    run.bytes.map(|bytes| bytes as i64),
+                    run.status,
+                    run.retries,
+                    run.error,
+                ],
+            )
+            .context("Failed to record run in history database")?;
+        Ok(())
+    }
+
+    /// The most recent `limit` runs, newest first, optionally only for
+    /// `directory`.
+    pub fn runs(&self, directory: Option<&Path>, limit: usize) -> Result<Vec<HistoryRun>> {
+        let conn = self.connection();
+        let mut statement = conn
+            .prepare(
+                "SELECT * FROM runs
+                 WHERE ?1 IS NULL OR directory = ?1
+                 ORDER BY started_at DESC, id DESC
+                 LIMIT ?2",
+            )
+            .context("Failed to query history database")?;
+        let rows = statement
+            .query_map(
+                params![directory.map(|d| d.to_string_lossy()), limit as i64],
+                HistoryRun::from_row,
+            )
+            .context("Failed to query history database")?;
+
+        rows.collect::<rusqlite::Result<_>>()
+            .context("Failed to read run from history database")
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // A panic mid-statement leaves nothing half-written worth refusing
+        self.conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Print runs one per line, in the order given.
+pub fn print_runs(runs: &[HistoryRun]) {
+    if runs.is_empty() {
+        println!("No runs recorded");
+        return;
+    }
+
+    for run in runs {
+        let bytes = run.bytes.map(format_bytes).unwrap_or_default();
+        let duration = run
+            .duration_secs()
+            .map(|secs| format!("{:.1}s", secs))
+            .unwrap_or_default();
+        println!(
+            "{}  {:8} {:10} {:>10} {:>8} {:>2} retries  {}",
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            run.tool,
+            run.status,
+            bytes,
+            duration,
+            run.retries,
+            run.directory.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(directory: &str, started_secs: i64, status: &str) -> HistoryRun {
+        let started_at = DateTime::from_timestamp(1_700_000_000 + started_secs, 0).unwrap();
+        HistoryRun {
+            directory: PathBuf::from(directory),
+            tool: "migrate".to_string(),
+            started_at,
+            finished_at: Some(started_at + chrono::Duration::milliseconds(2500)),
+            bytes: Some(4096),
+            status: status.to_string(),
+            retries: 1,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn runs_are_queried_newest_first_and_outlive_the_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/history.db");
+        let history = HistoryDb::open(&path).unwrap();
+        history.record(&run("/src/a", 0, "ok")).unwrap();
+        history.record(&run("/src/b", 10, "failed")).unwrap();
+        history.record(&run("/src/a", 20, "ok")).unwrap();
+        drop(history);
+
+        let history = HistoryDb::open(&path).unwrap();
+        let all = history.runs(None, 10).unwrap();
+        let order: Vec<_> = all
+            .iter()
+            .map(|run| (run.directory.to_str().unwrap(), run.status.as_str()))
+            .collect();
+        assert_eq!(order, [("/src/a", "ok"), ("/src/b", "failed"), ("/src/a", "ok")]);
+        assert_eq!(all[0].started_at, run("/src/a", 20, "ok").started_at);
+        assert_eq!(all[0].bytes, Some(4096));
+        assert_eq!(all[0].retries, 1);
+        assert_eq!(all[0].duration_secs(), Some(2.5));
+
+        let a = history.runs(Some(Path::new("/src/a")), 10).unwrap();
+        assert_eq!(a.len(), 2);
+        assert!(a.iter().all(|run| run.directory == Path::new("/src/a")));
+        assert_eq!(history.runs(None, 1).unwrap().len(), 1);
+        assert!(history.runs(Some(Path::new("/src/c")), 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::config::parse_size;
+use crate::Config;
+use anyhow::{Context, Result};
+use dialoguer::Input;
+use std::path::Path;
+
+/// Ask for the settings people most often forget to edit, validating each
+/// answer before moving on.
+pub fn prompt_config(source: &Path) -> Result<Config> {
+    let defaults = Config::new_project(&source.to_string_lossy(), "")?;
+
+    let dest: String = Input::new()
+        .with_prompt("Destination directory")
+        .validate_with(|input: &String| -> Result<(), String> {
+            let path = Path::new(input.trim());
+            if input.trim().is_empty() {
+                Err("destination is required".to_string())
+            } else if !path.is_absolute() {
+                Err("destination must be an absolute path".to_string())
+            } else if path == source {
+                Err("destination must differ from the source".to_string())
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()
+        .context("Failed to read destination")?;
+
+    let parallel_workers: u32 = Input::new()
+        .with_prompt("Parallel workers")
+        .default(defaults.thresholds.parallel_workers)
+        .validate_with(|input: &u32| -> Result<(), &str> {
+            if *input == 0 {
+                Err("must be at least 1")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()
+        .context("Failed to read parallel workers")?;
+
+    let large_directory_size: String = Input::new()
+        .with_prompt("Large directory threshold")
+        .default(defaults.thresholds.large_directory_size.clone())
+        .validate_with(|input: &String| -> Result<(), String> {
+            parse_size(input).map(|_| ()).map_err(|e| e.to_string())
+        })
+        .interact_text()
+        .context("Failed to read large directory threshold")?;
+
+    let rsync_flags: String = Input::new()
+        .with_prompt("rsync flags")
+        .default(defaults.rsync_flags.clone())
+        .validate_with(|input: &String| -> Result<(), &str> {
+            if input.trim().is_empty() {
+                Err("rsync flags must not be empty")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()
+        .context("Failed to read rsync flags")?;
+
+    let mut config = Config {
+        dest_path: dest.trim().into(),
+        ..defaults
+    };
+    config.thresholds.parallel_workers = parallel_workers;
+    config.thresholds.large_directory_size = large_directory_size;
+    config.rsync_flags = rsync_flags;
+    config.validate()?;
+
+    Ok(config)
+}
@@ -1,10 +1,68 @@
 // Re-export main modules for use by other parts of the application
 pub mod config;
 pub mod daemon;
+pub mod doctor;
+pub mod exit;
+pub mod export;
+pub mod history;
+pub mod init;
+pub mod logging;
+pub mod manifest;
+pub mod metrics;
+pub mod plan;
+pub mod progress;
+pub mod report;
 pub mod socket;
+pub mod status;
 pub mod tui;
+pub mod validate;
+pub mod webhook;
 pub mod worker;
 
 pub use config::Config;
 pub use socket::{SocketServer, WorkerMessage, WorkerState};
-pub use worker::{WorkerManager, WorkerStatus};
\ No newline at end of file
+pub use worker::{WorkerManager, WorkerStatus};
+
+use anyhow::Result;
+use tokio::sync::broadcast;
+
+/// Per-directory outcome of a migration run through [`Freight`].
+pub type MigrationSummary = report::MigrationReport;
+
+/// Runs a migration from another program: what `freight migrate` does,
+/// without the dashboard. The daemon socket, tool binaries, reports and
+/// history all behave as they do from the command line. Signals are left
+/// to the embedding program unless [`Freight::handle_signals`] is set.
+pub struct Freight {
+    config: Config,
+    message_tx: broadcast::Sender<WorkerMessage>,
+    signals: bool,
+}
+
+impl Freight {
+    pub fn new(config: Config) -> Self {
+        let (message_tx, _) = broadcast::channel(config.broadcast_capacity);
+        Self { config, message_tx, signals: false }
+    }
+
+    /// Let SIGINT/SIGTERM drain the run and SIGHUP reload the config, as
+    /// `freight migrate` does. Off by default.
+    pub fn handle_signals(mut self, signals: bool) -> Self {
+        self.signals = signals;
+        self
+    }
+
+    /// Every worker message of the runs that follow. Subscribe before
+    /// `run_migration` so nothing is missed.
+    pub fn subscribe(&self) -> broadcast::Receiver<WorkerMessage> {
+        self.message_tx.subscribe()
+    }
+
+    /// Validate the config, run the migration to the end and summarize it.
+    pub async fn run_migration(&self) -> Result<MigrationSummary> {
+        self.config.validate()?;
+        daemon::run_migration_daemon(self.config.clone(), self.message_tx.clone(), self.signals)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Migration stopped before it could be summarized"))
+    }
+}
\ No newline at end of file
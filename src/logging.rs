@@ -0,0 +1,322 @@
+use crate::Config;
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, Layer, Registry};
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How log lines are written, chosen with `log_format` or `--log-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// tracing's human-readable lines
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log pipelines
+    Json,
+}
+
+/// Set up tracing from the project config, if there is one. Output always
+/// goes to stderr; when `log_file` is set it is also written there, rotated
+/// daily. `format` overrides the config's `log_format`, and `quiet` holds
+/// stderr to warnings and errors. Hold on to the returned guard until exit
+/// so buffered lines are flushed.
+pub fn init(
+    config: Option<&Config>,
+    format: Option<LogFormat>,
+    quiet: bool,
+) -> Result<Option<WorkerGuard>> {
+    // A bad log_level is reported by Config::validate, where commands that
+    // care about the config will fail; logging itself just falls back
+    let level = config
+        .and_then(|config| config.log_level_filter().ok())
+        .unwrap_or(LevelFilter::INFO);
+    let format = format
+        .or(config.map(|config| config.log_format))
+        .unwrap_or_default();
+    let stderr_level = if quiet { level.min(LevelFilter::WARN) } else { level };
+
+    let mut layers = vec![format_layer(format, std::io::stderr, !no_color(), stderr_level)];
+
+    let Some(log_file) = config.and_then(Config::resolved_log_file) else {
+        tracing_subscriber::registry().with(layers).init();
+        return Ok(None);
+    };
+
+    let (writer, guard) = tracing_appender::non_blocking(rolling_writer(&log_file)?);
+    layers.push(format_layer(format, writer, false, level));
+    tracing_subscriber::registry().with(layers).init();
+
+    Ok(Some(guard))
+}
+
+/// Whether `NO_COLOR` is set to anything non-empty, asking for output
+/// without ANSI styling (https://no-color.org).
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Whether plain-text output to `stream` may be colored: it is a terminal
+/// and `NO_COLOR` isn't set.
+pub fn use_color(stream: &impl IsTerminal) -> bool {
+    !no_color() && stream.is_terminal()
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn format_layer<W>(format: LogFormat, writer: W, ansi: bool, level: LevelFilter) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Pretty => fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .with_filter(level)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .with_writer(writer)
+            .event_format(JsonFormat)
+            .with_filter(level)
+            .boxed(),
+    }
+}
+
+/// Writes each event as a JSON object: `timestamp`, `level`, `target`,
+/// then the message and every other field under its own key, so fields
+/// like `tool`, `directory` and `bytes` stay structured.
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        writeln!(writer, "{}", Value::Object(event_json(event)))
+    }
+}
+
+/// The JSON object `JsonFormat` writes for `event`.
+pub fn event_json(event: &Event<'_>) -> Map<String, Value> {
+    let metadata = event.metadata();
+    let mut object = Map::new();
+    object.insert(
+        "timestamp".to_string(),
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true).into(),
+    );
+    object.insert("level".to_string(), metadata.level().as_str().into());
+    object.insert("target".to_string(), metadata.target().into());
+    event.record(&mut JsonFields(&mut object));
+    object
+}
+
+/// Records event fields as JSON values, keeping numbers and booleans typed.
+struct JsonFields<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonFields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+}
+
+/// Daily-rotated writer: `log_file` of `daemon.log` writes to
+/// `daemon.log.2024-01-31`, starting a new file each day.
+fn rolling_writer(log_file: &Path) -> Result<tracing_appender::rolling::RollingFileAppender> {
+    let dir = log_file.parent().unwrap_or(Path::new("."));
+    let file_name = log_file
+        .file_name()
+        .with_context(|| format!("Invalid log_file: {}", log_file.display()))?;
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+
+    tracing_appender::rolling::Builder::new()
+        .rotation(tracing_appender::rolling::Rotation::DAILY)
+        .filename_prefix(file_name.to_string_lossy())
+        .build(dir)
+        .with_context(|| format!("Failed to open log file {}", log_file.display()))
+}
+
+/// The newest file written for `log_file`, which is the dated file for
+/// today once rotation has kicked in.
+pub fn latest_log_file(log_file: &Path) -> Result<Option<PathBuf>> {
+    let dir = log_file.parent().unwrap_or(Path::new("."));
+    let Some(prefix) = log_file.file_name().and_then(|name| name.to_str()) else {
+        anyhow::bail!("Invalid log_file: {}", log_file.display());
+    };
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read log directory {}", dir.display()))
+        }
+    };
+
+    // Dated suffixes sort chronologically, so the greatest name is newest
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name == prefix || name.starts_with(&format!("{}.", prefix)))
+        })
+        .max())
+}
+
+/// Print the last `lines` lines of the daemon log and, with `follow`, keep
+/// printing new output as it is appended, across daily rotations.
+pub async fn print_logs(log_file: &Path, lines: usize, follow: bool) -> Result<()> {
+    let mut current = latest_log_file(log_file)?;
+    let mut offset = 0;
+
+    match &current {
+        Some(path) => {
+            let content = fs::read(path)
+                .with_context(|| format!("Failed to read log file {}", path.display()))?;
+            let content = String::from_utf8_lossy(&content);
+            let tail: Vec<&str> = content.lines().rev().take(lines).collect();
+            for line in tail.iter().rev() {
+                println!("{}", line);
+            }
+            offset = content.len() as u64;
+        }
+        None if follow => eprintln!("Waiting for {} to be created...", log_file.display()),
+        None => {
+            eprintln!("No log file yet at {}", log_file.display());
+            return Ok(());
+        }
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout();
+    loop {
+        tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+
+        let latest = latest_log_file(log_file)?;
+        if latest != current {
+            current = latest;
+            offset = 0;
+        }
+        let Some(path) = &current else { continue };
+
+        let mut file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+        };
+        let len = file.metadata()?.len();
+        if len < offset {
+            // Truncated underneath us; start over from the top
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+        let mut appended = Vec::new();
+        file.read_to_end(&mut appended)?;
+        offset += appended.len() as u64;
+        stdout.write_all(&appended)?;
+        stdout.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer the test can read back.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_events_keep_worker_fields_structured() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let layer = format_layer(LogFormat::Json, move || writer.clone(), false, LevelFilter::INFO);
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                tool = "migrate",
+                directory = "/src/a b",
+                bytes = 1024u64,
+                ok = true,
+                "Worker finished"
+            );
+            tracing::debug!("below the level");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1, "{:?}", lines);
+        let event: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["target"], module_path!());
+        assert_eq!(event["message"], "Worker finished");
+        assert_eq!(event["tool"], "migrate");
+        assert_eq!(event["directory"], "/src/a b");
+        assert_eq!(event["bytes"], 1024);
+        assert_eq!(event["ok"], true);
+        let timestamp = event["timestamp"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(), "{}", timestamp);
+    }
+}
@@ -1,22 +1,29 @@
 use anyhow::{Result, Context};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use freight::plan::{self, MigrationPlan};
+use freight::report::{MigrationReport, ReportFormat};
+use freight::socket::{auth_token_from_env, query_workers, ListenAddr};
+use freight::worker::{check_rsync_flags, ensure_tool_binaries, prepare_dest_root};
+use freight::tui::{format_bytes, DashboardSettings};
+use freight::exit::{self, MigrationFailed};
+use freight::history::{self, HistoryDb};
+use freight::logging::LogFormat;
+use freight::{daemon, doctor, init, logging, status, tui, validate, Config};
+use tokio::task::JoinHandle;
 use tracing::info;
 
-mod config;
-mod daemon;
-mod socket;
-mod tui;
-mod worker;
-
-pub use config::Config;
-pub use socket::{SocketServer, WorkerMessage};
-pub use worker::{WorkerManager, WorkerStatus};
-
 #[derive(Parser)]
 #[command(name = "freight")]
 #[command(about = "NFS Migration Suite Orchestrator")]
 #[command(version = "0.1.0")]
 struct Cli {
+    /// Log line format, overriding the config's log_format
+    #[arg(long, global = true, value_enum)]
+    log_format: Option<LogFormat>,
+    /// Only print warnings, errors and the output asked for; no progress
+    /// logging or banners
+    #[arg(short, long, global = true)]
+    quiet: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -28,6 +35,12 @@ enum Commands {
         /// Migration source directory (defaults to current directory)
         #[arg(short, long)]
         source: Option<String>,
+        /// Prompt for destination, workers and rsync flags
+        #[arg(short, long)]
+        interactive: bool,
+        /// Overwrite an existing project config
+        #[arg(long)]
+        force: bool,
     },
     /// Start daemon and show dashboard
     Dashboard {
@@ -37,6 +50,18 @@ enum Commands {
         /// Migration destination directory  
         #[arg(short, long)]
         dest: Option<String>,
+        /// Plan the migration without transferring any data
+        #[arg(long)]
+        dry_run: bool,
+        /// Ring the bell and send a desktop notification when the migration finishes
+        #[arg(long)]
+        notify: bool,
+        /// Quit on `q` without asking, even while workers are running
+        #[arg(long)]
+        no_confirm_quit: bool,
+        /// Start the dashboard in its dense layout (toggle with `c`)
+        #[arg(long)]
+        compact: bool,
     },
     /// Start migration with dashboard
     Migrate {
@@ -44,80 +69,462 @@ enum Commands {
         source: String,
         /// Migration destination directory
         dest: String,
+        /// Bandwidth limit for rsync (e.g. "50MB"), overrides config
+        #[arg(long)]
+        bwlimit: Option<String>,
+        /// Plan the migration without transferring any data
+        #[arg(long)]
+        dry_run: bool,
+        /// Write the completion report here instead of .freight/reports/
+        #[arg(long)]
+        report: Option<std::path::PathBuf>,
+        /// Completion report format
+        #[arg(long, value_enum)]
+        report_format: Option<ReportFormat>,
+        /// Ring the bell and send a desktop notification when the migration finishes
+        #[arg(long)]
+        notify: bool,
+        /// Quit on `q` without asking, even while workers are running
+        #[arg(long)]
+        no_confirm_quit: bool,
+        /// Start the dashboard in its dense layout (toggle with `c`)
+        #[arg(long)]
+        compact: bool,
+        /// Only migrate this top-level directory of the source (repeatable)
+        #[arg(long, value_name = "DIR")]
+        only: Vec<String>,
+        /// Migrate the directories in this plan instead of discovering them
+        #[arg(long, value_name = "FILE")]
+        plan: Option<std::path::PathBuf>,
+        /// Send this run to DIR instead of the config's dest_path, without
+        /// changing the saved config
+        #[arg(long, value_name = "DIR")]
+        dest_override: Option<std::path::PathBuf>,
+        /// Migrate every directory, even ones unchanged since they last migrated
+        #[arg(long)]
+        force: bool,
+    },
+    /// Discover and scan directories, saving the result as a migration plan
+    Plan {
+        /// Migration source directory
+        source: String,
+        /// Migration destination directory
+        dest: String,
+        /// Write the plan here instead of .freight/plans/
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Only plan this top-level directory of the source (repeatable)
+        #[arg(long, value_name = "DIR")]
+        only: Vec<String>,
     },
     /// Start daemon only (background)
     Daemon {
         /// Run in foreground (don't daemonize)
         #[arg(short, long)]
         foreground: bool,
+        /// Listen on a TCP address (e.g. 0.0.0.0:7420) for remote workers
+        #[arg(long, value_name = "ADDR")]
+        tcp: Option<String>,
     },
     /// Connect TUI client to existing daemon
-    Connect,
+    Connect {
+        /// Attach read-only to the daemon socket at this path, e.g. one
+        /// forwarded over SSH
+        #[arg(long, conflicts_with = "address")]
+        socket: Option<std::path::PathBuf>,
+        /// Attach read-only to a TCP daemon at host:port
+        #[arg(long)]
+        address: Option<String>,
+        /// Start the dashboard in its dense layout (toggle with `c`)
+        #[arg(long)]
+        compact: bool,
+    },
+    /// Print a snapshot of the running daemon's workers
+    Status {
+        /// Print the raw JSON snapshot
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the environment and config before migrating
+    Doctor {
+        /// Migration source directory (defaults to current directory)
+        #[arg(short, long)]
+        source: Option<String>,
+    },
+    /// Check the project's config and `.freight` directory, without side effects
+    Validate {
+        /// Migration source directory (defaults to current directory)
+        #[arg(short, long)]
+        source: Option<String>,
+    },
+    /// Print the daemon log file configured by `log_file`
+    Logs {
+        /// Migration source directory (defaults to current directory)
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Keep printing new lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+        /// Number of lines to show from the end of the log
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+    },
+    /// Print past worker runs recorded in `history_db`
+    History {
+        /// Migration source directory (defaults to current directory)
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Only show runs for this directory
+        #[arg(long, value_name = "DIR")]
+        dir: Option<std::path::PathBuf>,
+        /// Number of runs to show, newest first
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Print a shell completion script to stdout
+    #[command(hide = true)]
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+impl Commands {
+    /// The existing project config this command runs against, used to set
+    /// up logging before anything else happens.
+    fn project_config(&self) -> Option<Config> {
+        let source = match self {
+            Commands::Migrate { source, .. } | Commands::Plan { source, .. } => {
+                Some(source.clone())
+            }
+            Commands::Dashboard { source, .. }
+            | Commands::Doctor { source }
+            | Commands::Validate { source }
+            | Commands::History { source, .. } => source.clone(),
+            // Logging to the file we're about to tail would echo ourselves
+            Commands::Init { .. } | Commands::Logs { .. } | Commands::Completions { .. } => {
+                return None
+            }
+            Commands::Daemon { .. } | Commands::Connect { .. } | Commands::Status { .. } => None,
+        };
+        let source = match source {
+            Some(source) => std::path::PathBuf::from(source),
+            None => std::env::current_dir().ok()?,
+        };
+
+        Config::load(&source.join(".freight/config.json")).ok()
+    }
+}
 
+#[tokio::main]
+async fn main() {
     let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(exit::code_for(&e));
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    // Initialize tracing; the guard flushes the log file on exit
+    let project_config = cli.command.project_config();
+    let _log_guard = logging::init(project_config.as_ref(), cli.log_format, cli.quiet)?;
+    let quiet = cli.quiet;
 
     match cli.command {
-        Commands::Init { source } => {
+        Commands::Init { source, interactive, force } => {
             let current_dir = std::env::current_dir().context("Failed to get current directory")?;
-            let source_path = source
-                .map(|s| std::path::PathBuf::from(s))
-                .unwrap_or(current_dir)
+            let source_path = source.map(std::path::PathBuf::from).unwrap_or(current_dir);
+            Config::check_init_target(&source_path)?;
+            let source_path = source_path
                 .canonicalize()
                 .context("Failed to resolve absolute path")?;
 
+            if Config::is_initialized(&source_path) && !force {
+                anyhow::bail!(
+                    "{} is already a freight project; use --force to overwrite its config",
+                    source_path.display()
+                );
+            }
+
             info!("Initializing freight project in: {}", source_path.display());
-            Config::init_project(source_path.to_str().unwrap())?;
-            println!("Freight project initialized successfully!");
+            if interactive {
+                let config = init::prompt_config(&source_path)?;
+                Config::init_project_with(source_path.to_str().unwrap(), &config)?;
+            } else {
+                Config::init_project(source_path.to_str().unwrap())?;
+            }
+            if !quiet {
+                println!("Freight project initialized successfully!");
+            }
             Ok(())
         }
-        Commands::Dashboard { source, dest } => {
+        Commands::Dashboard { source, dest, dry_run, notify, no_confirm_quit, compact } => {
             info!("Starting freight dashboard");
 
-            // Start daemon in background
-            let daemon_handle = tokio::spawn(async move { daemon::start_daemon().await });
+            // With both paths given, the dashboard drives a migration;
+            // otherwise it just hosts the daemon for standalone tools
+            let migrating = source.is_some() && dest.is_some();
+            let (daemon_handle, settings) = match (source, dest) {
+                (Some(source), Some(dest)) => {
+                    let mut config = Config::load_or_create(&source, &dest)?;
+                    config.dry_run |= dry_run;
+                    config.validate()?;
+                    ensure_tool_binaries()?;
+                    check_rsync_flags(&config)?;
+                    prepare_dest_root(&config)?;
+                    let settings = DashboardSettings::from_config(Some(&config));
+                    let handle =
+                        tokio::spawn(async move { daemon::start_migration_daemon(config).await });
+                    (handle, settings)
+                }
+                _ if dry_run => {
+                    anyhow::bail!("--dry-run requires both --source and --dest");
+                }
+                _ => {
+                    // No run of its own, so no report to exit by
+                    let handle = tokio::spawn(async move {
+                        let daemon = daemon::start_daemon(ListenAddr::default(), false, None);
+                        daemon.await.map(|()| None)
+                    });
+                    // The daemon started here ignores the project config's
+                    // transport, so only the dashboard's own settings apply
+                    let settings = DashboardSettings {
+                        listen_addr: ListenAddr::default(),
+                        auth_token: auth_token_from_env(),
+                        ..DashboardSettings::from_config(project_config.as_ref())
+                    };
+                    (handle, settings)
+                }
+            };
 
             // Start TUI client
-            let tui_result = tui::run_dashboard().await;
+            let confirm_quit = settings.confirm_quit && !no_confirm_quit;
+            let tui_result =
+                tui::run_dashboard(DashboardSettings { notify, confirm_quit, compact, ..settings })
+                    .await;
 
-            // Clean shutdown
-            daemon_handle.abort();
-            tui_result
+            if migrating {
+                finish_migration(daemon_handle, tui_result).await
+            } else {
+                daemon_handle.abort();
+                tui_result
+            }
         }
-        Commands::Migrate { source, dest } => {
+        Commands::Migrate {
+            source,
+            dest,
+            bwlimit,
+            dry_run,
+            report,
+            report_format,
+            notify,
+            no_confirm_quit,
+            compact,
+            only,
+            plan,
+            dest_override,
+            force,
+        } => {
             info!("Starting migration: {} -> {}", source, dest);
 
             // Load or create config
-            let config = Config::load_or_create(&source, &dest)?;
+            let mut config = Config::load_or_create(&source, &dest)?;
+            if bwlimit.is_some() {
+                config.bandwidth_limit = bwlimit;
+            }
+            config.dry_run |= dry_run;
+            if report.is_some() {
+                config.report_path = report;
+            }
+            if let Some(format) = report_format {
+                config.report_format = format;
+            }
+            // "a/" from shell completion means the same as "a"
+            config.only_directories = only
+                .iter()
+                .map(|dir| dir.trim_end_matches('/').to_string())
+                .collect();
+            if plan.is_some() && !config.only_directories.is_empty() {
+                anyhow::bail!("--plan and --only cannot be combined; plan with --only instead");
+            }
+            if plan.is_some() && dest_override.is_some() {
+                anyhow::bail!(
+                    "--plan and --dest-override cannot be combined; the plan fixes each destination"
+                );
+            }
+            config.plan_path = plan;
+            config.dest_override = dest_override;
+            config.force = force;
+            if let Some(dest) = &config.dest_override {
+                info!("Overriding dest_path with {} for this run", dest.display());
+            }
+            config.validate()?;
+            // Checked here too so the error reaches the terminal rather
+            // than only the daemon log behind the dashboard
+            ensure_tool_binaries()?;
+            check_rsync_flags(&config)?;
+            prepare_dest_root(&config)?;
+            let mut settings = DashboardSettings {
+                notify,
+                compact,
+                ..DashboardSettings::from_config(Some(&config))
+            };
+            settings.confirm_quit &= !no_confirm_quit;
 
             // Start daemon with migration
             let daemon_handle =
                 tokio::spawn(async move { daemon::start_migration_daemon(config).await });
 
             // Start TUI client
-            let tui_result = tui::run_dashboard().await;
+            let tui_result = tui::run_dashboard(settings).await;
+            finish_migration(daemon_handle, tui_result).await
+        }
+        Commands::Plan { source, dest, output, only } => {
+            let mut config = Config::load_or_create(&source, &dest)?;
+            config.only_directories = only
+                .iter()
+                .map(|dir| dir.trim_end_matches('/').to_string())
+                .collect();
+            config.validate()?;
+            ensure_tool_binaries()?;
 
-            // Clean shutdown
-            daemon_handle.abort();
-            tui_result
+            let plan = MigrationPlan::build(&config).await?;
+            let path = output.unwrap_or_else(|| plan::default_plan_path(&config.freight_dir()));
+            plan.write(&path)?;
+
+            println!(
+                "Planned {} directories ({}) -> {}",
+                plan.directories.len(),
+                format_bytes(plan.total_size()),
+                path.display()
+            );
+            Ok(())
         }
-        Commands::Daemon { foreground } => {
+        Commands::Daemon { foreground, tcp } => {
+            let listen_addr = tcp.map(ListenAddr::Tcp).unwrap_or_default();
             if foreground {
                 info!("Starting freight daemon in foreground");
-                daemon::start_daemon().await
+                daemon::start_daemon(listen_addr, !quiet, project_config.as_ref()).await
             } else {
                 info!("Starting freight daemon in background");
-                daemon::daemonize_and_start().await
+                daemon::daemonize_and_start(listen_addr).await
             }
         }
-        Commands::Connect => {
+        Commands::Connect { socket, address, compact } => {
             info!("Connecting to existing freight daemon");
-            tui::run_dashboard().await
+            // Follow the project config in the current directory, if any,
+            // so TCP and abstract-socket daemons are found too
+            let mut settings = DashboardSettings::from_config(project_config.as_ref());
+            // An explicit address is someone else's daemon; only watch it
+            if let Some(listen_addr) = socket
+                .map(ListenAddr::Unix)
+                .or_else(|| address.map(ListenAddr::Tcp))
+            {
+                settings.listen_addr = listen_addr;
+                settings.read_only = true;
+            }
+            settings.compact = compact;
+            tui::run_dashboard(settings).await
+        }
+        Commands::Status { json } => {
+            let settings = DashboardSettings::from_config(project_config.as_ref());
+            let snapshot =
+                query_workers(&settings.listen_addr, settings.auth_token.as_deref()).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&snapshot)?);
+            } else {
+                status::print_status(&snapshot);
+            }
+            Ok(())
+        }
+        Commands::Doctor { source } => {
+            let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+            let source_path = source.map(std::path::PathBuf::from).unwrap_or(current_dir);
+
+            let results = doctor::run_checks(&source_path);
+            doctor::print_checklist(&results, quiet);
+
+            if results.iter().all(|r| r.passed) {
+                if !quiet {
+                    println!("All checks passed");
+                }
+                Ok(())
+            } else {
+                std::process::exit(exit::FAILURE);
+            }
         }
+        Commands::Validate { source } => {
+            let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+            let source_path = source.map(std::path::PathBuf::from).unwrap_or(current_dir);
+
+            let problems = validate::check_project(&source_path);
+            if problems.is_empty() && quiet {
+                return Ok(());
+            }
+            validate::print_problems(&source_path, &problems);
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                std::process::exit(exit::CONFIG_ERROR);
+            }
+        }
+        Commands::Logs { source, follow, lines } => {
+            let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+            let source_path = source.map(std::path::PathBuf::from).unwrap_or(current_dir);
+            let config_path = source_path.join(".freight/config.json");
+
+            let config = Config::load(&config_path)?;
+            let log_file = config.resolved_log_file().with_context(|| {
+                format!("log_file is not set in {}", config_path.display())
+            })?;
+            logging::print_logs(&log_file, lines, follow).await
+        }
+        Commands::History { source, dir, limit } => {
+            let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+            let source_path = source.map(std::path::PathBuf::from).unwrap_or(current_dir.clone());
+            let config_path = source_path.join(".freight/config.json");
+
+            let config = Config::load(&config_path)?;
+            let history_db = config.resolved_history_db().with_context(|| {
+                format!("history_db is not set in {}", config_path.display())
+            })?;
+            if !history_db.exists() {
+                anyhow::bail!("No history recorded yet at {}", history_db.display());
+            }
+            // Runs are recorded under absolute paths
+            let dir = dir.map(|dir| dir.canonicalize().unwrap_or_else(|_| current_dir.join(dir)));
+
+            let history = HistoryDb::open(&history_db)?;
+            history::print_runs(&history.runs(dir.as_deref(), limit)?);
+            Ok(())
+        }
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+            Ok(())
+        }
+    }
+}
+
+/// Shut down the migration daemon once the dashboard has closed, and exit
+/// by the run's final report rather than by what the dashboard last showed:
+/// `exit::MIGRATION_FAILED` if any directory failed, and a plain failure if
+/// the dashboard was closed before the run finished.
+async fn finish_migration(
+    daemon_handle: JoinHandle<Result<Option<MigrationReport>>>,
+    dashboard: Result<()>,
+) -> Result<()> {
+    if !daemon_handle.is_finished() {
+        daemon_handle.abort();
+        dashboard?;
+        anyhow::bail!("Dashboard closed before the migration finished");
+    }
+    dashboard?;
+    match daemon_handle.await?? {
+        Some(report) if report.failed() > 0 => Err(MigrationFailed(report.failed()).into()),
+        Some(_) => Ok(()),
+        None => anyhow::bail!("Migration was cut short before it finished"),
     }
 }
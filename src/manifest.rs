@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::worker::unit_freight_dir;
+
+/// What a directory looked like when it last migrated successfully, kept
+/// in `.freight/manifests/` so an unchanged directory can be skipped next
+/// time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryManifest {
+    pub directory: PathBuf,
+    pub dest: PathBuf,
+    /// Hash of every entry's path, size and mtime, from freight-scan
+    pub manifest: String,
+    pub migrated_at: DateTime<Utc>,
+}
+
+impl DirectoryManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest from {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse manifest from {}", path.display()))
+    }
+
+    /// Write the manifest, creating parent directories as needed.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create manifest directory {}", parent.display())
+            })?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(self).context("Failed to serialize manifest")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write manifest to {}", path.display()))
+    }
+}
+
+/// `<freight_dir>/manifests/<directory relative to source>.json`
+pub fn manifest_path(freight_dir: &Path, source_path: &Path, directory: &Path) -> PathBuf {
+    let relative = directory.strip_prefix(source_path).unwrap_or(directory);
+    freight_dir
+        .join("manifests")
+        .join(format!("{}.json", relative.display()))
+}
+
+/// The manifest hash freight-scan recorded for `directory`, if any.
+pub fn scanned_manifest(directory: &Path) -> Option<String> {
+    let scan = fs::read_to_string(unit_freight_dir(directory).join("scan.json")).ok()?;
+    parse_manifest(&scan)
+}
+
+/// Pull `"manifest": "<hash>"` out of a scan result. Read as text for the
+/// same reason as `total_size` in plan.rs: log lines can leak into other
+/// fields and break the JSON.
+fn parse_manifest(scan: &str) -> Option<String> {
+    let rest = &scan[scan.find("\"manifest\"")? + "\"manifest\"".len()..];
+    let value = rest.trim_start_matches([':', ' ']).strip_prefix('"')?;
+    let hash = &value[..value.find('"')?];
+    (!hash.is_empty()).then(|| hash.to_string())
+}
@@ -0,0 +1,139 @@
+use crate::worker::WorkerKey;
+use crate::WorkerState;
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Upper bounds, in seconds, of the per-directory duration histogram.
+const DURATION_BUCKETS: &[f64] = &[60.0, 300.0, 900.0, 3600.0, 4.0 * 3600.0, 24.0 * 3600.0];
+
+type Workers = Arc<RwLock<HashMap<WorkerKey, WorkerState>>>;
+
+/// Run-wide totals behind the counter and histogram metrics. Each worker is
+/// recorded once, when it finishes, so the totals only grow even as
+/// finished workers are pruned from the daemon's map.
+#[derive(Debug, Clone, Default)]
+pub struct Counters {
+    bytes_transferred: u64,
+    retries: u64,
+    /// Finished workers per `DURATION_BUCKETS` bound, not cumulative
+    duration_buckets: [u64; DURATION_BUCKETS.len()],
+    duration_sum: f64,
+    duration_count: u64,
+}
+
+impl Counters {
+    /// Add a worker that has just finished.
+    pub fn record_finished(&mut self, worker: &WorkerState) {
+        self.bytes_transferred += worker.bytes_transferred.unwrap_or(0);
+        self.retries += u64::from(worker.attempts.saturating_sub(1));
+        if let (Some(start), Some(end)) = (worker.started_at, worker.finished_at) {
+            if let Ok(duration) = end.duration_since(start) {
+                let seconds = duration.as_secs_f64();
+                if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+                    self.duration_buckets[bucket] += 1;
+                }
+                self.duration_sum += seconds;
+                self.duration_count += 1;
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct MetricsState {
+    workers: Workers,
+    counters: Arc<Mutex<Counters>>,
+}
+
+/// Serve Prometheus metrics on `0.0.0.0:<port>/metrics` until the task is
+/// dropped.
+pub async fn serve_metrics(
+    port: u16,
+    workers: Workers,
+    counters: Arc<Mutex<Counters>>,
+) -> Result<()> {
+    let addr = format!("0.0.0.0:{}", port);
+    let listener = TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics endpoint on {}", addr))?;
+
+    info!("Metrics endpoint listening on http://{}/metrics", addr);
+
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(MetricsState { workers, counters });
+    axum::serve(listener, app)
+        .await
+        .context("Metrics endpoint failed")
+}
+
+async fn metrics_handler(State(state): State<MetricsState>) -> impl IntoResponse {
+    let workers = state.workers.read().await;
+    let counters = state
+        .counters
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    let body = render_metrics(&workers, &counters);
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+}
+
+/// Render the Prometheus text exposition format: the status gauge from the
+/// worker map as it stands, everything else from `counters`.
+pub fn render_metrics(workers: &HashMap<WorkerKey, WorkerState>, counters: &Counters) -> String {
+    let mut by_status: BTreeMap<&str, u64> = BTreeMap::new();
+    for worker in workers.values() {
+        *by_status.entry(worker.status.as_str()).or_default() += 1;
+    }
+
+    let mut out = String::new();
+
+    // Writing to a String can't fail, so the fmt results are ignored
+    let _ = writeln!(out, "# HELP freight_workers Workers known to the daemon, by status");
+    let _ = writeln!(out, "# TYPE freight_workers gauge");
+    for (status, count) in &by_status {
+        let _ = writeln!(out, "freight_workers{{status=\"{}\"}} {}", status, count);
+    }
+
+    let _ = writeln!(out, "# HELP freight_bytes_transferred_total Bytes reported by workers");
+    let _ = writeln!(out, "# TYPE freight_bytes_transferred_total counter");
+    let _ = writeln!(out, "freight_bytes_transferred_total {}", counters.bytes_transferred);
+
+    let _ = writeln!(out, "# HELP freight_retries_total rsync attempts beyond the first");
+    let _ = writeln!(out, "# TYPE freight_retries_total counter");
+    let _ = writeln!(out, "freight_retries_total {}", counters.retries);
+
+    let _ = writeln!(out, "# HELP freight_directory_duration_seconds Time each finished worker ran");
+    let _ = writeln!(out, "# TYPE freight_directory_duration_seconds histogram");
+    let mut count = 0;
+    for (bound, in_bucket) in DURATION_BUCKETS.iter().zip(counters.duration_buckets) {
+        count += in_bucket;
+        let _ = writeln!(
+            out,
+            "freight_directory_duration_seconds_bucket{{le=\"{}\"}} {}",
+            bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "freight_directory_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        counters.duration_count
+    );
+    let _ = writeln!(out, "freight_directory_duration_seconds_sum {}", counters.duration_sum);
+    let _ = writeln!(out, "freight_directory_duration_seconds_count {}", counters.duration_count);
+
+    out
+}
@@ -0,0 +1,182 @@
+use crate::worker::{discover_directories, unit_freight_dir, SCAN_BINARY};
+use crate::Config;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::warn;
+
+/// A migration worked out ahead of time by `freight plan`, replayed
+/// verbatim by `freight migrate --plan`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationPlan {
+    pub created_at: DateTime<Utc>,
+    pub source_path: PathBuf,
+    pub dest_path: PathBuf,
+    pub directories: Vec<PlannedDirectory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedDirectory {
+    pub directory: PathBuf,
+    /// Size from the scan, if it succeeded
+    pub size: Option<u64>,
+    /// Where the directory is migrated to, after `dest_map`
+    pub dest: PathBuf,
+}
+
+impl MigrationPlan {
+    /// Discover and scan every directory the config would migrate.
+    pub async fn build(config: &Config) -> Result<Self> {
+        // Unreadable directories were already warned about, and a plan
+        // only lists what can be migrated
+        let directories = discover_directories(config).await?.directories;
+
+        let mut planned = Vec::with_capacity(directories.len());
+        for directory in directories {
+            let size = scan_size(&directory).await;
+            if size.is_none() {
+                warn!("No scan size for {}, planning it without one", directory.display());
+            }
+            planned.push(PlannedDirectory {
+                dest: config.dest_dir_for(&directory),
+                directory,
+                size,
+            });
+        }
+
+        Ok(Self {
+            created_at: Utc::now(),
+            source_path: config.source_path.clone(),
+            dest_path: config.dest_root().to_path_buf(),
+            directories: planned,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read plan from {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse plan from {}", path.display()))
+    }
+
+    /// Write the plan, creating parent directories as needed.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create plan directory {}", parent.display())
+            })?;
+        }
+
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize plan")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write plan to {}", path.display()))
+    }
+
+    /// Planned directories that no longer exist.
+    pub fn missing_directories(&self) -> Vec<&Path> {
+        self.directories
+            .iter()
+            .map(|planned| planned.directory.as_path())
+            .filter(|directory| !directory.exists())
+            .collect()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.directories.iter().filter_map(|planned| planned.size).sum()
+    }
+}
+
+/// Default location: `<freight_dir>/plans/<timestamp>.json`
+pub fn default_plan_path(freight_dir: &Path) -> PathBuf {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    freight_dir.join("plans").join(format!("{}.json", timestamp))
+}
+
+/// Run `freight-scan` on `directory` and read the size it recorded.
+async fn scan_size(directory: &Path) -> Option<u64> {
+    let status = Command::new(SCAN_BINARY)
+        .arg("--quiet")
+        .arg(directory)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            warn!("Scan of {} exited with {}", directory.display(), status);
+            return None;
+        }
+        Err(e) => {
+            warn!("Failed to run {} on {}: {}", SCAN_BINARY, directory.display(), e);
+            return None;
+        }
+    }
+
+    let scan_file = unit_freight_dir(directory).join("scan.json");
+    let content = fs::read_to_string(scan_file).ok()?;
+    parse_total_size(&content)
+}
+
+/// Pull `"total_size": N` out of a scan result. Read as text rather than
+/// JSON because freight-scan's log lines can leak into other fields.
+fn parse_total_size(scan: &str) -> Option<u64> {
+    let rest = &scan[scan.find("\"total_size\"")? + "\"total_size\"".len()..];
+    let digits = rest.trim_start_matches([':', ' ']);
+    let end = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_round_trips_through_its_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let present = dir.path().join("present");
+        fs::create_dir(&present).unwrap();
+        let plan = MigrationPlan {
+            created_at: Utc::now(),
+            source_path: dir.path().to_path_buf(),
+            dest_path: PathBuf::from("/dst"),
+            directories: vec![
+                PlannedDirectory {
+                    directory: present.clone(),
+                    size: Some(4096),
+                    dest: PathBuf::from("/dst/archive/present"),
+                },
+                PlannedDirectory {
+                    directory: dir.path().join("gone"),
+                    size: None,
+                    dest: PathBuf::from("/dst/gone"),
+                },
+            ],
+        };
+        let path = default_plan_path(&dir.path().join(".freight"));
+
+        plan.write(&path).unwrap();
+        let loaded = MigrationPlan::load(&path).unwrap();
+
+        assert_eq!(loaded.created_at, plan.created_at);
+        assert_eq!(loaded.source_path, plan.source_path);
+        assert_eq!(loaded.directories.len(), 2);
+        assert_eq!(loaded.directories[0].dest, Path::new("/dst/archive/present"));
+        assert_eq!(loaded.directories[0].size, Some(4096));
+        assert_eq!(loaded.directories[1].size, None);
+        assert_eq!(loaded.total_size(), 4096);
+        assert_eq!(loaded.missing_directories(), [dir.path().join("gone")]);
+    }
+
+    #[test]
+    fn scan_total_size_is_read_from_text() {
+        let scan = r#"{"total_files": 3, "total_size": 12345, "x": 1}"#;
+        assert_eq!(parse_total_size(scan), Some(12345));
+        assert_eq!(parse_total_size(r#"{"total_size":7}"#), Some(7));
+        assert_eq!(parse_total_size(r#"{"total_files": 3}"#), None);
+    }
+}
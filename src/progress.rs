@@ -0,0 +1,120 @@
+use crate::worker::MigrationBackend;
+
+/// What one line of a copy tool's output says about the transfer so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressUpdate {
+    pub bytes: Option<u64>,
+    pub files: Option<u64>,
+    pub message: Option<String>,
+}
+
+/// Turns a backend's output, a line at a time, into progress updates.
+pub trait ProgressParser: Send {
+    /// The update `line` carries, or `None` for anything else.
+    fn parse_line(&mut self, line: &str) -> Option<ProgressUpdate>;
+}
+
+/// The parser for what `backend` prints while it copies.
+pub fn parser_for(backend: MigrationBackend) -> Box<dyn ProgressParser> {
+    match backend {
+        MigrationBackend::Rsync => Box::new(RsyncProgress),
+        MigrationBackend::Tar | MigrationBackend::Cp => Box::new(SilentProgress),
+    }
+}
+
+/// rsync `--info=progress2`, whose lines total the whole transfer:
+/// `  1,234,567  45%   12.34MB/s    0:00:10 (xfr#12, to-chk=34/100)`
+pub struct RsyncProgress;
+
+impl ProgressParser for RsyncProgress {
+    fn parse_line(&mut self, line: &str) -> Option<ProgressUpdate> {
+        let mut fields = line.split_whitespace();
+        let bytes = fields.next()?.replace(',', "").parse().ok()?;
+        let percent = fields.next().filter(|field| field.ends_with('%'))?;
+        let rate = fields.next().filter(|field| field.ends_with("/s"));
+        // Only present once a file has finished: "(xfr#12,"
+        let files = fields
+            .find_map(|field| field.strip_prefix("(xfr#"))
+            .and_then(|count| count.trim_end_matches([',', ')']).parse().ok());
+
+        Some(ProgressUpdate {
+            bytes: Some(bytes),
+            files,
+            message: Some(match rate {
+                Some(rate) => format!("{} at {}", percent, rate),
+                None => percent.to_string(),
+            }),
+        })
+    }
+}
+
+/// tar and cp print nothing while they copy, so their progress is only
+/// what freight-migrate reports itself.
+pub struct SilentProgress;
+
+impl ProgressParser for SilentProgress {
+    fn parse_line(&mut self, _line: &str) -> Option<ProgressUpdate> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(bytes: u64, files: Option<u64>, message: &str) -> Option<ProgressUpdate> {
+        Some(ProgressUpdate {
+            bytes: Some(bytes),
+            files,
+            message: Some(message.to_string()),
+        })
+    }
+
+    #[test]
+    fn rsync_progress2_lines_give_bytes_files_and_rate() {
+        let mut parser = parser_for(MigrationBackend::Rsync);
+        for (line, expected) in [
+            // Before the first file finishes there's no transfer count
+            (
+                "         32,768   0%    0.00kB/s    0:00:00",
+                update(32_768, None, "0% at 0.00kB/s"),
+            ),
+            (
+                "     1,234,567  45%   12.34MB/s    0:00:10 (xfr#12, to-chk=34/100)",
+                update(1_234_567, Some(12), "45% at 12.34MB/s"),
+            ),
+            (
+                "  6,442,450,944 100%  101.23MB/s    0:01:00 (xfr#100, to-chk=0/100)",
+                update(6_442_450_944, Some(100), "100% at 101.23MB/s"),
+            ),
+            (
+                "    524,288  50%  1.00MB/s  0:00:01 (xfr#3, ir-chk=1000/1005)",
+                update(524_288, Some(3), "50% at 1.00MB/s"),
+            ),
+        ] {
+            assert_eq!(parser.parse_line(line), expected, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn other_rsync_output_is_not_progress() {
+        let mut parser = parser_for(MigrationBackend::Rsync);
+        for line in [
+            "sending incremental file list",
+            "",
+            "sent 1,234 bytes  received 56 bytes  2,580.00 bytes/sec",
+            "Number of files: 100 (reg: 90, dir: 10)",
+            "total size is 6,442,450,944  speedup is 1.00",
+        ] {
+            assert_eq!(parser.parse_line(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn tar_and_cp_report_no_progress_of_their_own() {
+        for backend in [MigrationBackend::Tar, MigrationBackend::Cp] {
+            let line = "     1,234,567  45%   12.34MB/s    0:00:10 (xfr#12, to-chk=34/100)";
+            assert_eq!(parser_for(backend).parse_line(line), None);
+        }
+    }
+}
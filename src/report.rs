@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::tui::format_bytes;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// When the run started, if this report covers one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    pub generated_at: DateTime<Utc>,
+    pub source_path: PathBuf,
+    pub dest_path: PathBuf,
+    pub dry_run: bool,
+    #[serde(default)]
+    pub totals: RunTotals,
+    pub directories: Vec<DirectoryReport>,
+}
+
+/// The whole run at a glance, worked out from its directories.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunTotals {
+    pub bytes_transferred: u64,
+    /// Wall-clock time from the start of the run to the report
+    pub duration_secs: Option<f64>,
+    /// `bytes_transferred` over `duration_secs`
+    pub bytes_per_sec: Option<f64>,
+    /// How many directories ended in each status
+    pub directories_by_status: BTreeMap<String, usize>,
+    pub retries: u32,
+}
+
+impl RunTotals {
+    pub fn new(
+        directories: &[DirectoryReport],
+        started_at: Option<DateTime<Utc>>,
+        finished_at: DateTime<Utc>,
+    ) -> Self {
+        let bytes_transferred = directories.iter().filter_map(|d| d.bytes_transferred).sum();
+        let duration_secs = started_at
+            .map(|start| (finished_at - start).num_milliseconds().max(0) as f64 / 1000.0);
+        let mut directories_by_status = BTreeMap::new();
+        for directory in directories {
+            *directories_by_status.entry(directory.status.clone()).or_insert(0) += 1;
+        }
+
+        Self {
+            bytes_transferred,
+            duration_secs,
+            bytes_per_sec: duration_secs
+                .filter(|secs| *secs > 0.0)
+                .map(|secs| bytes_transferred as f64 / secs),
+            directories_by_status,
+            retries: directories.iter().map(|d| d.retries).sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryReport {
+    pub directory: PathBuf,
+    /// The last phase that ran for this directory (scan or migrate)
+    pub phase: String,
+    pub status: String,
+    pub bytes_transferred: Option<u64>,
+    pub duration_secs: Option<f64>,
+    pub retries: u32,
+    pub error: Option<String>,
+}
+
+impl MigrationReport {
+    pub fn succeeded(&self) -> usize {
+        self.directories
+            .iter()
+            .filter(|d| matches!(d.status.as_str(), "completed" | "planned" | "skipped"))
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.directories
+            .iter()
+            .filter(|d| d.status == "failed")
+            .count()
+    }
+
+    pub fn cancelled(&self) -> usize {
+        self.directories
+            .iter()
+            .filter(|d| d.status == "cancelled")
+            .count()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.directories
+            .iter()
+            .filter_map(|d| d.bytes_transferred)
+            .sum()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).context("Failed to serialize report")
+            }
+            ReportFormat::Html => Ok(self.to_html()),
+        }
+    }
+
+    /// Write the report, creating parent directories as needed.
+    pub fn write(&self, path: &Path, format: ReportFormat) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create report directory {}", parent.display())
+            })?;
+        }
+
+        fs::write(path, self.render(format)?)
+            .with_context(|| format!("Failed to write report to {}", path.display()))
+    }
+
+    fn to_html(&self) -> String {
+        let mut rows = String::new();
+        for dir in &self.directories {
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&dir.directory.display().to_string()),
+                escape_html(&dir.phase),
+                escape_html(&dir.status),
+                dir.bytes_transferred.map(|b| b.to_string()).unwrap_or_default(),
+                dir.duration_secs.map(|d| format!("{:.1}", d)).unwrap_or_default(),
+                dir.retries,
+                escape_html(dir.error.as_deref().unwrap_or("")),
+            ));
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Freight migration report</title></head>\n<body>\n\
+             <h1>Freight migration report</h1>\n\
+             <p>{} &rarr; {}{}</p>\n\
+             <p>Generated {} &mdash; {} succeeded, {} failed, {} cancelled, {} bytes in {} ({}), {} retries</p>\n\
+             <table border=\"1\">\n\
+             <tr><th>Directory</th><th>Phase</th><th>Status</th><th>Bytes</th><th>Duration (s)</th><th>Retries</th><th>Error</th></tr>\n\
+             {}</table>\n</body>\n</html>\n",
+            escape_html(&self.source_path.display().to_string()),
+            escape_html(&self.dest_path.display().to_string()),
+            if self.dry_run { " (dry run)" } else { "" },
+            self.generated_at.to_rfc3339(),
+            self.succeeded(),
+            self.failed(),
+            self.cancelled(),
+            self.total_bytes(),
+            self.totals
+                .duration_secs
+                .map(|secs| format!("{:.1}s", secs))
+                .unwrap_or_else(|| "-".to_string()),
+            self.totals
+                .bytes_per_sec
+                .map(|rate| format!("{}/s", format_bytes(rate as u64)))
+                .unwrap_or_else(|| "-".to_string()),
+            self.totals.retries,
+            rows,
+        )
+    }
+}
+
+/// Default location: `<freight_dir>/reports/<timestamp>.<ext>`
+pub fn default_report_path(freight_dir: &Path, format: ReportFormat) -> PathBuf {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    freight_dir
+        .join("reports")
+        .join(format!("{}.{}", timestamp, format.extension()))
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,14 +1,153 @@
-use tokio::net::{UnixListener, UnixStream};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, Context};
 use tracing::{info, warn, error, debug};
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::{RwLock, broadcast};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{RwLock, broadcast, mpsc, watch};
+use crate::worker::{ControlMessage, WorkerKey};
+use crate::config::write_atomic;
+use crate::metrics::Counters;
 
 pub const SOCKET_PATH: &str = "/tmp/freight-daemon.sock";
 
+/// Sent by TUI clients in place of worker traffic to receive the broadcast.
+pub const SUBSCRIBE_COMMAND: &str = "SUBSCRIBE";
+
+/// Answered with a single JSON `WorkersSnapshot` line. The connection is
+/// closed afterwards unless the command is followed by `keep_open`.
+pub const QUERY_COMMAND: &str = "QUERY";
+
+/// Answered with `PONG`. Only a bound listener can answer, so a reply
+/// means the daemon is ready for connections.
+pub const PING_COMMAND: &str = "PING";
+/// Messages buffered per receiver when the config doesn't say otherwise.
+pub const DEFAULT_BROADCAST_CAPACITY: usize = 1000;
+/// Longest message line accepted when the config doesn't say otherwise.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// `CANCEL tool=<tool> dir=<dir>` asks the daemon to stop one worker.
+pub const CANCEL_COMMAND: &str = "CANCEL";
+
+pub const DEFAULT_TCP_ADDRESS: &str = "0.0.0.0:7420";
+
+/// Name of the daemon socket in the Linux abstract namespace.
+pub const ABSTRACT_SOCKET_NAME: &str = "freight-daemon";
+
+/// Environment variable carrying the shared auth token to daemon and tools.
+pub const AUTH_TOKEN_ENV: &str = "FREIGHT_AUTH_TOKEN";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Unix,
+    Tcp,
+}
+
+/// Another daemon is live on the address we wanted. Unlike other socket
+/// errors this won't clear up by retrying.
+#[derive(Debug)]
+pub struct AlreadyRunning(pub String);
+
+impl std::fmt::Display for AlreadyRunning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Freight daemon already running on {}", self.0)
+    }
+}
+
+impl std::error::Error for AlreadyRunning {}
+
+/// Nothing is listening on the daemon address.
+#[derive(Debug)]
+pub struct DaemonUnreachable(pub String);
+
+impl std::fmt::Display for DaemonUnreachable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No freight daemon running on {}", self.0)
+    }
+}
+
+impl std::error::Error for DaemonUnreachable {}
+
+/// How a Unix-transport daemon names its socket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketAddressMode {
+    /// A socket file at `SOCKET_PATH`
+    #[default]
+    Path,
+    /// A name in the Linux abstract namespace; nothing on disk to clean up.
+    /// Falls back to `Path` on other platforms.
+    Abstract,
+}
+
+/// File mode and optional group applied to the Unix socket after binding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SocketPermissions {
+    pub mode: u32,
+    pub group: Option<String>,
+}
+
+impl Default for SocketPermissions {
+    fn default() -> Self {
+        Self {
+            mode: 0o600,
+            group: None,
+        }
+    }
+}
+
+/// Where the daemon accepts worker and TUI connections.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListenAddr {
+    Unix(PathBuf),
+    /// Linux abstract-namespace socket name, without the leading NUL
+    Abstract(String),
+    Tcp(String),
+}
+
+/// Where a standalone daemon listening on `addr` keeps its worker state, so
+/// daemons on different addresses never restore each other's workers. A
+/// Unix socket's state sits beside it; other addresses get a file in the
+/// temp directory.
+pub fn state_path(addr: &ListenAddr) -> PathBuf {
+    let (kind, name) = match addr {
+        ListenAddr::Unix(path) => {
+            let mut state = path.clone().into_os_string();
+            state.push(".state.json");
+            return PathBuf::from(state);
+        }
+        ListenAddr::Abstract(name) => ("abstract", name),
+        ListenAddr::Tcp(address) => ("tcp", address),
+    };
+    let key: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    std::env::temp_dir().join(format!("freight-daemon-state-{}-{}.json", kind, key))
+}
+
+impl Default for ListenAddr {
+    fn default() -> Self {
+        ListenAddr::Unix(PathBuf::from(SOCKET_PATH))
+    }
+}
+
+impl std::fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListenAddr::Unix(path) => write!(f, "{}", path.display()),
+            ListenAddr::Abstract(name) => write!(f, "@{}", name),
+            ListenAddr::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerMessage {
     pub message_type: MessageType,
@@ -16,6 +155,12 @@ pub struct WorkerMessage {
     pub directory: Option<String>,
     pub status: Option<String>,
     pub bytes: Option<u64>,
+    /// Size of the whole directory, when the tool knows it (scan's STOP)
+    #[serde(default)]
+    pub bytes_total: Option<u64>,
+    /// Files moved so far, when the tool reports them
+    #[serde(default)]
+    pub files: Option<u64>,
     pub message: Option<String>,
     pub host: Option<String>,
     pub pid: Option<u32>,
@@ -27,146 +172,718 @@ pub enum MessageType {
     Start,
     Progress,
     Stop,
+    /// From a tool: it is exiting. From the daemon to subscribers: the
+    /// worker was pruned under `worker_retention` and should be dropped.
+    Bye,
+    /// From the daemon to subscribers only: a problem on its side, such as
+    /// a worker that failed to spawn. `status` is the [`NoticeLevel`] and
+    /// `message` says what happened.
+    Notice,
+}
+
+/// How serious a daemon `Notice` is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoticeLevel {
+    Warning,
+    Error,
+}
+
+impl NoticeLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoticeLevel::Warning => "warning",
+            NoticeLevel::Error => "error",
+        }
+    }
+}
+
+/// How long the daemon keeps finished workers in its map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerRetention {
+    /// Forever, until the daemon restarts without its state file
+    #[default]
+    Keep,
+    /// Prune workers this many seconds after they finished
+    RemoveAfter(u64),
+}
+
+impl WorkerRetention {
+    pub fn grace(&self) -> Option<Duration> {
+        match self {
+            WorkerRetention::Keep => None,
+            WorkerRetention::RemoveAfter(secs) => Some(Duration::from_secs(*secs)),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+/// What a single connection may send before the daemon drops it.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionLimits {
+    /// Bytes per line, newline included
+    pub max_line_length: usize,
+    /// Lines per second; `None` for no limit
+    pub max_messages_per_sec: Option<u32>,
+    /// Drop the connection on a line that isn't UTF-8, rather than
+    /// replacing the bad bytes and carrying on
+    pub strict_utf8: bool,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            max_messages_per_sec: None,
+            strict_utf8: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerState {
     pub tool: String,
     pub directory: Option<String>,
     pub status: String,
     pub last_message: Option<String>,
     pub bytes_transferred: Option<u64>,
+    #[serde(default)]
+    pub bytes_total: Option<u64>,
+    #[serde(default)]
+    pub files: Option<u64>,
     pub host: Option<String>,
     pub pid: Option<u32>,
     pub connected: bool,
+    #[serde(default)]
+    pub started_at: Option<SystemTime>,
+    #[serde(default)]
+    pub finished_at: Option<SystemTime>,
+    /// rsync attempts announced via `PROGRESS msg=Attempt`
+    #[serde(default)]
+    pub attempts: u32,
+    /// The `M` of the latest `Attempt N/M`
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+impl WorkerState {
+    /// The key this worker is filed under, if it names a directory.
+    pub fn key(&self) -> Option<WorkerKey> {
+        Some(WorkerKey::new(&self.tool, self.directory.as_deref()?))
+    }
+}
+
+/// Worker maps are written as a JSON list of states, since each state
+/// already carries its tool and directory and a structured key can't be a
+/// JSON object key. The `"tool:dir"`-keyed object older daemons wrote is
+/// still read.
+mod worker_list {
+    use super::{WorkerKey, WorkerState};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+    
+    pub fn serialize<S: Serializer>(
+        workers: &HashMap<WorkerKey, WorkerState>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut states: Vec<_> = workers.iter().collect();
+        states.sort_by(|a, b| a.0.cmp(b.0));
+        serializer.collect_seq(states.into_iter().map(|(_, state)| state))
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<WorkerKey, WorkerState>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            List(Vec<WorkerState>),
+            Keyed(HashMap<String, WorkerState>),
+        }
+        
+        let states = match Stored::deserialize(deserializer)? {
+            Stored::List(states) => states,
+            Stored::Keyed(states) => states.into_values().collect(),
+        };
+        // Older daemons filed bare HELLOs as "unknown:unknown"
+        Ok(states
+            .into_iter()
+            .filter_map(|state| Some((state.key()?, state)))
+            .collect())
+    }
+}
+
+/// Reply to `QUERY`: the daemon's view of every worker at one moment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkersSnapshot {
+    #[serde(with = "worker_list")]
+    pub workers: HashMap<WorkerKey, WorkerState>,
+    /// When the daemon started; missing from older daemons
+    #[serde(default)]
+    pub started_at: Option<SystemTime>,
+}
+
+impl WorkersSnapshot {
+    /// How long the daemon had been running when it answered, by this
+    /// host's clock.
+    pub fn uptime(&self) -> Option<Duration> {
+        let started = self.started_at?;
+        Some(SystemTime::now().duration_since(started).unwrap_or_default())
+    }
 }
 
 pub struct SocketServer {
-    workers: Arc<RwLock<HashMap<String, WorkerState>>>,
+    workers: Arc<RwLock<HashMap<WorkerKey, WorkerState>>>,
     message_tx: broadcast::Sender<WorkerMessage>,
+    listen_addr: ListenAddr,
+    auth_token: Option<Arc<str>>,
+    socket_permissions: SocketPermissions,
+    control_tx: Option<mpsc::UnboundedSender<ControlMessage>>,
+    /// Totals for the metrics counters, kept apart from `workers` so
+    /// pruning finished workers doesn't take anything back
+    counters: Arc<Mutex<Counters>>,
+    limits: ConnectionLimits,
+    /// Flips to true once the listener is bound
+    listening: watch::Sender<bool>,
+    /// When the server was created, reported as the daemon's start in `QUERY`
+    started_at: SystemTime,
 }
 
 impl SocketServer {
-    pub fn new() -> (Self, broadcast::Receiver<WorkerMessage>) {
-        let (message_tx, message_rx) = broadcast::channel(1000);
+    /// `capacity` is how many messages a slow receiver can fall behind
+    /// before it starts missing them.
+    pub fn new(capacity: usize) -> (Self, broadcast::Receiver<WorkerMessage>) {
+        Self::with_sender(broadcast::channel(capacity).0)
+    }
+    
+    /// Broadcast on `message_tx`, so receivers subscribed before the
+    /// server existed hear every message too.
+    pub fn with_sender(
+        message_tx: broadcast::Sender<WorkerMessage>,
+    ) -> (Self, broadcast::Receiver<WorkerMessage>) {
+        let message_rx = message_tx.subscribe();
         
         (
             Self {
                 workers: Arc::new(RwLock::new(HashMap::new())),
                 message_tx,
+                listen_addr: ListenAddr::default(),
+                auth_token: None,
+                socket_permissions: SocketPermissions::default(),
+                control_tx: None,
+                counters: Arc::new(Mutex::new(Counters::default())),
+                limits: ConnectionLimits::default(),
+                listening: watch::channel(false).0,
+                started_at: SystemTime::now(),
             },
             message_rx,
         )
     }
     
+    pub fn set_listen_addr(&mut self, listen_addr: ListenAddr) {
+        self.listen_addr = listen_addr;
+    }
+    
+    pub fn listen_addr(&self) -> &ListenAddr {
+        &self.listen_addr
+    }
+    
+    pub fn set_socket_permissions(&mut self, socket_permissions: SocketPermissions) {
+        self.socket_permissions = socket_permissions;
+    }
+    
+    /// Route client commands such as CANCEL to the worker manager.
+    pub fn set_control_sender(&mut self, control_tx: mpsc::UnboundedSender<ControlMessage>) {
+        self.control_tx = Some(control_tx);
+    }
+    
+    /// Require every connection to open with `AUTH <token>`.
+    pub fn set_auth_token(&mut self, auth_token: Option<String>) {
+        self.auth_token = auth_token.map(Arc::from);
+    }
+    
+    pub fn set_connection_limits(&mut self, limits: ConnectionLimits) {
+        self.limits = limits;
+    }
+    
+    /// Resolves once the server has bound its address and accepts
+    /// connections. Never resolves if binding keeps failing.
+    pub async fn wait_listening(&self) {
+        let _ = self.listening.subscribe().wait_for(|listening| *listening).await;
+    }
+    
+    /// Listen until binding or accepting fails. Callers that want the
+    /// server to outlive transient errors should restart it.
     pub async fn start(&self) -> Result<()> {
-        // Remove existing socket file
-        let _ = std::fs::remove_file(SOCKET_PATH);
+        match &self.listen_addr {
+            ListenAddr::Unix(path) => self.serve_unix(path).await,
+            ListenAddr::Abstract(name) => self.serve_abstract(name).await,
+            ListenAddr::Tcp(addr) => self.serve_tcp(addr).await,
+        }
+    }
+    
+    #[cfg(target_os = "linux")]
+    async fn serve_abstract(&self, name: &str) -> Result<()> {
+        use std::os::linux::net::SocketAddrExt;
+        
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)
+            .context("Invalid abstract socket name")?;
+        let listener = std::os::unix::net::UnixListener::bind_addr(&addr)
+            .with_context(|| format!("Failed to bind abstract socket @{}", name))?;
+        listener.set_nonblocking(true)?;
+        let listener = UnixListener::from_std(listener)?;
         
-        let listener = UnixListener::bind(SOCKET_PATH)
+        info!("Socket server listening on @{}", name);
+        self.listening.send_replace(true);
+        
+        loop {
+            let (stream, _) = listener.accept().await.context("Failed to accept connection")?;
+            self.spawn_connection(stream);
+        }
+    }
+    
+    #[cfg(not(target_os = "linux"))]
+    async fn serve_abstract(&self, _name: &str) -> Result<()> {
+        self.serve_unix(Path::new(SOCKET_PATH)).await
+    }
+    
+    async fn serve_unix(&self, path: &Path) -> Result<()> {
+        claim_socket_path(path).await?;
+        
+        let listener = UnixListener::bind(path)
             .context("Failed to bind Unix socket")?;
+        apply_socket_permissions(path, &self.socket_permissions)?;
         
-        info!("Socket server listening on {}", SOCKET_PATH);
+        info!("Socket server listening on {}", path.display());
+        self.listening.send_replace(true);
         
         loop {
-            match listener.accept().await {
-                Ok((stream, _)) => {
-                    let workers = Arc::clone(&self.workers);
-                    let message_tx = self.message_tx.clone();
-                    
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_worker_connection(stream, workers, message_tx).await {
-                            error!("Worker connection error: {}", e);
-                        }
-                    });
-                }
-                Err(e) => {
-                    error!("Failed to accept connection: {}", e);
-                }
-            }
+            let (stream, _) = listener.accept().await.context("Failed to accept connection")?;
+            self.spawn_connection(stream);
+        }
+    }
+    
+    async fn serve_tcp(&self, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind TCP socket on {}", addr))?;
+        
+        info!("Socket server listening on tcp://{}", addr);
+        self.listening.send_replace(true);
+        
+        loop {
+            let (stream, peer) = listener.accept().await.context("Failed to accept connection")?;
+            debug!("Accepted TCP connection from {}", peer);
+            self.spawn_connection(stream);
         }
     }
     
-    pub async fn get_workers(&self) -> HashMap<String, WorkerState> {
+    fn spawn_connection<S>(&self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let context = ConnectionContext {
+            workers: Arc::clone(&self.workers),
+            counters: Arc::clone(&self.counters),
+            message_tx: self.message_tx.clone(),
+            auth_token: self.auth_token.clone(),
+            control_tx: self.control_tx.clone(),
+            limits: self.limits,
+            started_at: self.started_at,
+        };
+        
+        tokio::spawn(async move {
+            if let Err(e) = handle_worker_connection(stream, context).await {
+                error!("Worker connection error: {}", e);
+            }
+        });
+    }
+    
+    /// Shared handle to the live worker map, for read-only consumers such
+    /// as the metrics endpoint.
+    pub fn workers_handle(&self) -> Arc<RwLock<HashMap<WorkerKey, WorkerState>>> {
+        Arc::clone(&self.workers)
+    }
+    
+    /// Shared handle to the run-wide totals behind the metrics counters.
+    pub fn counters_handle(&self) -> Arc<Mutex<Counters>> {
+        Arc::clone(&self.counters)
+    }
+    
+    pub async fn get_workers(&self) -> HashMap<WorkerKey, WorkerState> {
         self.workers.read().await.clone()
     }
+    
+    /// Restore worker state persisted by a previous daemon, replacing any
+    /// workers already known. Workers are marked disconnected until they say
+    /// HELLO again.
+    pub async fn load_state(&self, path: &Path) -> Result<usize> {
+        let mut restored = load_workers(path)?;
+        for worker in restored.values_mut() {
+            worker.connected = false;
+        }
+        
+        let count = restored.len();
+        *self.workers.write().await = restored;
+        Ok(count)
+    }
+    
+    /// Record a message on the daemon's own behalf, as if a tool had sent
+    /// it, and pass it on to subscribers.
+    pub async fn publish(&self, message: WorkerMessage) {
+        if let Some(id) = WorkerKey::for_message(&message) {
+            let mut workers = self.workers.write().await;
+            if apply_message(&mut workers, &id, &message, None) {
+                lock_counters(&self.counters).record_finished(&workers[&id]);
+            }
+            if let Some(worker) = workers.get_mut(&id) {
+                worker.connected = false;
+            }
+        }
+        let _ = self.message_tx.send(message);
+    }
+    
+    /// Tell subscribers about a problem the daemon hit, which would
+    /// otherwise only reach its log. No worker is recorded for it.
+    pub fn notify(&self, level: NoticeLevel, text: impl Into<String>) {
+        let _ = self.message_tx.send(WorkerMessage {
+            message_type: MessageType::Notice,
+            tool: "daemon".to_string(),
+            directory: None,
+            status: Some(level.as_str().to_string()),
+            bytes: None,
+            bytes_total: None,
+            files: None,
+            message: Some(text.into()),
+            host: None,
+            pid: None,
+        });
+    }
+    
+    /// Drop workers that finished more than `grace` ago and tell
+    /// subscribers with a BYE for each. Returns how many were pruned.
+    pub async fn prune_finished(&self, grace: Duration) -> usize {
+        let now = SystemTime::now();
+        let mut pruned = Vec::new();
+        self.workers.write().await.retain(|_, worker| {
+            let expired = worker
+                .finished_at
+                .and_then(|finished| now.duration_since(finished).ok())
+                .is_some_and(|age| age >= grace);
+            if expired {
+                pruned.push((worker.tool.clone(), worker.directory.clone()));
+            }
+            !expired
+        });
+        
+        for (tool, directory) in &pruned {
+            debug!("Pruning finished worker {}:{}", tool, directory.as_deref().unwrap_or("unknown"));
+            let _ = self.message_tx.send(WorkerMessage {
+                message_type: MessageType::Bye,
+                tool: tool.clone(),
+                directory: directory.clone(),
+                status: None,
+                bytes: None,
+                bytes_total: None,
+                files: None,
+                message: None,
+                host: None,
+                pid: None,
+            });
+        }
+        pruned.len()
+    }
+    
+    pub async fn save_state(&self, path: &Path) -> Result<()> {
+        let workers = self.workers.read().await;
+        save_workers(path, &workers)
+    }
+}
+
+/// Anything that can carry the line protocol to the daemon.
+pub trait DaemonStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> DaemonStream for T {}
+
+/// Connect to a daemon listening on `addr`, whichever transport it uses.
+pub async fn connect(addr: &ListenAddr) -> std::io::Result<Box<dyn DaemonStream>> {
+    match addr {
+        ListenAddr::Unix(path) => Ok(Box::new(UnixStream::connect(path).await?)),
+        ListenAddr::Abstract(name) => Ok(Box::new(connect_abstract(name)?)),
+        ListenAddr::Tcp(addr) => Ok(Box::new(TcpStream::connect(addr).await?)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn connect_abstract(name: &str) -> std::io::Result<UnixStream> {
+    use std::os::linux::net::SocketAddrExt;
+    
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+    let stream = std::os::unix::net::UnixStream::connect_addr(&addr)?;
+    stream.set_nonblocking(true)?;
+    UnixStream::from_std(stream)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn connect_abstract(_name: &str) -> std::io::Result<UnixStream> {
+    std::os::unix::net::UnixStream::connect(SOCKET_PATH).and_then(|stream| {
+        stream.set_nonblocking(true)?;
+        UnixStream::from_std(stream)
+    })
+}
+
+/// Make `path` available for binding. A socket file that still accepts
+/// connections belongs to a live daemon and is left alone; one that refuses
+/// them is stale and gets removed.
+pub async fn claim_socket_path(path: &Path) -> Result<()> {
+    if fs::symlink_metadata(path).is_err() {
+        return Ok(());
+    }
+    
+    if UnixStream::connect(path).await.is_ok() {
+        return Err(AlreadyRunning(path.display().to_string()).into());
+    }
+    
+    debug!("Removing stale socket {}", path.display());
+    fs::remove_file(path)
+        .with_context(|| format!("Failed to remove stale socket {}", path.display()))
+}
+
+/// Restrict who can connect to the socket. The default umask would leave it
+/// connectable by every user on the box.
+pub fn apply_socket_permissions(path: &Path, permissions: &SocketPermissions) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    
+    if let Some(group) = &permissions.group {
+        let gid = lookup_group_id(group)?;
+        std::os::unix::fs::chown(path, None, Some(gid))
+            .with_context(|| format!("Failed to set group {} on {}", group, path.display()))?;
+    }
+    
+    fs::set_permissions(path, fs::Permissions::from_mode(permissions.mode))
+        .with_context(|| format!("Failed to set mode {:o} on {}", permissions.mode, path.display()))
+}
+
+pub(crate) fn lookup_user_id(user: &str) -> Result<u32> {
+    if let Ok(uid) = user.parse() {
+        return Ok(uid);
+    }
+    
+    let users = fs::read_to_string("/etc/passwd").context("Failed to read /etc/passwd")?;
+    users
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?;
+            (name == user).then(|| uid.parse().ok()).flatten()
+        })
+        .next()
+        .with_context(|| format!("Unknown user: {}", user))
+}
+
+pub(crate) fn lookup_group_id(group: &str) -> Result<u32> {
+    if let Ok(gid) = group.parse() {
+        return Ok(gid);
+    }
+    
+    let groups = fs::read_to_string("/etc/group").context("Failed to read /etc/group")?;
+    groups
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let gid = fields.nth(1)?;
+            (name == group).then(|| gid.parse().ok()).flatten()
+        })
+        .next()
+        .with_context(|| format!("Unknown group: {}", group))
+}
+
+pub fn load_workers(path: &Path) -> Result<HashMap<WorkerKey, WorkerState>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read worker state from {}", path.display()))?;
+    
+    worker_list::deserialize(&mut serde_json::Deserializer::from_str(&content))
+        .with_context(|| format!("Failed to parse worker state from {}", path.display()))
+}
+
+pub fn save_workers(path: &Path, workers: &HashMap<WorkerKey, WorkerState>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory {}", parent.display()))?;
+    }
+    
+    let mut content = Vec::new();
+    worker_list::serialize(workers, &mut serde_json::Serializer::pretty(&mut content))
+        .context("Failed to serialize worker state")?;
+    
+    write_atomic(path, &content)
+        .with_context(|| format!("Failed to write worker state to {}", path.display()))
 }
 
-async fn handle_worker_connection(
-    stream: UnixStream,
-    workers: Arc<RwLock<HashMap<String, WorkerState>>>,
+/// What each connection shares with the server that accepted it.
+struct ConnectionContext {
+    workers: Arc<RwLock<HashMap<WorkerKey, WorkerState>>>,
+    counters: Arc<Mutex<Counters>>,
     message_tx: broadcast::Sender<WorkerMessage>,
-) -> Result<()> {
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
-    let mut worker_id: Option<String> = None;
+    auth_token: Option<Arc<str>>,
+    control_tx: Option<mpsc::UnboundedSender<ControlMessage>>,
+    limits: ConnectionLimits,
+    started_at: SystemTime,
+}
+
+async fn handle_worker_connection<S>(stream: S, context: ConnectionContext) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let ConnectionContext {
+        workers,
+        counters,
+        message_tx,
+        auth_token,
+        control_tx,
+        limits,
+        started_at,
+    } = context;
+    let (read_half, mut write_half) = tokio::io::split(stream);
+    let mut reader = BufReader::new(read_half);
+    let mut buf = Vec::new();
+    let mut worker_id: Option<WorkerKey> = None;
+    let mut hello: Option<WorkerMessage> = None;
+    let mut authenticated = auth_token.is_none();
+    let mut rate = MessageRate::new(limits.max_messages_per_sec);
+    let mut warned_utf8 = false;
     
     loop {
-        line.clear();
-        match reader.read_line(&mut line).await {
-            Ok(0) => {
-                // Connection closed
-                if let Some(id) = &worker_id {
-                    let mut workers_guard = workers.write().await;
-                    if let Some(worker) = workers_guard.get_mut(id) {
-                        worker.connected = false;
-                    }
-                    debug!("Worker {} disconnected", id);
-                }
+        buf.clear();
+        match read_bounded_line(&mut reader, &mut buf, limits.max_line_length).await {
+            // Connection closed
+            Ok(0) => break,
+            Ok(_) if !buf.ends_with(b"\n") && buf.len() > limits.max_line_length => {
+                error!(
+                    "Dropping connection that sent a line over {} bytes",
+                    limits.max_line_length
+                );
                 break;
             }
             Ok(_) => {
+                // Tools piping through odd shells or locales can let a
+                // stray byte through; one bad filename shouldn't cost the
+                // whole connection
+                let line = String::from_utf8_lossy(&buf);
+                if let std::borrow::Cow::Owned(_) = line {
+                    if limits.strict_utf8 {
+                        error!("Dropping connection that sent a line that isn't UTF-8");
+                        break;
+                    }
+                    if !warned_utf8 {
+                        warn!("Replacing invalid UTF-8 in messages from a connection");
+                        warned_utf8 = true;
+                    }
+                }
+                // Also drops the \r of CRLF line endings
                 let line = line.trim();
                 if line.is_empty() {
                     continue;
                 }
                 
+                if !rate.allow() {
+                    error!(
+                        "Dropping connection that sent more than {} messages a second",
+                        limits.max_messages_per_sec.unwrap_or_default()
+                    );
+                    break;
+                }
+                
+                // Checked before logging so the token never reaches the logs
+                if !authenticated {
+                    let presented = match line.split_once(char::is_whitespace) {
+                        Some(("AUTH", token)) => token.trim(),
+                        _ => "",
+                    };
+                    match &auth_token {
+                        Some(token) if tokens_match(presented, token) => {
+                            authenticated = true;
+                            continue;
+                        }
+                        _ => {
+                            warn!("Dropping connection that failed authentication");
+                            break;
+                        }
+                    }
+                }
+                
                 debug!("Received message: {}", line);
                 
+                if line == SUBSCRIBE_COMMAND && worker_id.is_none() {
+                    return stream_to_subscriber(write_half, message_tx.subscribe()).await;
+                }
+                
+                if line == PING_COMMAND {
+                    write_half.write_all(b"PONG\n").await?;
+                    continue;
+                }
+                
+                if let Some(keep_open) = parse_query_command(line) {
+                    let snapshot = WorkersSnapshot {
+                        workers: workers.read().await.clone(),
+                        started_at: Some(started_at),
+                    };
+                    let mut payload = serde_json::to_string(&snapshot)
+                        .context("Failed to serialize worker snapshot")?;
+                    payload.push('\n');
+                    write_half.write_all(payload.as_bytes()).await?;
+                    if keep_open {
+                        continue;
+                    }
+                    break;
+                }
+                
+                if let Some((tool, directory)) = parse_cancel_command(line) {
+                    cancel_worker(
+                        tool,
+                        directory,
+                        &workers,
+                        &counters,
+                        &message_tx,
+                        control_tx.as_ref(),
+                    )
+                    .await;
+                    continue;
+                }
+                
                 if let Ok(message) = parse_worker_message(line) {
-                    let id = format!("{}:{}", 
-                        message.tool, 
-                        message.directory.as_deref().unwrap_or("unknown")
-                    );
+                    // A HELLO without tool/dir can't be keyed yet, so hold
+                    // on to its host and pid until this connection says
+                    // which worker it belongs to
+                    if matches!(message.message_type, MessageType::Hello)
+                        && message.directory.is_none()
+                    {
+                        hello = Some(message);
+                        continue;
+                    }
+                    
+                    let Some(id) = WorkerKey::for_message(&message) else {
+                        warn!("Ignoring {:?} from {} without a directory", message.message_type, message.tool);
+                        continue;
+                    };
+                    
+                    // A tool leaving isn't news for subscribers; they hear
+                    // about it when retention prunes the worker
+                    if matches!(message.message_type, MessageType::Bye) {
+                        if let Some(worker) = workers.write().await.get_mut(&id) {
+                            worker.connected = false;
+                            worker.finished_at.get_or_insert_with(SystemTime::now);
+                        }
+                        debug!("Worker {} said BYE", id);
+                        continue;
+                    }
                     
                     // Update worker state
                     {
-                        let mut workers_guard = workers.write().await;
-                        let worker = workers_guard.entry(id.clone()).or_insert_with(|| {
-                            WorkerState {
-                                tool: message.tool.clone(),
-                                directory: message.directory.clone(),
-                                status: "unknown".to_string(),
-                                last_message: None,
-                                bytes_transferred: None,
-                                host: None,
-                                pid: None,
-                                connected: true,
-                            }
-                        });
-                        
-                        match message.message_type {
-                            MessageType::Hello => {
-                                worker.host = message.host.clone();
-                                worker.pid = message.pid;
-                                worker.connected = true;
-                                worker.status = "connected".to_string();
-                            }
-                            MessageType::Start => {
-                                worker.status = "running".to_string();
-                            }
-                            MessageType::Progress => {
-                                worker.last_message = message.message.clone();
-                                if let Some(bytes) = message.bytes {
-                                    worker.bytes_transferred = Some(bytes);
-                                }
-                            }
-                            MessageType::Stop => {
-                                worker.status = message.status.clone().unwrap_or_else(|| "completed".to_string());
-                                if let Some(bytes) = message.bytes {
-                                    worker.bytes_transferred = Some(bytes);
-                                }
-                            }
+                        let mut workers = workers.write().await;
+                        if apply_message(&mut workers, &id, &message, hello.take()) {
+                            lock_counters(&counters).record_finished(&workers[&id]);
                         }
                     }
                     
@@ -185,9 +902,394 @@ async fn handle_worker_connection(
         }
     }
     
+    if let Some(id) = &worker_id {
+        if let Some(worker) = workers.write().await.get_mut(id) {
+            worker.connected = false;
+        }
+        debug!("Worker {} disconnected", id);
+    }
+    
     Ok(())
 }
 
+/// The counters stay usable even if a holder panicked mid-update; they're
+/// plain numbers, so there's no invariant to break.
+fn lock_counters(counters: &Mutex<Counters>) -> std::sync::MutexGuard<'_, Counters> {
+    counters.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Fold a message into the daemon's view of worker `id`, creating it on
+/// first sight. `hello` is a keyless HELLO held from earlier on the same
+/// connection. Returns whether the message finished the worker, so it's
+/// counted once however many STOPs follow.
+pub(crate) fn apply_message(
+    workers: &mut HashMap<WorkerKey, WorkerState>,
+    id: &WorkerKey,
+    message: &WorkerMessage,
+    hello: Option<WorkerMessage>,
+) -> bool {
+    let worker = workers.entry(id.clone()).or_insert_with(|| {
+        WorkerState {
+            tool: message.tool.clone(),
+            directory: message.directory.clone(),
+            status: "unknown".to_string(),
+            last_message: None,
+            bytes_transferred: None,
+            bytes_total: None,
+            files: None,
+            host: None,
+            pid: None,
+            connected: true,
+            started_at: None,
+            finished_at: None,
+            attempts: 0,
+            max_attempts: None,
+        }
+    });
+    
+    if let Some(hello) = hello {
+        worker.host = hello.host;
+        worker.pid = hello.pid;
+    }
+    
+    match message.message_type {
+        MessageType::Hello => {
+            worker.host = message.host.clone();
+            worker.pid = message.pid;
+            worker.connected = true;
+            // Tools re-send HELLO after reconnecting mid-run
+            if worker.status == "unknown" {
+                worker.status = "connected".to_string();
+            }
+        }
+        // A second START while running is another process on
+        // the same directory; keep the first one's timing
+        MessageType::Start if worker.status == "running" => {
+            warn!("Duplicate START for running worker {}", id);
+        }
+        MessageType::Start => {
+            worker.status = "running".to_string();
+            worker.started_at = Some(SystemTime::now());
+            worker.finished_at = None;
+        }
+        MessageType::Progress => {
+            if is_attempt_message(message.message.as_deref()) {
+                worker.attempts += 1;
+                if let Some((_, limit)) = parse_attempt(message.message.as_deref()) {
+                    worker.max_attempts = Some(limit);
+                }
+            }
+            worker.last_message = message.message.clone();
+            if let Some(bytes) = message.bytes {
+                worker.bytes_transferred = Some(bytes);
+            }
+            if message.bytes_total.is_some() {
+                worker.bytes_total = message.bytes_total;
+            }
+            if message.files.is_some() {
+                worker.files = message.files;
+            }
+        }
+        // Keep "cancelled" when the dying worker reports its own STOP, and
+        // "failed" when a worker the daemon timed out says it was interrupted
+        MessageType::Stop if worker.status == "cancelled" => {}
+        MessageType::Stop
+            if worker.status == "failed" && message.status.as_deref() == Some("interrupted") => {}
+        MessageType::Stop => {
+            let finishing = worker.finished_at.is_none();
+            worker.status = message.status.clone().unwrap_or_else(|| "completed".to_string());
+            worker.finished_at = Some(SystemTime::now());
+            if let Some(bytes) = message.bytes {
+                worker.bytes_transferred = Some(bytes);
+            }
+            if message.bytes_total.is_some() {
+                worker.bytes_total = message.bytes_total;
+            }
+            if message.files.is_some() {
+                worker.files = message.files;
+            }
+            return finishing;
+        }
+        MessageType::Bye | MessageType::Notice => {}
+    }
+    false
+}
+
+/// Read up to and including the next newline, but no more than one byte
+/// past `max`, so a client that never sends a newline can't grow the
+/// buffer without bound. A full buffer with no newline means the line was
+/// too long.
+async fn read_bounded_line<R>(reader: &mut R, buf: &mut Vec<u8>, max: usize) -> std::io::Result<usize>
+where
+    R: AsyncBufRead + Unpin,
+{
+    reader.take(max as u64 + 1).read_until(b'\n', buf).await
+}
+
+/// Fixed one-second window counting a connection's messages.
+struct MessageRate {
+    limit: Option<u32>,
+    window_start: Instant,
+    count: u32,
+}
+
+impl MessageRate {
+    fn new(limit: Option<u32>) -> Self {
+        Self { limit, window_start: Instant::now(), count: 0 }
+    }
+    
+    /// Count one message, returning false once the window's limit is exceeded.
+    fn allow(&mut self) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            self.window_start = Instant::now();
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count <= limit
+    }
+}
+
+/// Forward a CANCEL to the worker manager and tell TUI clients right away;
+/// the worker itself may take a moment to die.
+async fn cancel_worker(
+    tool: String,
+    directory: String,
+    workers: &RwLock<HashMap<WorkerKey, WorkerState>>,
+    counters: &Mutex<Counters>,
+    message_tx: &broadcast::Sender<WorkerMessage>,
+    control_tx: Option<&mpsc::UnboundedSender<ControlMessage>>,
+) {
+    let Some(control_tx) = control_tx else {
+        warn!("Ignoring CANCEL for {}:{}, no migration is running", tool, directory);
+        return;
+    };
+    
+    info!("Cancel requested for {}:{}", tool, directory);
+    if control_tx
+        .send(ControlMessage::Cancel { tool: tool.clone(), directory: directory.clone() })
+        .is_err()
+    {
+        warn!("Worker manager has stopped, cannot cancel {}:{}", tool, directory);
+        return;
+    }
+    
+    if let Some(worker) = workers.write().await.get_mut(&WorkerKey::new(&tool, &directory)) {
+        let finishing = worker.finished_at.is_none();
+        worker.status = "cancelled".to_string();
+        worker.finished_at = Some(SystemTime::now());
+        if finishing {
+            lock_counters(counters).record_finished(worker);
+        }
+    }
+    
+    let _ = message_tx.send(WorkerMessage {
+        message_type: MessageType::Stop,
+        tool,
+        directory: Some(directory),
+        status: Some("cancelled".to_string()),
+        bytes: None,
+        bytes_total: None,
+        files: None,
+        message: None,
+        host: None,
+        pid: None,
+    });
+}
+
+/// Parse `QUERY [keep_open]`, returning whether to keep the connection open.
+fn parse_query_command(line: &str) -> Option<bool> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != QUERY_COMMAND {
+        return None;
+    }
+    Some(parts.next() == Some("keep_open"))
+}
+
+/// Ask the daemon for a one-shot snapshot of its workers.
+pub async fn query_workers(addr: &ListenAddr, auth_token: Option<&str>) -> Result<WorkersSnapshot> {
+    let stream = connect(addr)
+        .await
+        .context(DaemonUnreachable(addr.to_string()))?;
+    let (read_half, mut write_half) = tokio::io::split(stream);
+    
+    let mut request = String::new();
+    if let Some(token) = auth_token {
+        request.push_str(&format!("AUTH {}\n", token));
+    }
+    request.push_str(&format!("{}\n", QUERY_COMMAND));
+    write_half.write_all(request.as_bytes()).await?;
+    
+    let mut line = String::new();
+    BufReader::new(read_half)
+        .read_line(&mut line)
+        .await
+        .context("Failed to read snapshot from daemon")?;
+    if line.trim().is_empty() {
+        anyhow::bail!("Daemon closed the connection without replying (bad auth token?)");
+    }
+    
+    serde_json::from_str(line.trim()).context("Failed to parse snapshot from daemon")
+}
+
+/// Whether the daemon at `addr` answers PING within `timeout`.
+pub async fn ping(addr: &ListenAddr, auth_token: Option<&str>, timeout: Duration) -> bool {
+    let exchange = async {
+        let stream = connect(addr).await?;
+        let (read_half, mut write_half) = tokio::io::split(stream);
+        
+        let mut request = String::new();
+        if let Some(token) = auth_token {
+            request.push_str(&format!("AUTH {}\n", token));
+        }
+        request.push_str(&format!("{}\n", PING_COMMAND));
+        write_half.write_all(request.as_bytes()).await?;
+        
+        let mut line = String::new();
+        BufReader::new(read_half).read_line(&mut line).await?;
+        anyhow::Ok(line.trim() == "PONG")
+    };
+    matches!(tokio::time::timeout(timeout, exchange).await, Ok(Ok(true)))
+}
+
+/// Poll the daemon with PING until it answers or `timeout` passes.
+/// Returns whether it became ready.
+pub async fn wait_until_ready(
+    addr: &ListenAddr,
+    auth_token: Option<&str>,
+    timeout: Duration,
+) -> bool {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if ping(addr, auth_token, POLL_INTERVAL).await {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Parse `CANCEL tool=<tool> dir=<dir>` into its tool and directory.
+pub fn parse_cancel_command(line: &str) -> Option<(String, String)> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != CANCEL_COMMAND {
+        return None;
+    }
+    
+    let mut tool = None;
+    let mut directory = None;
+    for part in parts {
+        if let Some(value) = part.strip_prefix("tool=") {
+            tool = Some(unescape_value(value));
+        } else if let Some(value) = part.strip_prefix("dir=") {
+            directory = Some(unescape_value(value));
+        }
+    }
+    
+    Some((tool?, directory?))
+}
+
+/// freight-migrate announces each rsync attempt as `Attempt N/M`.
+pub fn is_attempt_message(message: Option<&str>) -> bool {
+    message.is_some_and(|m| m.split_whitespace().next() == Some("Attempt"))
+}
+
+/// `(N, M)` from an `Attempt N/M` message.
+pub fn parse_attempt(message: Option<&str>) -> Option<(u32, u32)> {
+    let (attempt, limit) = message?.strip_prefix("Attempt ")?.trim().split_once('/')?;
+    Some((attempt.parse().ok()?, limit.parse().ok()?))
+}
+
+/// Percent-encode a protocol value so it survives whitespace splitting.
+/// Mirrors `socket_escape` in lib/socket.sh.
+pub fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' | ' ' | '\t' | '\n' | '\r' => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Decode `%XX` sequences written by `escape_value`. Anything that isn't a
+/// valid escape is kept verbatim, so unescaped values from older tools
+/// still parse.
+pub fn unescape_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub fn auth_token_from_env() -> Option<String> {
+    std::env::var(AUTH_TOKEN_ENV).ok().filter(|token| !token.is_empty())
+}
+
+/// Compare without short-circuiting so response timing doesn't leak how much
+/// of the token a client guessed correctly.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Forward every broadcast worker message to a TUI client as a JSON line
+/// until the client goes away.
+async fn stream_to_subscriber<W: AsyncWriteExt + Unpin>(
+    mut writer: W,
+    mut message_rx: broadcast::Receiver<WorkerMessage>,
+) -> Result<()> {
+    info!("TUI client subscribed");
+    
+    loop {
+        let message = match message_rx.recv().await {
+            Ok(message) => message,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("TUI client lagged, skipped {} messages", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        
+        let mut payload = serde_json::to_string(&message)
+            .context("Failed to serialize worker message")?;
+        payload.push('\n');
+        
+        if let Err(e) = writer.write_all(payload.as_bytes()).await {
+            debug!("TUI client disconnected: {}", e);
+            break;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Parse one protocol line. Fields may be separated by any run of spaces
+/// or tabs, and a trailing `\r` from a CRLF line ending is ignored.
 fn parse_worker_message(line: &str) -> Result<WorkerMessage> {
     let parts: Vec<&str> = line.split_whitespace().collect();
     
@@ -197,24 +1299,32 @@ fn parse_worker_message(line: &str) -> Result<WorkerMessage> {
     
     match parts[0] {
         "HELLO" => {
-            // HELLO freight/0.1.0 host=hostname pid=1234
+            // HELLO freight/0.1.0 host=hostname pid=1234 tool=scan dir=user/
             let mut host = None;
             let mut pid = None;
+            let mut tool = "unknown".to_string();
+            let mut directory = None;
             
-            for part in &parts[2..] {
+            for part in parts.iter().skip(2) {
                 if let Some(value) = part.strip_prefix("host=") {
-                    host = Some(value.to_string());
+                    host = Some(unescape_value(value));
                 } else if let Some(value) = part.strip_prefix("pid=") {
                     pid = value.parse().ok();
+                } else if let Some(value) = part.strip_prefix("tool=") {
+                    tool = unescape_value(value);
+                } else if let Some(value) = part.strip_prefix("dir=") {
+                    directory = Some(unescape_value(value));
                 }
             }
             
             Ok(WorkerMessage {
                 message_type: MessageType::Hello,
-                tool: "unknown".to_string(),
-                directory: None,
+                tool,
+                directory,
                 status: None,
                 bytes: None,
+                bytes_total: None,
+                files: None,
                 message: None,
                 host,
                 pid,
@@ -227,9 +1337,9 @@ fn parse_worker_message(line: &str) -> Result<WorkerMessage> {
             
             for part in &parts[1..] {
                 if let Some(value) = part.strip_prefix("tool=") {
-                    tool = value.to_string();
+                    tool = unescape_value(value);
                 } else if let Some(value) = part.strip_prefix("dir=") {
-                    directory = Some(value.to_string());
+                    directory = Some(unescape_value(value));
                 }
             }
             
@@ -239,27 +1349,35 @@ fn parse_worker_message(line: &str) -> Result<WorkerMessage> {
                 directory,
                 status: None,
                 bytes: None,
+                bytes_total: None,
+                files: None,
                 message: None,
                 host: None,
                 pid: None,
             })
         }
         "PROGRESS" => {
-            // PROGRESS tool=scan dir=user/ msg=scanning...
+            // PROGRESS tool=scan dir=user/ msg=scanning... bytes=512 bytes_total=4096 files=12
             let mut tool = "unknown".to_string();
             let mut directory = None;
             let mut message = None;
             let mut bytes = None;
+            let mut bytes_total = None;
+            let mut files = None;
             
             for part in &parts[1..] {
                 if let Some(value) = part.strip_prefix("tool=") {
-                    tool = value.to_string();
+                    tool = unescape_value(value);
                 } else if let Some(value) = part.strip_prefix("dir=") {
-                    directory = Some(value.to_string());
+                    directory = Some(unescape_value(value));
                 } else if let Some(value) = part.strip_prefix("msg=") {
-                    message = Some(value.to_string());
+                    message = Some(unescape_value(value));
                 } else if let Some(value) = part.strip_prefix("bytes=") {
                     bytes = value.parse().ok();
+                } else if let Some(value) = part.strip_prefix("bytes_total=") {
+                    bytes_total = value.parse().ok();
+                } else if let Some(value) = part.strip_prefix("files=") {
+                    files = value.parse().ok();
                 }
             }
             
@@ -269,30 +1387,38 @@ fn parse_worker_message(line: &str) -> Result<WorkerMessage> {
                 directory,
                 status: None,
                 bytes,
+                bytes_total,
+                files,
                 message,
                 host: None,
                 pid: None,
             })
         }
         "STOP" => {
-            // STOP tool=scan dir=user/ status=ok bytes=1234 msg=completed
+            // STOP tool=scan dir=user/ status=ok bytes=1234 bytes_total=1234 files=40 msg=completed
             let mut tool = "unknown".to_string();
             let mut directory = None;
             let mut status = None;
             let mut bytes = None;
+            let mut bytes_total = None;
+            let mut files = None;
             let mut message = None;
             
             for part in &parts[1..] {
                 if let Some(value) = part.strip_prefix("tool=") {
-                    tool = value.to_string();
+                    tool = unescape_value(value);
                 } else if let Some(value) = part.strip_prefix("dir=") {
-                    directory = Some(value.to_string());
+                    directory = Some(unescape_value(value));
                 } else if let Some(value) = part.strip_prefix("status=") {
-                    status = Some(value.to_string());
+                    status = Some(unescape_value(value));
                 } else if let Some(value) = part.strip_prefix("bytes=") {
                     bytes = value.parse().ok();
+                } else if let Some(value) = part.strip_prefix("bytes_total=") {
+                    bytes_total = value.parse().ok();
+                } else if let Some(value) = part.strip_prefix("files=") {
+                    files = value.parse().ok();
                 } else if let Some(value) = part.strip_prefix("msg=") {
-                    message = Some(value.to_string());
+                    message = Some(unescape_value(value));
                 }
             }
             
@@ -302,11 +1428,316 @@ fn parse_worker_message(line: &str) -> Result<WorkerMessage> {
                 directory,
                 status,
                 bytes,
+                bytes_total,
+                files,
                 message,
                 host: None,
                 pid: None,
             })
         }
+        "BYE" => {
+            // BYE tool=scan dir=user/
+            let mut tool = "unknown".to_string();
+            let mut directory = None;
+            
+            for part in &parts[1..] {
+                if let Some(value) = part.strip_prefix("tool=") {
+                    tool = unescape_value(value);
+                } else if let Some(value) = part.strip_prefix("dir=") {
+                    directory = Some(unescape_value(value));
+                }
+            }
+            
+            Ok(WorkerMessage {
+                message_type: MessageType::Bye,
+                tool,
+                directory,
+                status: None,
+                bytes: None,
+                bytes_total: None,
+                files: None,
+                message: None,
+                host: None,
+                pid: None,
+            })
+        }
         _ => Err(anyhow::anyhow!("Unknown message type: {}", parts[0])),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::render_metrics;
+
+    async fn publish_line(server: &SocketServer, line: &str) {
+        server.publish(parse_worker_message(line).unwrap()).await;
+    }
+
+    fn metric(metrics: &str, name: &str) -> String {
+        metrics
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("{} missing from:\n{}", name, metrics))
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn counters_survive_pruning_and_repeated_stops() {
+        let (server, _rx) = SocketServer::new(16);
+        publish_line(&server, "START tool=migrate dir=/src/a").await;
+        publish_line(&server, "PROGRESS tool=migrate dir=/src/a msg=Attempt%201/3").await;
+        publish_line(&server, "PROGRESS tool=migrate dir=/src/a msg=Attempt%202/3").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/a status=ok bytes=100").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/a status=ok bytes=100").await;
+        publish_line(&server, "START tool=migrate dir=/src/b").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/b status=ok bytes=50").await;
+
+        let before = {
+            let workers = server.workers_handle();
+            let counters = server.counters_handle().lock().unwrap().clone();
+            let workers = workers.read().await;
+            render_metrics(&workers, &counters)
+        };
+        assert_eq!(metric(&before, "freight_bytes_transferred_total"), "150");
+        assert_eq!(metric(&before, "freight_retries_total"), "1");
+        assert_eq!(metric(&before, "freight_directory_duration_seconds_count"), "2");
+        assert_eq!(metric(&before, "freight_workers{status=\"ok\"}"), "2");
+
+        assert_eq!(server.prune_finished(Duration::ZERO).await, 2);
+        let after = {
+            let workers = server.workers_handle();
+            let counters = server.counters_handle().lock().unwrap().clone();
+            let workers = workers.read().await;
+            render_metrics(&workers, &counters)
+        };
+        assert_eq!(metric(&after, "freight_bytes_transferred_total"), "150");
+        assert_eq!(metric(&after, "freight_retries_total"), "1");
+        assert_eq!(metric(&after, "freight_directory_duration_seconds_count"), "2");
+        assert!(!after.contains("freight_workers{"));
+    }
+
+    #[tokio::test]
+    async fn colon_and_space_directories_are_kept_apart() {
+        let (server, _rx) = SocketServer::new(16);
+        publish_line(&server, "START tool=migrate dir=/src/a:b").await;
+        publish_line(&server, "START tool=migrate dir=/src/a%20b").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/a%20b status=failed").await;
+        publish_line(&server, "START tool=migrate dir=unknown").await;
+        publish_line(&server, "START tool=migrate").await;
+
+        let workers = server.get_workers().await;
+        assert_eq!(workers.len(), 3);
+        assert_eq!(workers[&WorkerKey::new("migrate", "/src/a:b")].status, "running");
+        assert_eq!(workers[&WorkerKey::new("migrate", "/src/a b")].status, "failed");
+        assert_eq!(workers[&WorkerKey::new("migrate", "unknown")].status, "running");
+    }
+
+    #[tokio::test]
+    async fn saved_state_round_trips_awkward_directory_names() {
+        let (server, _rx) = SocketServer::new(16);
+        publish_line(&server, "START tool=scan dir=/src/a:b").await;
+        publish_line(&server, "START tool=scan dir=/src/a%20b:c").await;
+
+        let state = tempfile::tempdir().unwrap();
+        let path = state.path().join("state.json");
+        server.save_state(&path).await.unwrap();
+        let restored = load_workers(&path).unwrap();
+
+        assert_eq!(restored.len(), 2);
+        for key in [WorkerKey::new("scan", "/src/a:b"), WorkerKey::new("scan", "/src/a b:c")] {
+            assert_eq!(restored[&key].directory.as_deref(), key.directory.to_str());
+        }
+    }
+
+    #[test]
+    fn snapshots_from_older_daemons_still_parse() {
+        let json = r#"{"workers":{
+            "scan:/src/a:b":{"tool":"scan","directory":"/src/a:b","status":"ok",
+                "last_message":null,"bytes_transferred":null,"host":null,"pid":null,
+                "connected":false},
+            "unknown:unknown":{"tool":"unknown","directory":null,"status":"connected",
+                "last_message":null,"bytes_transferred":null,"host":null,"pid":null,
+                "connected":true}
+        }}"#;
+        let snapshot: WorkersSnapshot = serde_json::from_str(json).unwrap();
+
+        assert_eq!(snapshot.workers.len(), 1);
+        assert_eq!(snapshot.workers[&WorkerKey::new("scan", "/src/a:b")].status, "ok");
+    }
+
+    #[tokio::test]
+    async fn restored_state_keeps_every_field_but_marks_workers_disconnected() {
+        let (server, _rx) = SocketServer::new(16);
+        publish_line(&server, "HELLO freight/0.1.0 host=nas1 pid=4242 tool=migrate dir=/src/a").await;
+        publish_line(&server, "START tool=migrate dir=/src/a").await;
+        publish_line(&server, "PROGRESS tool=migrate dir=/src/a msg=Attempt%201/3").await;
+        publish_line(&server, "PROGRESS tool=migrate dir=/src/a msg=Attempt%202/3 bytes=10 files=3").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/a status=ok bytes=2048 files=7").await;
+        publish_line(&server, "START tool=scan dir=/src/b").await;
+        let saved = server.get_workers().await;
+
+        let state = tempfile::tempdir().unwrap();
+        let path = state.path().join("nested/state.json");
+        server.save_state(&path).await.unwrap();
+        let (restarted, _rx) = SocketServer::new(16);
+        assert_eq!(restarted.load_state(&path).await.unwrap(), 2);
+        let restored = restarted.get_workers().await;
+
+        assert_eq!(restored.len(), saved.len());
+        for (key, before) in &saved {
+            let after = &restored[key];
+            assert_eq!(
+                (&after.tool, &after.directory, &after.status, &after.last_message),
+                (&before.tool, &before.directory, &before.status, &before.last_message)
+            );
+            assert_eq!(
+                (after.bytes_transferred, after.files, after.attempts, after.max_attempts),
+                (before.bytes_transferred, before.files, before.attempts, before.max_attempts)
+            );
+            assert_eq!((&after.host, after.pid), (&before.host, before.pid));
+            assert_eq!((after.started_at, after.finished_at), (before.started_at, before.finished_at));
+            assert!(!after.connected);
+        }
+        let finished = &restored[&WorkerKey::new("migrate", "/src/a")];
+        assert_eq!(finished.host.as_deref(), Some("nas1"));
+        assert_eq!(finished.bytes_transferred, Some(2048));
+        assert_eq!(finished.attempts, 2);
+    }
+
+    #[test]
+    fn cancel_command_parses_tool_and_escaped_directory() {
+        let line = format!("CANCEL tool=migrate dir={}", escape_value("/data/my projects"));
+        assert_eq!(
+            parse_cancel_command(&line),
+            Some(("migrate".to_string(), "/data/my projects".to_string()))
+        );
+        assert_eq!(
+            parse_cancel_command("CANCEL dir=/data/a tool=scan"),
+            Some(("scan".to_string(), "/data/a".to_string()))
+        );
+
+        assert_eq!(parse_cancel_command("CANCEL tool=migrate"), None);
+        assert_eq!(parse_cancel_command("CANCEL dir=/data/a"), None);
+        assert_eq!(parse_cancel_command("STOP tool=migrate dir=/data/a"), None);
+        assert_eq!(parse_cancel_command(""), None);
+    }
+
+    #[test]
+    fn scan_stop_carries_bytes_total() {
+        let message =
+            parse_worker_message("STOP tool=scan dir=/src/a status=ok bytes_total=4096 files=12")
+                .unwrap();
+
+        assert!(matches!(message.message_type, MessageType::Stop));
+        assert_eq!(message.tool, "scan");
+        assert_eq!(message.directory.as_deref(), Some("/src/a"));
+        assert_eq!(message.status.as_deref(), Some("ok"));
+        assert_eq!(message.bytes_total, Some(4096));
+        assert_eq!(message.bytes, None);
+        assert_eq!(message.files, Some(12));
+    }
+
+    #[test]
+    fn stop_bytes_total_is_optional_and_ignored_when_malformed() {
+        let stop = |line| parse_worker_message(line).unwrap();
+
+        assert_eq!(stop("STOP tool=migrate dir=/src/a status=ok bytes=10").bytes_total, None);
+        assert_eq!(stop("STOP tool=scan dir=/src/a status=ok bytes_total=lots").bytes_total, None);
+        assert_eq!(stop("STOP tool=scan dir=/src/a bytes_total=-1").bytes_total, None);
+        let both = stop("STOP tool=migrate dir=/src/a status=ok bytes=10 bytes_total=20");
+        assert_eq!((both.bytes, both.bytes_total), (Some(10), Some(20)));
+        assert_eq!(
+            stop(&format!("STOP tool=scan dir=/src/a bytes_total={}", u64::MAX)).bytes_total,
+            Some(u64::MAX)
+        );
+    }
+
+    #[tokio::test]
+    async fn scan_total_is_kept_on_the_worker_state() {
+        let (server, _) = SocketServer::new(16);
+
+        publish_line(&server, "START tool=scan dir=/src/a").await;
+        publish_line(&server, "STOP tool=scan dir=/src/a status=ok bytes_total=4096").await;
+
+        let workers = server.get_workers().await;
+        assert_eq!(workers[&WorkerKey::new("scan", "/src/a")].bytes_total, Some(4096));
+    }
+
+    #[tokio::test]
+    async fn second_start_for_a_running_worker_keeps_the_first() {
+        let (server, _) = SocketServer::new(16);
+        publish_line(&server, "START tool=migrate dir=/src/a").await;
+        let first = server.get_workers().await[&WorkerKey::new("migrate", "/src/a")].started_at;
+
+        publish_line(&server, "START tool=migrate dir=/src/a").await;
+
+        let workers = server.get_workers().await;
+        assert_eq!(workers.len(), 1);
+        assert_eq!(workers[&WorkerKey::new("migrate", "/src/a")].started_at, first);
+    }
+
+    #[test]
+    fn files_count_is_parsed_from_progress_and_stop() {
+        let parse = |line| parse_worker_message(line).unwrap().files;
+
+        assert_eq!(parse("PROGRESS tool=migrate dir=/src/a bytes=10 files=1234"), Some(1234));
+        assert_eq!(parse("STOP tool=migrate dir=/src/a status=ok files=0"), Some(0));
+        assert_eq!(parse("PROGRESS tool=migrate dir=/src/a bytes=10"), None);
+        assert_eq!(parse("PROGRESS tool=migrate dir=/src/a files=1,234"), None);
+        assert_eq!(parse("STOP tool=migrate dir=/src/a status=ok files=-3"), None);
+    }
+
+    #[tokio::test]
+    async fn loading_state_replaces_the_workers_already_known() {
+        let (earlier, _rx) = SocketServer::new(16);
+        publish_line(&earlier, "START tool=scan dir=/src/a").await;
+        let state = tempfile::tempdir().unwrap();
+        let path = state.path().join("state.json");
+        earlier.save_state(&path).await.unwrap();
+
+        let (server, _rx) = SocketServer::new(16);
+        publish_line(&server, "START tool=migrate dir=/src/stale").await;
+        assert_eq!(server.load_state(&path).await.unwrap(), 1);
+
+        let workers = server.get_workers().await;
+        assert_eq!(workers.keys().collect::<Vec<_>>(), [&WorkerKey::new("scan", "/src/a")]);
+    }
+
+    #[test]
+    fn each_listen_address_has_its_own_state_file() {
+        let unix = state_path(&ListenAddr::Unix("/run/freight/a.sock".into()));
+        assert_eq!(unix, Path::new("/run/freight/a.sock.state.json"));
+
+        let paths = [
+            unix,
+            state_path(&ListenAddr::Unix("/run/freight/b.sock".into())),
+            state_path(&ListenAddr::Abstract("freight-daemon".into())),
+            state_path(&ListenAddr::Tcp("127.0.0.1:7420".into())),
+            state_path(&ListenAddr::Tcp("127.0.0.1:7421".into())),
+        ];
+        let distinct: std::collections::HashSet<_> = paths.iter().collect();
+        assert_eq!(distinct.len(), paths.len());
+        assert!(paths[3].starts_with(std::env::temp_dir()), "{}", paths[3].display());
+    }
+
+    #[tokio::test]
+    async fn a_timed_out_worker_stays_failed_when_it_reports_being_interrupted() {
+        let (server, _rx) = SocketServer::new(16);
+        publish_line(&server, "START tool=migrate dir=/src/a").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/a status=failed").await;
+        publish_line(&server, "STOP tool=migrate dir=/src/a status=interrupted").await;
+
+        let workers = server.get_workers().await;
+        assert_eq!(workers[&WorkerKey::new("migrate", "/src/a")].status, "failed");
+    }
+
+    #[test]
+    fn parser_ignores_crlf_and_runs_of_spaces_and_tabs() {
+        let line = "STOP \ttool=migrate\t\tdir=/src/a  status=ok\r";
+        let message = parse_worker_message(line).unwrap();
+        assert!(matches!(message.message_type, MessageType::Stop));
+        assert_eq!(message.directory.as_deref(), Some("/src/a"));
+        assert_eq!(message.status.as_deref(), Some("ok"));
+    }
+}
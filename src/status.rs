@@ -0,0 +1,58 @@
+use crate::logging::use_color;
+use crate::socket::WorkersSnapshot;
+use crate::tui::{format_bytes, format_duration};
+use chrono::{DateTime, Local};
+use crossterm::style::Stylize;
+
+/// Print a snapshot as one line per worker, sorted by tool then directory,
+/// after the daemon's uptime if it reported when it started. Statuses are
+/// colored when stdout is a terminal and `NO_COLOR` is unset.
+pub fn print_status(snapshot: &WorkersSnapshot) {
+    if let (Some(started), Some(uptime)) = (snapshot.started_at, snapshot.uptime()) {
+        let started: DateTime<Local> = started.into();
+        println!(
+            "Daemon uptime: {} (since {})",
+            format_duration(uptime),
+            started.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
+    if snapshot.workers.is_empty() {
+        println!("No workers have reported to the daemon");
+        return;
+    }
+
+    let mut workers: Vec<_> = snapshot.workers.values().collect();
+    workers.sort_by(|a, b| (&a.tool, &a.directory).cmp(&(&b.tool, &b.directory)));
+    let color = use_color(&std::io::stdout());
+
+    for worker in workers {
+        let bytes = worker
+            .bytes_transferred
+            .map(format_bytes)
+            .unwrap_or_default();
+        println!(
+            "{:8} {} {:>10}  {}",
+            worker.tool,
+            status_cell(&worker.status, color),
+            bytes,
+            worker.directory.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+/// The status column, padded before styling so the escapes don't count
+/// towards its width.
+fn status_cell(status: &str, color: bool) -> String {
+    let cell = format!("{:10}", status);
+    if !color {
+        return cell;
+    }
+    match status {
+        "running" | "connected" => cell.cyan().to_string(),
+        "ok" | "completed" => cell.green().to_string(),
+        "failed" => cell.red().to_string(),
+        "cancelled" => cell.yellow().to_string(),
+        _ => cell,
+    }
+}
@@ -1,37 +1,443 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Sparkline, Tabs},
     Frame, Terminal,
 };
-use std::io;
-use std::time::{Duration, Instant};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::net::UnixStream;
-use tracing::{error, info};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use crate::socket::{
+    self, auth_token_from_env, escape_value, parse_attempt, DaemonStream, ListenAddr,
+    MessageType, NoticeLevel, WorkersSnapshot, CANCEL_COMMAND, QUERY_COMMAND, SUBSCRIBE_COMMAND,
+};
+use crate::export::{self, ExportFormat, ExportRow};
+use crate::{Config, WorkerMessage};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Dashboard tick rate when there is no project config to read it from.
+pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(250);
+/// Stale-data threshold when there is no project config to read it from.
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(60);
+/// Updates applied per frame when there is no project config to read it from.
+pub const DEFAULT_MAX_UPDATES_PER_FRAME: usize = 1000;
+/// Worker names when there is no project config to read a template from:
+/// the directory and its parent.
+pub const DEFAULT_NAME_TEMPLATE: &str = "{parent}/{name}";
+/// Reconnect interval when there is no project config to read it from.
+pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+/// How long the first connect waits for a starting daemon to answer PING.
+pub const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the dashboard finds the daemon and how it behaves while connected.
+#[derive(Debug, Clone)]
+pub struct DashboardSettings {
+    pub listen_addr: ListenAddr,
+    pub auth_token: Option<String>,
+    pub tick_rate: Duration,
+    /// Wait between attempts to reach the daemon
+    pub retry_interval: Duration,
+    /// Give up after this many failed attempts in a row; `None` never does
+    pub reconnect_limit: Option<u32>,
+    /// Ring the bell and send a desktop notification when everything finishes
+    pub notify: bool,
+    /// Ask before quitting while workers are running
+    pub confirm_quit: bool,
+    /// Flag the numbers as stale after this long without a message; `None`
+    /// never does
+    pub stale_after: Option<Duration>,
+    /// Most daemon updates folded in before a redraw; the rest wait for
+    /// the next frame
+    pub max_updates_per_frame: usize,
+    /// Template for worker names, see [`display_name`]
+    pub name_template: String,
+    pub theme: Theme,
+    /// Where `e` writes snapshots of the worker table
+    pub export_dir: PathBuf,
+    pub export_format: ExportFormat,
+    /// Only watch: no CANCEL or other commands are sent to the daemon
+    pub read_only: bool,
+    /// Start in the dense layout `c` toggles
+    pub compact: bool,
+}
+
+impl DashboardSettings {
+    pub fn from_config(config: Option<&Config>) -> Self {
+        match config {
+            Some(config) => Self {
+                listen_addr: config.listen_addr(),
+                auth_token: config.resolved_auth_token(),
+                tick_rate: config.tui_tick_rate(),
+                retry_interval: Duration::from_secs(config.socket_retry_interval),
+                reconnect_limit: config.tui_reconnect_limit,
+                notify: false,
+                confirm_quit: config.tui_confirm_quit,
+                stale_after: config.tui_stale_after(),
+                max_updates_per_frame: config.tui_max_updates_per_frame,
+                name_template: config.tui_name_template.clone(),
+                theme: config.theme.theme(),
+                export_dir: config.freight_dir().join("exports"),
+                export_format: config.export_format,
+                read_only: false,
+                compact: false,
+            },
+            None => Self {
+                listen_addr: ListenAddr::default(),
+                auth_token: auth_token_from_env(),
+                tick_rate: DEFAULT_TICK_RATE,
+                retry_interval: DEFAULT_RETRY_INTERVAL,
+                reconnect_limit: None,
+                notify: false,
+                confirm_quit: true,
+                stale_after: Some(DEFAULT_STALE_AFTER),
+                max_updates_per_frame: DEFAULT_MAX_UPDATES_PER_FRAME,
+                name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+                theme: Theme::default(),
+                export_dir: PathBuf::from(".freight/exports"),
+                export_format: ExportFormat::default(),
+                read_only: false,
+                compact: false,
+            },
+        }
+    }
+}
+
+/// Named color schemes selectable with the `theme` config option.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    /// No colors at all, only bold and reverse video
+    Mono,
+}
+
+impl ThemeName {
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeName::Dark => Theme::dark(),
+            ThemeName::Light => Theme::light(),
+            ThemeName::Mono => Theme::mono(),
+        }
+    }
+}
+
+/// Styles the dashboard draws with: one per worker status plus a few accents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub running: Style,
+    pub completed: Style,
+    pub planned: Style,
+    pub failed: Style,
+    pub cancelled: Style,
+    /// Handshaked with HELLO but not started yet
+    pub idle: Style,
+    /// Title and progress percentages
+    pub accent: Style,
+    /// Tool name column
+    pub tool: Style,
+    /// Directory names and elapsed times
+    pub text: Style,
+    /// Byte counts, messages and the footer
+    pub muted: Style,
+    /// Connection lost banner
+    pub alert: Style,
+    /// Retry counts short of the limit
+    pub warning: Style,
+    /// Throughput sparkline
+    pub chart: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    fn fg(color: Color) -> Style {
+        Style::default().fg(color)
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            running: Self::fg(Color::Yellow),
+            completed: Self::fg(Color::Green),
+            planned: Self::fg(Color::Cyan),
+            failed: Self::fg(Color::Red),
+            cancelled: Self::fg(Color::Magenta),
+            idle: Self::fg(Color::LightBlue).add_modifier(Modifier::ITALIC),
+            accent: Self::fg(Color::Cyan),
+            tool: Self::fg(Color::Blue),
+            text: Self::fg(Color::White),
+            muted: Self::fg(Color::Gray),
+            alert: Self::fg(Color::Red).add_modifier(Modifier::BOLD),
+            warning: Self::fg(Color::LightRed),
+            chart: Self::fg(Color::Green),
+        }
+    }
+
+    /// Darker shades that stay readable on a white background.
+    pub fn light() -> Self {
+        Self {
+            running: Self::fg(Color::Rgb(160, 100, 0)),
+            completed: Self::fg(Color::Rgb(0, 120, 0)),
+            planned: Self::fg(Color::Blue),
+            failed: Self::fg(Color::Rgb(180, 0, 0)),
+            cancelled: Self::fg(Color::Rgb(140, 0, 140)),
+            idle: Self::fg(Color::Rgb(60, 60, 200)).add_modifier(Modifier::ITALIC),
+            accent: Self::fg(Color::Blue),
+            tool: Self::fg(Color::Rgb(0, 90, 160)),
+            text: Self::fg(Color::Black),
+            muted: Self::fg(Color::DarkGray),
+            alert: Self::fg(Color::Rgb(180, 0, 0)).add_modifier(Modifier::BOLD),
+            warning: Self::fg(Color::Rgb(200, 80, 0)),
+            chart: Self::fg(Color::Rgb(0, 120, 0)),
+        }
+    }
+
+    pub fn mono() -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        Self {
+            running: bold,
+            completed: plain,
+            planned: plain,
+            failed: bold.add_modifier(Modifier::REVERSED),
+            cancelled: plain,
+            idle: plain.add_modifier(Modifier::ITALIC),
+            accent: bold,
+            tool: plain,
+            text: plain,
+            muted: plain,
+            alert: bold.add_modifier(Modifier::REVERSED),
+            warning: bold,
+            chart: plain,
+        }
+    }
+
+    pub fn status(&self, status: &str) -> Style {
+        match status {
+            "running" => self.running,
+            "completed" => self.completed,
+            "planned" => self.planned,
+            "failed" => self.failed,
+            "cancelled" => self.cancelled,
+            "idle" => self.idle,
+            "skipped" => self.muted,
+            _ => self.muted,
+        }
+    }
+}
+
+/// Group name for workers that never reported a host.
+pub const LOCAL_HOST: &str = "local";
+/// Throughput samples kept for the header sparkline, one per tick.
+pub const THROUGHPUT_SAMPLES: usize = 120;
+/// How long a footer notice such as an export confirmation stays up.
+pub const NOTICE_DURATION: Duration = Duration::from_secs(3);
+/// Spinner shown next to running workers, one frame per tick.
+pub const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 pub struct App {
     workers: Vec<WorkerDisplay>,
+    /// Index into `rows()`
     selected: usize,
+    /// First row drawn in the worker list, moved to keep `selected` visible
+    scroll_offset: usize,
+    /// Where the last draw put the worker list's rows, for mouse clicks
+    list_area: Rect,
     last_update: Instant,
+    /// Set whenever something visible changed since the last draw
+    dirty: bool,
+    connection: Connection,
+    /// Show workers under per-host headers
+    grouped: bool,
+    /// Dense rows with columns sized to their contents
+    compact: bool,
+    /// Hosts whose workers are hidden in the grouped view
+    collapsed: HashSet<String>,
+    tab: WorkerTab,
+    /// Some directory still has work ahead of it
+    active: bool,
+    /// Aggregate bytes/sec, oldest first, at most `THROUGHPUT_SAMPLES`
+    throughput: VecDeque<u64>,
+    /// Transferred total and time at the previous sample
+    last_sample: Option<(u64, Instant)>,
+    /// The quit confirmation popup is showing
+    confirming_quit: bool,
+    /// Index into `SPINNER_FRAMES`
+    spinner_frame: usize,
+    /// Shown in the footer until the deadline passes
+    notice: Option<(String, Instant)>,
+    /// Attached with `connect --socket/--address`; cancelling is disabled
+    read_only: bool,
+    /// The daemon's latest `Notice`, shown on a status line until another
+    /// replaces it
+    daemon_notice: Option<(NoticeLevel, String)>,
+    /// Template for worker names, see [`display_name`]
+    name_template: String,
+    /// When the daemon started, from its latest snapshot
+    daemon_started_at: Option<SystemTime>,
+}
+
+/// Workers sharing a host, with their combined byte count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostGroup {
+    pub host: String,
+    /// Indices into the worker list
+    pub workers: Vec<usize>,
+    pub bytes: u64,
+}
+
+/// Bytes moved against bytes expected, over the transfer workers whose
+/// directory size is known.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverallProgress {
+    /// 0.0 to 1.0
+    pub ratio: f64,
+    /// Transfer workers with a known total, i.e. counted in `ratio`
+    pub covered: usize,
+    /// All transfer workers
+    pub workers: usize,
+}
+
+/// Which phase's workers the list shows, switched with Tab/Shift-Tab.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WorkerTab {
+    Scan,
+    Migrate,
+    #[default]
+    All,
+}
+
+impl WorkerTab {
+    /// In the order the tab bar shows them.
+    pub const TABS: [WorkerTab; 3] = [WorkerTab::Scan, WorkerTab::Migrate, WorkerTab::All];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            WorkerTab::Scan => "Scan",
+            WorkerTab::Migrate => "Migrate",
+            WorkerTab::All => "All",
+        }
+    }
+
+    /// Whether a worker running `tool` belongs on this tab.
+    pub fn shows(&self, tool: &str) -> bool {
+        match self {
+            WorkerTab::Scan => tool == "scan",
+            WorkerTab::Migrate => tool == "migrate",
+            WorkerTab::All => true,
+        }
+    }
+
+    fn index(&self) -> usize {
+        Self::TABS.iter().position(|tab| tab == self).unwrap_or(0)
+    }
+}
+
+/// One line of the worker list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Row {
+    Host { host: String, count: usize, bytes: u64, collapsed: bool },
+    Worker(usize),
+}
+
+/// What a row shows, stable across list changes, so the selection can
+/// follow it.
+#[derive(Debug, Clone, PartialEq)]
+enum RowKey {
+    Host(String),
+    Worker(String, String),
+}
+
+/// State of the dashboard's subscription to the daemon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connection {
+    /// First attempt still in flight
+    Connecting,
+    Connected,
+    /// Dropped or unreachable; another attempt is scheduled
+    Retrying,
+    /// `reconnect_limit` was reached
+    GaveUp,
+}
+
+/// What the daemon reader task reports back to the UI loop.
+enum DaemonUpdate {
+    Connection(Connection),
+    Snapshot(WorkersSnapshot),
+    Message(WorkerMessage),
 }
 
 #[derive(Debug, Clone)]
 pub struct WorkerDisplay {
     pub tool: String,
+    /// Identifies the worker along with `tool`; `name` is what's shown
     pub directory: String,
+    pub name: String,
     pub status: String,
     pub progress: Option<f64>,
     pub message: Option<String>,
     pub bytes: Option<u64>,
+    /// Directory size from the scan phase, the denominator for `progress`
+    pub bytes_total: Option<u64>,
+    pub files: Option<u64>,
+    pub host: Option<String>,
+    pub started_at: Option<SystemTime>,
+    pub finished_at: Option<SystemTime>,
+    /// rsync attempts after the first, from freight-migrate's `Attempt N/M`
+    pub retries: u32,
+    pub max_retries: Option<u32>,
+}
+
+impl WorkerDisplay {
+    /// Status as shown on the board: a tool that has said HELLO but not
+    /// START is `idle`. The daemon's `connected` flag can't tell us more,
+    /// since the bash tools open a fresh connection for every message.
+    pub fn status_label(&self) -> &str {
+        match self.status.as_str() {
+            "connected" => "idle",
+            status => status,
+        }
+    }
+
+    /// Time spent so far, or the final duration once the worker has stopped.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let started = self.started_at?;
+        let end = self.finished_at.unwrap_or_else(SystemTime::now);
+        Some(end.duration_since(started).unwrap_or_default())
+    }
+
+    fn refresh_progress(&mut self) {
+        self.progress = match (self.bytes, self.bytes_total) {
+            (Some(bytes), Some(total)) if total > 0 => Some((bytes as f64 / total as f64).min(1.0)),
+            _ => None,
+        };
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
@@ -39,260 +445,2422 @@ impl App {
         Self {
             workers: Vec::new(),
             selected: 0,
+            scroll_offset: 0,
+            list_area: Rect::default(),
             last_update: Instant::now(),
+            dirty: true,
+            connection: Connection::Connecting,
+            grouped: false,
+            compact: false,
+            collapsed: HashSet::new(),
+            tab: WorkerTab::default(),
+            active: false,
+            throughput: VecDeque::with_capacity(THROUGHPUT_SAMPLES),
+            last_sample: None,
+            confirming_quit: false,
+            spinner_frame: 0,
+            notice: None,
+            read_only: false,
+            daemon_notice: None,
+            name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+            daemon_started_at: None,
         }
     }
 
-    pub fn update_worker(
-        &mut self,
-        tool: &str,
-        directory: &str,
-        status: &str,
-        message: Option<String>,
-        bytes: Option<u64>,
-    ) {
-        let worker_id = format!("{}:{}", tool, directory);
+    /// Show `text` in the footer for `NOTICE_DURATION`.
+    pub fn set_notice(&mut self, text: String) {
+        self.notice = Some((text, Instant::now() + NOTICE_DURATION));
+        self.dirty = true;
+    }
 
-        if let Some(worker) = self
-            .workers
-            .iter_mut()
-            .find(|w| format!("{}:{}", w.tool, w.directory) == worker_id)
-        {
-            worker.status = status.to_string();
-            worker.message = message;
-            worker.bytes = bytes;
-        } else {
-            self.workers.push(WorkerDisplay {
-                tool: tool.to_string(),
-                directory: directory.to_string(),
-                status: status.to_string(),
-                progress: None,
-                message,
-                bytes,
-            });
+    /// Drop the footer notice once it has been up long enough.
+    pub fn expire_notice(&mut self, now: Instant) {
+        if self.notice.as_ref().is_some_and(|(_, until)| now >= *until) {
+            self.notice = None;
+            self.dirty = true;
         }
+    }
 
-        self.last_update = Instant::now();
+    /// Disable cancelling, for dashboards attached to another daemon.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        self.dirty = true;
     }
 
-    pub fn next(&mut self) {
-        if !self.workers.is_empty() {
-            self.selected = (self.selected + 1) % self.workers.len();
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Name workers with `template` from now on, renaming those on the board.
+    pub fn set_name_template(&mut self, template: String) {
+        for worker in &mut self.workers {
+            worker.name = display_name(&worker.directory, &template);
         }
+        self.name_template = template;
+        self.dirty = true;
     }
 
-    pub fn previous(&mut self) {
-        if !self.workers.is_empty() {
-            self.selected = if self.selected > 0 {
-                self.selected - 1
+    /// The worker rows currently on screen, in display order; collapsed
+    /// hosts' workers are left out just as they are on the board.
+    pub fn export_rows(&self) -> Vec<ExportRow> {
+        self.rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                Row::Worker(index) => Some(&self.workers[index]),
+                Row::Host { .. } => None,
+            })
+            .map(|worker| ExportRow {
+                host: worker.host.clone().unwrap_or_else(|| LOCAL_HOST.to_string()),
+                tool: worker.tool.clone(),
+                directory: worker.directory.clone(),
+                status: worker.status_label().to_string(),
+                elapsed_secs: worker.elapsed().map(|d| d.as_secs()),
+                bytes: worker.bytes,
+                bytes_total: worker.bytes_total,
+                files: worker.files,
+                progress: worker.progress.map(|p| (p * 100.0).round() as u8),
+                message: worker.message.clone(),
+            })
+            .collect()
+    }
+
+    /// Move the running-worker spinner on by one frame, wrapping around.
+    pub fn advance_spinner(&mut self) {
+        self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+    }
+
+    pub fn spinner(&self) -> &'static str {
+        SPINNER_FRAMES[self.spinner_frame]
+    }
+
+    fn has_running_workers(&self) -> bool {
+        self.workers.iter().any(|w| w.status == "running")
+    }
+
+    /// Handle `q`: true if the dashboard should exit now, otherwise the
+    /// confirmation popup is shown. With `confirm` off, or nothing
+    /// running, there's nothing to lose by quitting straight away.
+    pub fn request_quit(&mut self, confirm: bool) -> bool {
+        if !confirm || !self.has_running_workers() {
+            return true;
+        }
+        self.confirming_quit = true;
+        self.dirty = true;
+        false
+    }
+
+    /// Answer the quit popup, returning whether to exit.
+    pub fn answer_quit(&mut self, confirmed: bool) -> bool {
+        self.confirming_quit = false;
+        self.dirty = true;
+        confirmed
+    }
+
+    pub fn confirming_quit(&self) -> bool {
+        self.confirming_quit
+    }
+
+    /// How long the daemon has been silent, once that exceeds `threshold`.
+    /// Quiet is only suspicious while connected with workers running; a
+    /// finished or idle migration has nothing to say.
+    pub fn stale_for(&self, now: Instant, threshold: Duration) -> Option<Duration> {
+        if self.connection != Connection::Connected || !self.has_running_workers() {
+            return None;
+        }
+        let silent = now.saturating_duration_since(self.last_update);
+        (silent >= threshold).then_some(silent)
+    }
+
+    /// Bytes moved so far by every transfer worker. Scan bytes are sizes
+    /// rather than transfers, so they don't count.
+    fn transferred_bytes(&self) -> u64 {
+        self.workers
+            .iter()
+            .filter(|w| w.tool != "scan")
+            .filter_map(|w| w.bytes)
+            .sum()
+    }
+
+    /// Record the aggregate transfer rate since the previous call.
+    pub fn sample_throughput(&mut self, now: Instant) {
+        let total = self.transferred_bytes();
+        if let Some((last_total, last_time)) = self.last_sample {
+            let secs = now.duration_since(last_time).as_secs_f64();
+            // Totals shrink when a worker retries or is dropped; that isn't
+            // negative throughput
+            let rate = if secs > 0.0 {
+                (total.saturating_sub(last_total) as f64 / secs) as u64
             } else {
-                self.workers.len() - 1
+                0
             };
+            if self.throughput.len() == THROUGHPUT_SAMPLES {
+                self.throughput.pop_front();
+            }
+            self.throughput.push_back(rate);
+            // A flat-zero line doesn't move, so an idle board stays idle
+            if self.throughput.iter().any(|&rate| rate > 0) {
+                self.dirty = true;
+            }
         }
+        self.last_sample = Some((total, now));
     }
-}
 
-pub async fn run_dashboard() -> Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    /// Progress of the whole migration, or `None` until some transfer
+    /// worker's directory size is known. Workers without a total are left
+    /// out rather than guessed at; `covered` says how many counted.
+    pub fn overall_progress(&self) -> Option<OverallProgress> {
+        let transfers: Vec<&WorkerDisplay> =
+            self.workers.iter().filter(|w| w.tool != "scan").collect();
+        let (mut done, mut total, mut covered) = (0u64, 0u64, 0);
+        for worker in &transfers {
+            if let Some(bytes_total) = worker.bytes_total.filter(|&t| t > 0) {
+                done += worker.bytes.unwrap_or(0).min(bytes_total);
+                total += bytes_total;
+                covered += 1;
+            }
+        }
+        (covered > 0).then(|| OverallProgress {
+            ratio: done as f64 / total as f64,
+            covered,
+            workers: transfers.len(),
+        })
+    }
 
-    // Create app state
-    let mut app = App::new();
+    /// The most recent sample, in bytes/sec.
+    pub fn current_throughput(&self) -> u64 {
+        self.throughput.back().copied().unwrap_or(0)
+    }
 
-    // Try to connect to daemon socket
-    let socket_connection = connect_to_daemon().await;
+    /// Samples squeezed into at most `width` points by averaging
+    /// neighbours, so the whole window fits however narrow the terminal.
+    pub fn throughput_series(&self, width: usize) -> Vec<u64> {
+        let samples: Vec<u64> = self.throughput.iter().copied().collect();
+        if width == 0 || samples.len() <= width {
+            return samples;
+        }
+        let bucket = samples.len().div_ceil(width);
+        samples
+            .chunks(bucket)
+            .map(|chunk| chunk.iter().sum::<u64>() / chunk.len() as u64)
+            .collect()
+    }
 
-    let result = run_app(&mut terminal, &mut app, socket_connection).await;
+    /// Whether every directory has finished since the last call, i.e. the
+    /// board just went from running to all-terminal. Fires once per
+    /// transition.
+    pub fn take_completion(&mut self) -> bool {
+        let migrating: HashSet<&str> = self
+            .workers
+            .iter()
+            .filter(|w| w.tool == "migrate")
+            .map(|w| w.directory.as_str())
+            .collect();
+        // A successful scan still has its migrate ahead of it
+        let active = self.workers.iter().any(|w| match w.status.as_str() {
+            "completed" if w.tool == "scan" => !migrating.contains(w.directory.as_str()),
+            "completed" | "planned" | "skipped" | "failed" | "cancelled" => false,
+            _ => true,
+        });
+        let finished = self.active && !active;
+        self.active = active;
+        finished
+    }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    /// Workers of any tool that ended `failed`.
+    fn failed_workers(&self) -> usize {
+        self.workers.iter().filter(|w| w.status == "failed").count()
+    }
 
-    result
-}
+    /// One-line outcome of the migrate phase, for the completion notice.
+    fn completion_summary(&self) -> String {
+        let migrated: Vec<_> = self.workers.iter().filter(|w| w.tool == "migrate").collect();
+        let failed = self.failed_workers();
+        let succeeded = migrated
+            .iter()
+            .filter(|w| matches!(w.status.as_str(), "completed" | "planned"))
+            .count();
+        format!("{} of {} directories migrated, {} failed", succeeded, migrated.len(), failed)
+    }
 
-async fn connect_to_daemon() -> Option<UnixStream> {
-    match UnixStream::connect(crate::socket::SOCKET_PATH).await {
-        Ok(stream) => {
-            info!("Connected to freight daemon");
-            Some(stream)
+    /// Indices of the workers the current tab shows.
+    fn visible_workers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.workers
+            .iter()
+            .enumerate()
+            .filter(|(_, worker)| self.tab.shows(&worker.tool))
+            .map(|(index, _)| index)
+    }
+
+    /// Workers on the current tab bucketed by host, sorted by host name.
+    /// Workers without a host go under `LOCAL_HOST`.
+    pub fn host_groups(&self) -> Vec<HostGroup> {
+        let mut groups: BTreeMap<&str, HostGroup> = BTreeMap::new();
+        for index in self.visible_workers() {
+            let worker = &self.workers[index];
+            let host = worker.host.as_deref().unwrap_or(LOCAL_HOST);
+            let group = groups.entry(host).or_insert_with(|| HostGroup {
+                host: host.to_string(),
+                workers: Vec::new(),
+                bytes: 0,
+            });
+            group.workers.push(index);
+            group.bytes += worker.bytes.unwrap_or(0);
         }
-        Err(e) => {
-            error!("Failed to connect to daemon: {}", e);
-            None
+        groups.into_values().collect()
+    }
+
+    /// The lines of the worker list as currently shown.
+    pub fn rows(&self) -> Vec<Row> {
+        if !self.grouped {
+            return self.visible_workers().map(Row::Worker).collect();
+        }
+
+        let mut rows = Vec::new();
+        for group in self.host_groups() {
+            let collapsed = self.collapsed.contains(&group.host);
+            rows.push(Row::Host {
+                host: group.host,
+                count: group.workers.len(),
+                bytes: group.bytes,
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(group.workers.into_iter().map(Row::Worker));
+            }
         }
+        rows
     }
-}
 
-async fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-    socket_connection: Option<UnixStream>,
-) -> Result<()> {
-    let mut last_tick = Instant::now();
-    let tick_rate = Duration::from_millis(250);
+    fn row_key(&self, row: &Row) -> Option<RowKey> {
+        match row {
+            Row::Host { host, .. } => Some(RowKey::Host(host.clone())),
+            Row::Worker(index) => self
+                .workers
+                .get(*index)
+                .map(|w| RowKey::Worker(w.tool.clone(), w.directory.clone())),
+        }
+    }
 
-    // If we have a socket connection, spawn a task to read messages
-    if let Some(stream) = socket_connection {
-        let mut reader = BufReader::new(stream);
-        tokio::spawn(async move {
-            let mut line = String::new();
-            loop {
-                line.clear();
-                match reader.read_line(&mut line).await {
-                    Ok(0) => break, // Connection closed
-                    Ok(_) => {
-                        // Parse and handle daemon messages
-                        // This would update the app state
-                    }
-                    Err(e) => {
-                        error!("Error reading from daemon: {}", e);
-                        break;
-                    }
-                }
+    fn selected_key(&self) -> Option<RowKey> {
+        self.rows()
+            .get(self.selected)
+            .and_then(|row| self.row_key(row))
+    }
+
+    /// Move the selection to the row showing `key`, if it is still shown.
+    fn reselect(&mut self, key: Option<RowKey>) {
+        if let Some(key) = key {
+            let rows = self.rows();
+            if let Some(position) = rows
+                .iter()
+                .position(|row| self.row_key(row).as_ref() == Some(&key))
+            {
+                self.selected = position;
             }
-        });
+        }
+        self.clamp_selection();
     }
 
-    loop {
-        terminal.draw(|f| ui(f, app))?;
+    pub fn tab(&self) -> WorkerTab {
+        self.tab
+    }
+
+    pub fn next_tab(&mut self) {
+        let next = (self.tab.index() + 1) % WorkerTab::TABS.len();
+        self.set_tab(WorkerTab::TABS[next]);
+    }
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+    pub fn previous_tab(&mut self) {
+        let len = WorkerTab::TABS.len();
+        self.set_tab(WorkerTab::TABS[(self.tab.index() + len - 1) % len]);
+    }
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Down => app.next(),
-                    KeyCode::Up => app.previous(),
-                    KeyCode::Char('r') => {
-                        // Refresh - could trigger rescan
-                    }
-                    _ => {}
+    /// Switch tabs, keeping the selected worker selected if it is still shown.
+    fn set_tab(&mut self, tab: WorkerTab) {
+        let key = self.selected_key();
+        self.tab = tab;
+        self.reselect(key);
+        self.dirty = true;
+    }
+
+    pub fn toggle_compact(&mut self) {
+        self.compact = !self.compact;
+        self.dirty = true;
+    }
+
+    pub fn toggle_grouped(&mut self) {
+        let key = self.selected_key();
+        self.grouped = !self.grouped;
+        self.reselect(key);
+        self.dirty = true;
+    }
+
+    /// Collapse or expand the host group under the selection, leaving the
+    /// selection on its header.
+    pub fn toggle_collapsed(&mut self) {
+        if !self.grouped {
+            return;
+        }
+        let host = match self.rows().get(self.selected) {
+            Some(Row::Host { host, .. }) => host.clone(),
+            Some(Row::Worker(index)) => self.workers[*index]
+                .host
+                .clone()
+                .unwrap_or_else(|| LOCAL_HOST.to_string()),
+            None => return,
+        };
+        if !self.collapsed.remove(&host) {
+            self.collapsed.insert(host.clone());
+        }
+        self.reselect(Some(RowKey::Host(host)));
+        self.dirty = true;
+    }
+
+    /// A tool handshaked for `directory`: show it as idle until it starts,
+    /// or just note its host if the row already exists.
+    fn hello(&mut self, tool: &str, directory: &str, host: Option<String>) {
+        let key = self.selected_key();
+        match self
+            .workers
+            .iter_mut()
+            .find(|w| w.tool == tool && w.directory == directory)
+        {
+            Some(worker) => {
+                if host.is_some() {
+                    worker.host = host;
                 }
             }
+            None => self.workers.push(WorkerDisplay {
+                tool: tool.to_string(),
+                directory: directory.to_string(),
+                name: display_name(directory, &self.name_template),
+                status: "connected".to_string(),
+                progress: None,
+                message: None,
+                bytes: None,
+                bytes_total: None,
+                files: None,
+                host,
+                started_at: None,
+                finished_at: None,
+                retries: 0,
+                max_retries: None,
+            }),
         }
+        self.reselect(key);
+        self.last_update = Instant::now();
+        self.dirty = true;
+    }
 
-        if last_tick.elapsed() >= tick_rate {
-            // Update app state periodically
-            last_tick = Instant::now();
+    pub fn set_connection(&mut self, connection: Connection) {
+        if self.connection != connection {
+            self.connection = connection;
+            self.dirty = true;
         }
     }
-}
-
-fn ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Min(0),
-            Constraint::Length(3),
-        ])
-        .split(f.size());
 
-    // Header
-    let header = Paragraph::new("Freight NFS Migration Suite")
-        .style(Style::default().fg(Color::Cyan))
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(header, chunks[0]);
+    /// Replace the worker list with the daemon's view, which covers
+    /// anything that happened while the dashboard was disconnected.
+    pub fn apply_snapshot(&mut self, snapshot: WorkersSnapshot) {
+        let selected = self.selected_key();
+        self.daemon_started_at = snapshot.started_at;
 
-    // Worker list
-    let workers: Vec<ListItem> = app
-        .workers
-        .iter()
-        .enumerate()
-        .map(|(i, worker)| {
-            let status_color = match worker.status.as_str() {
-                "running" => Color::Yellow,
-                "completed" => Color::Green,
-                "failed" => Color::Red,
-                _ => Color::Gray,
-            };
+        let mut states: Vec<_> = snapshot
+            .workers
+            .into_values()
+            .filter(|state| state.directory.is_some())
+            .collect();
+        states.sort_by(|a, b| {
+            (a.started_at, &a.tool, &a.directory).cmp(&(b.started_at, &b.tool, &b.directory))
+        });
 
-            let bytes_str = worker
-                .bytes
-                .map(|b| format!(" ({})", format_bytes(b)))
-                .unwrap_or_default();
-
-            let message_str = worker
-                .message
-                .as_ref()
-                .map(|m| format!(" - {}", m))
-                .unwrap_or_default();
-
-            let content = Line::from(vec![
-                Span::styled(
-                    format!("{:8}", worker.tool),
-                    Style::default().fg(Color::Blue),
-                ),
-                Span::raw(" "),
-                Span::styled(
-                    format!("{:20}", truncate(&worker.directory, 20)),
-                    Style::default().fg(Color::White),
-                ),
-                Span::raw(" "),
-                Span::styled(
-                    format!("{:10}", worker.status),
-                    Style::default().fg(status_color),
-                ),
-                Span::styled(bytes_str, Style::default().fg(Color::Gray)),
-                Span::styled(message_str, Style::default().fg(Color::Gray)),
-            ]);
+        self.workers = states
+            .into_iter()
+            .map(|state| WorkerDisplay {
+                status: match state.status.as_str() {
+                    "ok" => "completed".to_string(),
+                    _ => state.status,
+                },
+                tool: state.tool,
+                name: display_name(state.directory.as_deref().unwrap_or_default(), &self.name_template),
+                directory: state.directory.unwrap_or_default(),
+                progress: None,
+                message: state.last_message,
+                bytes: state.bytes_transferred,
+                bytes_total: state.bytes_total,
+                files: state.files,
+                host: state.host,
+                started_at: state.started_at,
+                finished_at: state.finished_at,
+                retries: state.attempts.saturating_sub(1),
+                max_retries: state.max_attempts.map(|max| max.saturating_sub(1)),
+            })
+            .collect();
 
-            let mut item = ListItem::new(content);
-            if i == app.selected {
-                item = item.style(Style::default().add_modifier(Modifier::REVERSED));
+        // Later phases reuse the total the scan worker reported
+        let scan_totals: HashMap<String, u64> = self
+            .workers
+            .iter()
+            .filter(|w| w.tool == "scan")
+            .filter_map(|w| Some((w.directory.clone(), w.bytes_total?)))
+            .collect();
+        for worker in &mut self.workers {
+            if worker.bytes_total.is_none() {
+                worker.bytes_total = scan_totals.get(&worker.directory).copied();
             }
-            item
-        })
-        .collect();
-
-    let workers_list = List::new(workers)
-        .block(Block::default().borders(Borders::ALL).title("Workers"))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+            worker.refresh_progress();
+        }
 
-    f.render_widget(workers_list, chunks[1]);
+        self.reselect(selected);
+        self.last_update = Instant::now();
+        self.dirty = true;
+    }
 
-    // Footer with controls
-    let footer = Paragraph::new("↑/↓: Navigate | r: Refresh | q: Quit")
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(footer, chunks[2]);
-}
+    /// Whether the board needs redrawing, clearing the flag.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
 
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
 
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
+    /// Running workers show a live elapsed time, so they need a redraw
+    /// every tick even without new messages.
+    fn has_running_timers(&self) -> bool {
+        self.workers
+            .iter()
+            .any(|w| w.started_at.is_some() && w.finished_at.is_none())
     }
 
-    if unit_index == 0 {
-        format!("{} {}", bytes, UNITS[unit_index])
-    } else {
-        format!("{:.1} {}", size, UNITS[unit_index])
+    /// Per-tick upkeep, asking for a redraw only when something on screen
+    /// moved.
+    pub fn tick(&mut self, now: Instant) {
+        self.sample_throughput(now);
+        self.advance_spinner();
+        self.expire_notice(now);
+        if self.has_running_timers() {
+            self.dirty = true;
+        }
     }
-}
 
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
+    pub fn update_worker(
+        &mut self,
+        tool: &str,
+        directory: &str,
+        status: &str,
+        message: Option<String>,
+        bytes: Option<u64>,
+    ) {
+        if let Some(worker) = self
+            .workers
+            .iter_mut()
+            .find(|w| w.tool == tool && w.directory == directory)
+        {
+            worker.status = status.to_string();
+            worker.message = message;
+            worker.bytes = bytes;
+        } else {
+            // Only a new worker can shift rows around the selection
+            let selected = self.selected_key();
+            self.workers.push(WorkerDisplay {
+                tool: tool.to_string(),
+                directory: directory.to_string(),
+                name: display_name(directory, &self.name_template),
+                status: status.to_string(),
+                progress: None,
+                message,
+                bytes,
+                bytes_total: None,
+                files: None,
+                host: None,
+                started_at: None,
+                finished_at: None,
+                retries: 0,
+                max_retries: None,
+            });
+            self.reselect(selected);
+        }
+
+        self.last_update = Instant::now();
+        self.dirty = true;
+    }
+
+    /// Drop a worker from the list, keeping the same worker selected when
+    /// it is still there.
+    pub fn remove_worker(&mut self, tool: &str, directory: &str) {
+        let Some(index) = self
+            .workers
+            .iter()
+            .position(|w| w.tool == tool && w.directory == directory)
+        else {
+            return;
+        };
+
+        let selected = self.selected_key();
+        self.workers.remove(index);
+        self.reselect(selected);
+        self.last_update = Instant::now();
+        self.dirty = true;
+    }
+
+    /// Keep `selected` a valid row index (or 0 for an empty list).
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+    }
+
+    /// Fold one update from the daemon reader task into the state.
+    fn apply_update(&mut self, update: DaemonUpdate) {
+        match update {
+            DaemonUpdate::Connection(connection) => self.set_connection(connection),
+            DaemonUpdate::Snapshot(snapshot) => self.apply_snapshot(snapshot),
+            DaemonUpdate::Message(message) => self.apply_message(message),
+        }
+    }
+
+    /// How long the daemon has been running, once a snapshot has said
+    /// when it started.
+    pub fn daemon_uptime(&self) -> Option<Duration> {
+        let started = self.daemon_started_at?;
+        Some(SystemTime::now().duration_since(started).unwrap_or_default())
+    }
+
+    /// The daemon's latest warning or error, if it has sent one.
+    pub fn daemon_notice(&self) -> Option<(NoticeLevel, &str)> {
+        self.daemon_notice.as_ref().map(|(level, text)| (*level, text.as_str()))
+    }
+
+    /// Fold a message streamed from the daemon into the worker list.
+    pub fn apply_message(&mut self, message: WorkerMessage) {
+        if let MessageType::Notice = message.message_type {
+            let level = match message.status.as_deref() {
+                Some("warning") => NoticeLevel::Warning,
+                _ => NoticeLevel::Error,
+            };
+            self.daemon_notice = Some((level, message.message.unwrap_or_default()));
+            self.dirty = true;
+            return;
+        }
+
+        let directory = match &message.directory {
+            Some(directory) => directory.clone(),
+            // A bare HELLO carries no tool/directory, so there's nothing to show yet
+            None => return,
+        };
+
+        let status = match message.message_type {
+            MessageType::Hello => {
+                self.hello(&message.tool, &directory, message.host);
+                return;
+            }
+            // The daemon pruned this worker under its retention policy
+            MessageType::Bye => {
+                self.remove_worker(&message.tool, &directory);
+                return;
+            }
+            MessageType::Notice => return,
+            MessageType::Start => "running".to_string(),
+            MessageType::Progress => self
+                .workers
+                .iter()
+                .find(|w| w.tool == message.tool && w.directory == directory)
+                .map(|w| w.status.clone())
+                .unwrap_or_else(|| "running".to_string()),
+            MessageType::Stop => match message.status.as_deref() {
+                // The worker's own STOP can trail the daemon's cancellation
+                _ if self.worker_status(&message.tool, &directory) == Some("cancelled") => {
+                    "cancelled".to_string()
+                }
+                // Likewise a timed-out worker's own STOP as it is killed
+                Some("interrupted")
+                    if self.worker_status(&message.tool, &directory) == Some("failed") =>
+                {
+                    "failed".to_string()
+                }
+                Some("ok") | None => "completed".to_string(),
+                Some(status) => status.to_string(),
+            },
+        };
+
+        self.update_worker(&message.tool, &directory, &status, message.message, message.bytes);
+
+        // Later phases reuse the total the scan worker reported
+        let bytes_total = message.bytes_total.or_else(|| {
+            self.workers
+                .iter()
+                .find(|w| w.tool == "scan" && w.directory == directory)
+                .and_then(|w| w.bytes_total)
+        });
+
+        if let Some(worker) = self
+            .workers
+            .iter_mut()
+            .find(|w| w.tool == message.tool && w.directory == directory)
+        {
+            if bytes_total.is_some() {
+                worker.bytes_total = bytes_total;
+            }
+            if message.files.is_some() {
+                worker.files = message.files;
+            }
+            worker.refresh_progress();
+
+            match message.message_type {
+                MessageType::Start => {
+                    worker.started_at = Some(SystemTime::now());
+                    worker.finished_at = None;
+                }
+                MessageType::Stop => {
+                    worker.finished_at = Some(SystemTime::now());
+                }
+                MessageType::Progress => {
+                    if let Some((attempt, limit)) = parse_attempt(worker.message.as_deref()) {
+                        worker.retries = attempt.saturating_sub(1);
+                        worker.max_retries = Some(limit.saturating_sub(1));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn worker_status(&self, tool: &str, directory: &str) -> Option<&str> {
+        self.workers
+            .iter()
+            .find(|w| w.tool == tool && w.directory == directory)
+            .map(|w| w.status.as_str())
+    }
+
+    /// The worker on the selected row; `None` on a host header.
+    pub fn selected_worker(&self) -> Option<&WorkerDisplay> {
+        match self.rows().get(self.selected)? {
+            Row::Worker(index) => self.workers.get(*index),
+            Row::Host { .. } => None,
+        }
+    }
+
+    pub fn next(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
+            self.dirty = true;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            self.selected = if self.selected > 0 {
+                self.selected - 1
+            } else {
+                len - 1
+            };
+            self.dirty = true;
+        }
+    }
+
+    /// Move the selection by `delta` rows without wrapping, as the scroll
+    /// wheel does.
+    pub fn scroll(&mut self, delta: isize) {
+        let last = self.rows().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+        self.dirty = true;
+    }
+
+    /// Record where the list's rows are drawn and scroll so the selection
+    /// is among them. Called by `ui` each draw.
+    fn place_list(&mut self, area: Rect) {
+        self.list_area = area;
+        let height = area.height as usize;
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if height > 0 && self.selected >= self.scroll_offset + height {
+            self.scroll_offset = self.selected + 1 - height;
+        }
+        // Don't leave blank rows at the bottom once the list has shrunk
+        let rows = self.rows().len();
+        self.scroll_offset = self.scroll_offset.min(rows.saturating_sub(height));
+    }
+
+    /// The row drawn at terminal cell (`column`, `row`), if any.
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        let inside = (area.x..area.x + area.width).contains(&column)
+            && (area.y..area.y + area.height).contains(&row);
+        if !inside {
+            return None;
+        }
+        let index = self.scroll_offset + (row - area.y) as usize;
+        (index < self.rows().len()).then_some(index)
+    }
+
+    /// Select the row under a mouse click.
+    pub fn click(&mut self, column: u16, row: u16) {
+        if let Some(index) = self.row_at(column, row) {
+            self.selected = index;
+            self.dirty = true;
+        }
+    }
+}
+
+pub async fn run_dashboard(settings: DashboardSettings) -> Result<()> {
+    // Setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Create app state
+    let mut app = App::new();
+    app.set_read_only(settings.read_only);
+    app.set_name_template(settings.name_template.clone());
+    if settings.compact {
+        app.toggle_compact();
+    }
+
+    let result = run_app(&mut terminal, &mut app, settings).await;
+
+    // Restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    result
+}
+
+/// Ask the daemon to cancel one worker over a short-lived connection, so
+/// the subscription stream stays read-only.
+async fn send_cancel(
+    listen_addr: ListenAddr,
+    auth_token: Option<String>,
+    tool: String,
+    directory: String,
+) {
+    let mut stream = match socket::connect(&listen_addr).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            error!("Failed to connect to daemon to cancel {}:{}: {}", tool, directory, e);
+            return;
+        }
+    };
+
+    let mut request = String::new();
+    if let Some(token) = auth_token {
+        request.push_str(&format!("AUTH {}\n", token));
+    }
+    request.push_str(&format!(
+        "{} tool={} dir={}\n",
+        CANCEL_COMMAND,
+        escape_value(&tool),
+        escape_value(&directory)
+    ));
+    if let Err(e) = stream.write_all(request.as_bytes()).await {
+        error!("Failed to send cancel for {}:{}: {}", tool, directory, e);
+    }
+}
+
+/// Ring the terminal bell and send a desktop notification. The desktop
+/// side is best effort: headless hosts often have no notification daemon.
+fn notify_complete(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    summary: String,
+) -> Result<()> {
+    terminal.backend_mut().write_all(b"\x07")?;
+    terminal.backend_mut().flush()?;
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .summary("Freight migration finished")
+            .body(&summary)
+            .show()
+        {
+            warn!("Failed to send desktop notification: {}", e);
+        }
+    });
+    Ok(())
+}
+
+async fn connect_to_daemon(
+    listen_addr: &ListenAddr,
+    auth_token: Option<&str>,
+) -> Option<Box<dyn DaemonStream>> {
+    match socket::connect(listen_addr).await {
+        Ok(mut stream) => {
+            info!("Connected to freight daemon");
+            // The snapshot comes back first, then the live stream
+            let mut subscribe = String::new();
+            if let Some(token) = auth_token {
+                subscribe.push_str(&format!("AUTH {}\n", token));
+            }
+            subscribe.push_str(&format!("{} keep_open\n", QUERY_COMMAND));
+            subscribe.push_str(&format!("{}\n", SUBSCRIBE_COMMAND));
+            if let Err(e) = stream.write_all(subscribe.as_bytes()).await {
+                error!("Failed to subscribe to daemon: {}", e);
+                return None;
+            }
+            Some(stream)
+        }
+        Err(e) => {
+            warn!("Failed to connect to daemon: {}", e);
+            None
+        }
+    }
+}
+
+/// Keep a subscription to the daemon open, reconnecting every
+/// `retry_interval` whenever it is unreachable or drops the connection.
+async fn maintain_connection(
+    settings: DashboardSettings,
+    update_tx: mpsc::UnboundedSender<DaemonUpdate>,
+) {
+    // A daemon started alongside the dashboard may not be listening yet;
+    // waiting here saves a whole retry interval on the first connect
+    if !socket::wait_until_ready(
+        &settings.listen_addr,
+        settings.auth_token.as_deref(),
+        READY_TIMEOUT,
+    )
+    .await
+    {
+        info!("Daemon not ready after {:?}, connecting anyway", READY_TIMEOUT);
+    }
+
+    let mut failures = 0;
+    loop {
+        let stream =
+            connect_to_daemon(&settings.listen_addr, settings.auth_token.as_deref()).await;
+        if let Some(stream) = stream {
+            match stream_updates(stream, &update_tx).await {
+                None => return, // Dashboard has exited
+                Some(true) => failures = 0,
+                // Dropped before the snapshot, e.g. a rejected token
+                Some(false) => failures += 1,
+            }
+        } else {
+            failures += 1;
+        }
+
+        if settings.reconnect_limit.is_some_and(|limit| failures >= limit) {
+            warn!("Giving up on the daemon after {} failed attempts", failures);
+            let _ = update_tx.send(DaemonUpdate::Connection(Connection::GaveUp));
+            return;
+        }
+        if update_tx.send(DaemonUpdate::Connection(Connection::Retrying)).is_err() {
+            return;
+        }
+        tokio::time::sleep(settings.retry_interval).await;
+    }
+}
+
+/// Forward the snapshot and live messages from one connection until it
+/// closes. Returns whether the snapshot arrived, or `None` once the
+/// dashboard has gone away.
+async fn stream_updates(
+    stream: Box<dyn DaemonStream>,
+    update_tx: &mpsc::UnboundedSender<DaemonUpdate>,
+) -> Option<bool> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    let mut attached = false;
+    loop {
+        line.clear();
+        let update = match reader.read_line(&mut line).await {
+            Ok(0) => break, // Connection closed
+            Ok(_) if !attached => match serde_json::from_str::<WorkersSnapshot>(line.trim()) {
+                Ok(snapshot) => {
+                    attached = true;
+                    update_tx
+                        .send(DaemonUpdate::Connection(Connection::Connected))
+                        .ok()?;
+                    DaemonUpdate::Snapshot(snapshot)
+                }
+                Err(e) => {
+                    warn!("Failed to parse daemon snapshot: {}", e);
+                    break;
+                }
+            },
+            Ok(_) => match serde_json::from_str::<WorkerMessage>(line.trim()) {
+                Ok(message) => DaemonUpdate::Message(message),
+                Err(e) => {
+                    warn!("Failed to parse daemon message: {}", e);
+                    continue;
+                }
+            },
+            Err(e) => {
+                error!("Error reading from daemon: {}", e);
+                break;
+            }
+        };
+        update_tx.send(update).ok()?;
+    }
+    Some(attached)
+}
+
+/// Apply up to `max` queued daemon updates in one pass, returning how many
+/// there were.
+fn apply_pending(
+    app: &mut App,
+    update_rx: &mut mpsc::UnboundedReceiver<DaemonUpdate>,
+    max: usize,
+) -> usize {
+    let mut applied = 0;
+    while applied < max {
+        let Ok(update) = update_rx.try_recv() else {
+            break;
+        };
+        app.apply_update(update);
+        applied += 1;
+    }
+    applied
+}
+
+async fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    settings: DashboardSettings,
+) -> Result<()> {
+    let mut last_tick = Instant::now();
+    let tick_rate = settings.tick_rate;
+    let notify = settings.notify;
+    let confirm_quit = settings.confirm_quit;
+    let stale_after = settings.stale_after;
+    let max_updates = settings.max_updates_per_frame;
+    let export_dir = settings.export_dir.clone();
+    let export_format = settings.export_format;
+    let theme = settings.theme;
+    let listen_addr = settings.listen_addr.clone();
+    let auth_token = settings.auth_token.clone();
+
+    let (update_tx, mut update_rx) = mpsc::unbounded_channel::<DaemonUpdate>();
+    tokio::spawn(maintain_connection(settings, update_tx));
+
+    loop {
+        // Everything that arrived since the last pass lands in one redraw,
+        // up to the per-frame cap
+        let applied = apply_pending(app, &mut update_rx, max_updates);
+        let backlogged = applied == max_updates;
+
+        if applied > 0 && app.take_completion() && notify {
+            notify_complete(terminal, app.completion_summary())?;
+        }
+
+        if app.take_dirty() {
+            let stale = stale_after.and_then(|after| app.stale_for(Instant::now(), after));
+            terminal.draw(|f| ui(f, app, &theme, stale))?;
+        }
+
+        // A backlog is worked off on the next pass rather than after a tick
+        let timeout = if backlogged {
+            Duration::ZERO
+        } else {
+            tick_rate
+                .checked_sub(last_tick.elapsed())
+                .unwrap_or_else(|| Duration::from_secs(0))
+        };
+
+        if crossterm::event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) if app.confirming_quit() => {
+                    let confirmed = matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y'));
+                    if app.answer_quit(confirmed) {
+                        return Ok(());
+                    }
+                }
+                Event::Key(key) => {
+                    app.mark_dirty();
+                    match key.code {
+                        // Otherwise the confirmation popup is now up
+                        KeyCode::Char('q') if app.request_quit(confirm_quit) => return Ok(()),
+                        KeyCode::Down => app.next(),
+                        KeyCode::Up => app.previous(),
+                        KeyCode::Char('h') => app.toggle_grouped(),
+                        KeyCode::Char('c') => app.toggle_compact(),
+                        KeyCode::Tab => app.next_tab(),
+                        KeyCode::BackTab => app.previous_tab(),
+                        KeyCode::Enter => app.toggle_collapsed(),
+                        KeyCode::Char('x') if app.read_only() => {
+                            app.set_notice("Read-only connection: cancelling is disabled".to_string());
+                        }
+                        KeyCode::Char('x') => {
+                            if let Some(worker) = app.selected_worker() {
+                                if worker.status == "running" {
+                                    tokio::spawn(send_cancel(
+                                        listen_addr.clone(),
+                                        auth_token.clone(),
+                                        worker.tool.clone(),
+                                        worker.directory.clone(),
+                                    ));
+                                }
+                            }
+                        }
+                        KeyCode::Char('e') => {
+                            let notice = match export::write_export(
+                                &export_dir,
+                                &app.export_rows(),
+                                export_format,
+                            ) {
+                                Ok(path) => format!("Exported to {}", path.display()),
+                                Err(e) => format!("Export failed: {:#}", e),
+                            };
+                            app.set_notice(notice);
+                        }
+                        KeyCode::Char('r') => {
+                            // Refresh - could trigger rescan
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Mouse(mouse) if !app.confirming_quit() => match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => app.click(mouse.column, mouse.row),
+                    MouseEventKind::ScrollDown => app.scroll(1),
+                    MouseEventKind::ScrollUp => app.scroll(-1),
+                    _ => {}
+                },
+                Event::Resize(..) => app.mark_dirty(),
+                _ => {}
+            }
+        }
+
+        if last_tick.elapsed() >= tick_rate {
+            app.tick(Instant::now());
+            last_tick = Instant::now();
+        }
+    }
+}
+
+fn ui(f: &mut Frame, app: &mut App, theme: &Theme, stale: Option<Duration>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(if app.daemon_notice.is_some() { 1 } else { 0 }),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    // Header, replaced by a banner while the daemon is unreachable
+    let header = match app.connection {
+        Connection::Connected => match stale {
+            Some(silent) => Paragraph::new(format!(
+                "Freight NFS Migration Suite — data is {} seconds stale",
+                silent.as_secs()
+            ))
+            .style(theme.alert),
+            None => match app.daemon_uptime() {
+                Some(uptime) => Paragraph::new(format!(
+                    "Freight NFS Migration Suite — uptime {}",
+                    format_duration(uptime)
+                )),
+                None => Paragraph::new("Freight NFS Migration Suite"),
+            }
+            .style(theme.accent),
+        },
+        Connection::Connecting => Paragraph::new("Connecting to daemon…").style(theme.running),
+        Connection::Retrying => {
+            Paragraph::new("Disconnected from daemon — retrying").style(theme.alert)
+        }
+        Connection::GaveUp => {
+            Paragraph::new("Disconnected from daemon — gave up, restart to retry").style(theme.alert)
+        }
+    };
+    let header_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(30), Constraint::Length(40)])
+        .split(chunks[0]);
+    f.render_widget(header.block(Block::default().borders(Borders::ALL)), header_chunks[0]);
+
+    let overall_block = Block::default().borders(Borders::ALL).title("Overall");
+    match app.overall_progress() {
+        Some(overall) => {
+            // Say how much of the board the percentage speaks for
+            let label = if overall.covered < overall.workers {
+                format!("{:.0}% ({}/{} sized)", overall.ratio * 100.0, overall.covered, overall.workers)
+            } else {
+                format!("{:.0}%", overall.ratio * 100.0)
+            };
+            let gauge = Gauge::default()
+                .block(overall_block)
+                .gauge_style(theme.chart)
+                .ratio(overall.ratio.clamp(0.0, 1.0))
+                .label(label);
+            f.render_widget(gauge, header_chunks[1]);
+        }
+        None => {
+            let waiting = Paragraph::new("–").style(theme.muted).alignment(Alignment::Center);
+            f.render_widget(waiting.block(overall_block), header_chunks[1]);
+        }
+    }
+
+    let throughput_block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("{}/s", format_bytes(app.current_throughput())));
+    let series = app.throughput_series(throughput_block.inner(header_chunks[2]).width as usize);
+    let sparkline = Sparkline::default()
+        .block(throughput_block)
+        .data(&series)
+        .style(theme.chart);
+    f.render_widget(sparkline, header_chunks[2]);
+
+    // Phase tabs over the worker list
+    let tabs = Tabs::new(WorkerTab::TABS.iter().map(|tab| tab.title()))
+        .select(app.tab.index())
+        .style(theme.muted)
+        .highlight_style(theme.accent.add_modifier(Modifier::BOLD | Modifier::UNDERLINED));
+    f.render_widget(tabs, chunks[1]);
+
+    // Worker list
+    let workers_block = Block::default().borders(Borders::ALL).title("Workers");
+    app.place_list(workers_block.inner(chunks[2]));
+    let indent = if app.grouped { "  " } else { "" };
+    let columns = app.compact.then(|| {
+        let width = (app.list_area.width as usize).saturating_sub(indent.len());
+        compact_columns(&app.workers, width)
+    });
+    let workers: Vec<ListItem> = app
+        .rows()
+        .into_iter()
+        .enumerate()
+        .skip(app.scroll_offset)
+        .map(|(i, row)| {
+            let content = match row {
+                Row::Worker(index) => match columns {
+                    Some(columns) => {
+                        compact_worker_line(&app.workers[index], indent, columns, theme)
+                    }
+                    None => worker_line(&app.workers[index], indent, app.spinner(), theme),
+                },
+                Row::Host { host, count, bytes, collapsed } => Line::from(vec![
+                    Span::raw(if collapsed { "▸ " } else { "▾ " }),
+                    Span::styled(host, Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(
+                        format!(
+                            "  {} worker{}, {}",
+                            count,
+                            if count == 1 { "" } else { "s" },
+                            format_bytes(bytes)
+                        ),
+                        theme.muted,
+                    ),
+                ]),
+            };
+
+            let mut item = ListItem::new(content);
+            if i == app.selected {
+                item = item.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            item
+        })
+        .collect();
+
+    if workers.is_empty() {
+        let empty = Paragraph::new("Waiting for workers…")
+            .style(theme.muted)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, middle_row(workers_block.inner(chunks[2])));
+        f.render_widget(workers_block, chunks[2]);
+    } else {
+        let workers_list = List::new(workers)
+            .block(workers_block)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+        f.render_widget(workers_list, chunks[2]);
+    }
+
+    // The daemon's latest problem, such as a worker that failed to spawn
+    if let Some((level, text)) = app.daemon_notice() {
+        let (label, style) = match level {
+            NoticeLevel::Warning => ("Daemon warning", theme.warning),
+            NoticeLevel::Error => ("Daemon error", theme.alert),
+        };
+        f.render_widget(Paragraph::new(format!("{}: {}", label, text)).style(style), chunks[3]);
+    }
+
+    // Footer with controls, or a notice such as an export confirmation
+    let footer = match &app.notice {
+        Some((notice, _)) => Paragraph::new(notice.as_str()).style(theme.accent),
+        None if app.read_only => Paragraph::new(
+            "Read-only | ↑/↓: Navigate | Tab: Phase | h: Group by host | c: Compact | Enter: Collapse host | e: Export | q: Quit",
+        )
+        .style(theme.muted),
+        None => Paragraph::new(
+            "↑/↓: Navigate | Tab: Phase | x: Cancel worker | h: Group by host | c: Compact | Enter: Collapse host | e: Export | r: Refresh | q: Quit",
+        )
+        .style(theme.muted),
+    }
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[4]);
+
+    if app.confirming_quit {
+        let prompt = "Migration in progress — quit anyway? [y/N]";
+        let area = centered_rect(prompt.chars().count() as u16 + 4, 3, f.size());
+        let popup = Paragraph::new(prompt)
+            .style(theme.alert)
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+}
+
+fn worker_line<'a>(
+    worker: &'a WorkerDisplay,
+    indent: &'a str,
+    spinner: &'a str,
+    theme: &Theme,
+) -> Line<'a> {
+    // Terminal states keep the column blank so the status stays aligned
+    let spinner = if worker.status == "running" { spinner } else { " " };
+    let status = worker.status_label();
+
+    let bytes_str = worker
+        .bytes
+        .map(|b| format!(" ({})", format_bytes(b)))
+        .unwrap_or_default();
+
+    let files_str = worker
+        .files
+        .map(|n| format!(" {} file{}", format_count(n), if n == 1 { "" } else { "s" }))
+        .unwrap_or_default();
+
+    let progress_str = worker
+        .progress
+        .map(|p| format!(" {:.0}%", p * 100.0))
+        .unwrap_or_default();
+
+    let elapsed_str = worker
+        .elapsed()
+        .map(format_duration)
+        .unwrap_or_default();
+
+    // Count up in the warning color, turning to failed on the last retry
+    let retries_str = match worker.max_retries {
+        _ if worker.retries == 0 => String::new(),
+        Some(max) => format!(" retry {}/{}", worker.retries, max),
+        None => format!(" retry {}", worker.retries),
+    };
+    let retries_style = match worker.max_retries {
+        Some(max) if worker.retries >= max => theme.failed,
+        _ => theme.warning,
+    };
+
+    let message_str = worker
+        .message
+        .as_ref()
+        .map(|m| format!(" - {}", m))
+        .unwrap_or_default();
+
+    Line::from(vec![
+        Span::raw(indent),
+        Span::styled(format!("{:8}", worker.tool), theme.tool),
+        Span::raw(" "),
+        Span::styled(format!("{:20}", truncate(&worker.name, 20)), theme.text),
+        Span::raw(" "),
+        Span::styled(spinner, theme.running),
+        Span::raw(" "),
+        Span::styled(format!("{:12}", status), theme.status(status)),
+        Span::styled(format!("{:>8} ", elapsed_str), theme.text),
+        Span::styled(bytes_str, theme.muted),
+        Span::styled(files_str, theme.muted),
+        Span::styled(progress_str, theme.accent),
+        Span::styled(retries_str, retries_style),
+        Span::styled(message_str, theme.muted),
+    ])
+}
+
+/// Compact-row space after the status for elapsed time, bytes, progress
+/// and retries.
+const COMPACT_TAIL_WIDTH: usize = 26;
+/// Narrowest the compact directory column gets, however small the terminal.
+const COMPACT_MIN_DIRECTORY: usize = 8;
+
+/// Column widths for the compact layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactColumns {
+    pub tool: usize,
+    pub directory: usize,
+    pub status: usize,
+}
+
+/// Size the compact columns to their longest values, giving the directory
+/// whatever of `width` the other columns leave over.
+pub fn compact_columns(workers: &[WorkerDisplay], width: usize) -> CompactColumns {
+    let longest = |len: fn(&WorkerDisplay) -> usize| workers.iter().map(len).max().unwrap_or(0);
+    let tool = longest(|w| w.tool.len());
+    let status = longest(|w| w.status_label().len());
+    // Three single-space separators
+    let room = width.saturating_sub(tool + status + COMPACT_TAIL_WIDTH + 3);
+    let directory = longest(|w| directory_name(&w.directory).len())
+        .min(room)
+        .max(COMPACT_MIN_DIRECTORY);
+    CompactColumns { tool, directory, status }
+}
+
+/// The last component of a worker's directory; every worker shares the
+/// source root, so the rest is noise in the compact layout.
+fn directory_name(directory: &str) -> &str {
+    std::path::Path::new(directory)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(directory)
+}
+
+/// What the board calls a worker on `directory`, from `template`: `{name}`
+/// is the directory's last path component, `{parent}` the one above it and
+/// `{path}` the whole path. A directory with no parent component drops
+/// `{parent}/` altogether, so the default doesn't start with a slash.
+pub fn display_name(directory: &str, template: &str) -> String {
+    let parent = std::path::Path::new(directory)
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .and_then(|parent| parent.to_str())
+        .unwrap_or("");
+    let template = match parent {
+        "" => template.replace("{parent}/", ""),
+        _ => template.to_string(),
+    };
+    template
+        .replace("{path}", directory)
+        .replace("{parent}", parent)
+        .replace("{name}", directory_name(directory))
+}
+
+fn compact_worker_line<'a>(
+    worker: &'a WorkerDisplay,
+    indent: &'a str,
+    columns: CompactColumns,
+    theme: &Theme,
+) -> Line<'a> {
+    let status = worker.status_label();
+    let elapsed_str = worker.elapsed().map(format_duration).unwrap_or_default();
+    let bytes_str = worker.bytes.map(format_bytes).unwrap_or_default();
+    let progress_str = worker
+        .progress
+        .map(|p| format!(" {:.0}%", p * 100.0))
+        .unwrap_or_default();
+    let (retries_str, retries_style) = match worker.max_retries {
+        _ if worker.retries == 0 => (String::new(), theme.warning),
+        Some(max) => (
+            format!(" r{}/{}", worker.retries, max),
+            if worker.retries >= max { theme.failed } else { theme.warning },
+        ),
+        None => (format!(" r{}", worker.retries), theme.warning),
+    };
+
+    Line::from(vec![
+        Span::raw(indent),
+        Span::styled(format!("{:w$} ", worker.tool, w = columns.tool), theme.tool),
+        Span::styled(
+            format!(
+                "{:w$} ",
+                truncate(directory_name(&worker.directory), columns.directory),
+                w = columns.directory
+            ),
+            theme.text,
+        ),
+        Span::styled(format!("{:w$} ", status, w = columns.status), theme.status(status)),
+        Span::styled(format!("{:>6} ", elapsed_str), theme.text),
+        Span::styled(bytes_str, theme.muted),
+        Span::styled(progress_str, theme.accent),
+        Span::styled(retries_str, retries_style),
+    ])
+}
+
+/// A `width` x `height` box in the middle of `area`, shrunk to fit.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// The single row halfway down `area`, for centered one-line messages.
+fn middle_row(area: Rect) -> Rect {
+    Rect {
+        y: area.y + area.height / 2,
+        height: area.height.min(1),
+        ..area
+    }
+}
+
+/// Format a byte count using 1024-based units, as the bash tools do.
+/// `u64::MAX` tops out at 16.0 EB, so EB is the last unit ever needed.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB", "EB"];
+    format_scaled(bytes, 1024, UNITS)
+}
+
+/// Format a byte count using 1000-based SI units, matching `df -H` and
+/// most vendor dashboards.
+pub fn format_bytes_si(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+    format_scaled(bytes, 1000, UNITS)
+}
+
+fn format_scaled(bytes: u64, base: u64, units: &[&str]) -> String {
+    // Pick the unit with integer math so values near u64::MAX don't get
+    // bumped across a boundary by f64 rounding; only the final division
+    // is lossy, and at one decimal place that loss is invisible.
+    let mut unit_index = 0;
+    let mut divisor: u64 = 1;
+    while unit_index < units.len() - 1 && bytes / divisor >= base {
+        divisor *= base;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        return format!("{} {}", bytes, units[unit_index]);
+    }
+
+    // 1048575 bytes is 1023.999 KB, which would print as "1024.0 KB"
+    let mut size = bytes as f64 / divisor as f64;
+    if size >= base as f64 - 0.05 && unit_index < units.len() - 1 {
+        size /= base as f64;
+        unit_index += 1;
+    }
+
+    format!("{:.1} {}", size, units[unit_index])
+}
+
+/// Group digits in thousands: `1,234,567`.
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Compact elapsed time: `45s`, `2m14s`, `3h05m`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// Shorten `s` to `max_len` characters, counting chars rather than bytes
+/// so a cut never lands inside a multi-byte one.
+fn truncate(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
         s.to_string()
     } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
+        let kept: String = s.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", kept)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bytes_at_unit_boundaries() {
+        for (bytes, text) in [
+            (0, "0 B"),
+            (1023, "1023 B"),
+            (1024, "1.0 KB"),
+            (1536, "1.5 KB"),
+            (1_048_575, "1.0 MB"),
+            (1_048_576, "1.0 MB"),
+            (1_073_741_823, "1.0 GB"),
+            (1 << 30, "1.0 GB"),
+            (1 << 40, "1.0 TB"),
+            (1 << 50, "1.0 PB"),
+            ((1 << 60) - 1, "1.0 EB"),
+            (1 << 60, "1.0 EB"),
+        ] {
+            assert_eq!(format_bytes(bytes), text, "{} bytes", bytes);
+        }
+    }
+
+    #[test]
+    fn format_bytes_near_u64_max_stays_in_exabytes() {
+        // f64 can't hold these exactly, but the unit is picked with integer
+        // math and one decimal hides the rounding
+        assert_eq!(format_bytes(u64::MAX), "16.0 EB");
+        assert_eq!(format_bytes(u64::MAX - 1), "16.0 EB");
+        assert_eq!(format_bytes(u64::MAX / 2), "8.0 EB");
+        assert_eq!(format_bytes_si(u64::MAX), "18.4 EB");
+    }
+
+    #[test]
+    fn format_bytes_si_uses_powers_of_1000() {
+        assert_eq!(format_bytes_si(999), "999 B");
+        assert_eq!(format_bytes_si(1000), "1.0 kB");
+        assert_eq!(format_bytes_si(999_999), "1.0 MB");
+        assert_eq!(format_bytes_si(1_500_000_000), "1.5 GB");
+    }
+
+    /// A worker as the board would hold it after a START.
+    fn worker(tool: &str, directory: &str, status: &str) -> WorkerDisplay {
+        WorkerDisplay {
+            tool: tool.to_string(),
+            directory: directory.to_string(),
+            name: display_name(directory, DEFAULT_NAME_TEMPLATE),
+            status: status.to_string(),
+            progress: None,
+            message: None,
+            bytes: None,
+            bytes_total: None,
+            files: None,
+            host: None,
+            started_at: Some(SystemTime::now()),
+            finished_at: None,
+            retries: 0,
+            max_retries: None,
+        }
+    }
+
+    #[test]
+    fn format_duration_covers_seconds_minutes_and_hours() {
+        for (secs, text) in [
+            (0, "0s"),
+            (59, "59s"),
+            (60, "1m00s"),
+            (3599, "59m59s"),
+            (3600, "1h00m"),
+            (27 * 3600 + 5 * 60 + 9, "27h05m"),
+        ] {
+            assert_eq!(format_duration(Duration::from_secs(secs)), text, "{}s", secs);
+        }
+    }
+
+    #[test]
+    fn elapsed_runs_until_the_worker_finishes() {
+        let mut running = worker("migrate", "/src/a", "running");
+        running.started_at = Some(SystemTime::now() - Duration::from_secs(90));
+        let elapsed = running.elapsed().unwrap();
+        assert!(elapsed >= Duration::from_secs(90) && elapsed < Duration::from_secs(100));
+
+        let mut finished = running.clone();
+        finished.finished_at = Some(running.started_at.unwrap() + Duration::from_secs(42));
+        assert_eq!(finished.elapsed(), Some(Duration::from_secs(42)));
+
+        let mut waiting = running;
+        waiting.started_at = None;
+        assert_eq!(waiting.elapsed(), None);
+    }
+
+    fn app_with(directories: &[&str]) -> App {
+        let mut app = App::new();
+        for directory in directories {
+            app.update_worker("migrate", directory, "running", None, None);
+        }
+        app
+    }
+
+    fn selected_directory(app: &App) -> Option<&str> {
+        app.selected_worker().map(|worker| worker.directory.as_str())
+    }
+
+    #[test]
+    fn removing_the_last_selected_worker_clamps_the_selection() {
+        let mut app = app_with(&["/src/a", "/src/b", "/src/c"]);
+        app.previous();
+        assert_eq!(selected_directory(&app), Some("/src/c"));
+
+        app.remove_worker("migrate", "/src/c");
+        assert_eq!(selected_directory(&app), Some("/src/b"));
+
+        app.remove_worker("migrate", "/src/a");
+        app.remove_worker("migrate", "/src/b");
+        assert_eq!(app.selected, 0);
+        assert_eq!(selected_directory(&app), None);
+        app.next();
+        app.scroll(3);
+        assert_eq!(app.selected, 0);
+    }
+
+    #[test]
+    fn removing_another_worker_keeps_the_selection_on_its_worker() {
+        let mut app = app_with(&["/src/a", "/src/b", "/src/c"]);
+        app.next();
+        assert_eq!(selected_directory(&app), Some("/src/b"));
+
+        app.remove_worker("migrate", "/src/a");
+
+        assert_eq!(selected_directory(&app), Some("/src/b"));
+    }
+
+    /// How many of `ticks` ticks would redraw the board.
+    fn redraws(app: &mut App, ticks: u32) -> u32 {
+        let start = Instant::now();
+        (1..=ticks)
+            .filter(|&n| {
+                app.tick(start + Duration::from_millis(250) * n);
+                app.take_dirty()
+            })
+            .count() as u32
+    }
+
+    #[test]
+    fn idle_board_stops_redrawing() {
+        let mut app = App::new();
+        for directory in ["/src/a", "/src/b"] {
+            let mut worker = worker("migrate", directory, "completed");
+            worker.bytes = Some(1024);
+            worker.finished_at = Some(SystemTime::now());
+            app.workers.push(worker);
+        }
+        app.mark_dirty();
+        assert!(app.take_dirty());
+
+        assert_eq!(redraws(&mut app, 100), 0);
+
+        app.update_worker("migrate", "/src/b", "completed", None, Some(1024));
+        assert!(app.take_dirty());
+        assert_eq!(redraws(&mut app, 100), 0);
+    }
+
+    #[test]
+    fn running_worker_redraws_every_tick() {
+        let mut app = App::new();
+        app.workers.push(worker("migrate", "/src/a", "running"));
+
+        assert_eq!(redraws(&mut app, 100), 100);
+    }
+
+    /// Draw `app` on a 160x30 screen, one string per line.
+    fn render(app: &mut App) -> Vec<String> {
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(160, 30)).unwrap();
+        terminal.draw(|f| ui(f, app, &Theme::default(), None)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect())
+            .collect()
+    }
+
+    fn line_with<'a>(screen: &'a [String], text: &str) -> Option<&'a String> {
+        screen.iter().find(|line| line.contains(text))
+    }
+
+    #[test]
+    fn empty_board_says_it_is_waiting_for_workers() {
+        let mut app = App::new();
+        app.set_connection(Connection::Connected);
+
+        let screen = render(&mut app);
+
+        let line = line_with(&screen, "Waiting for workers…").expect("no empty-state message");
+        let inside = line.trim().trim_matches('│');
+        assert_eq!(inside.trim(), "Waiting for workers…");
+        let left = inside.len() - inside.trim_start().len();
+        let right = inside.len() - inside.trim_end().len();
+        assert!(left.abs_diff(right) <= 1, "not centered: {:?}", line);
+
+        app.update_worker("migrate", "/src/a", "running", None, None);
+        assert!(line_with(&render(&mut app), "Waiting for workers").is_none());
+    }
+
+    #[test]
+    fn lost_connection_replaces_the_header_with_a_banner() {
+        let mut app = App::new();
+        assert!(line_with(&render(&mut app), "Connecting to daemon…").is_some());
+
+        app.set_connection(Connection::Retrying);
+        let screen = render(&mut app);
+        assert!(line_with(&screen, "Disconnected from daemon — retrying").is_some());
+        assert!(line_with(&screen, "Freight NFS Migration Suite").is_none());
+
+        app.set_connection(Connection::GaveUp);
+        assert!(line_with(&render(&mut app), "gave up, restart to retry").is_some());
+    }
+
+    #[tokio::test]
+    async fn dashboard_attaches_to_a_daemon_that_starts_later() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let listen_addr = ListenAddr::Tcp(port.to_string());
+        let settings = DashboardSettings {
+            listen_addr: listen_addr.clone(),
+            retry_interval: Duration::from_millis(50),
+            ..DashboardSettings::from_config(None)
+        };
+        let (update_tx, mut update_rx) = mpsc::unbounded_channel();
+        let client = tokio::spawn(maintain_connection(settings, update_tx));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let (mut server, _) = crate::SocketServer::new(16);
+        server.set_listen_addr(listen_addr.clone());
+        tokio::spawn(async move { server.start().await });
+
+        let attached = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                match update_rx.recv().await.unwrap() {
+                    DaemonUpdate::Connection(Connection::Connected) => break,
+                    DaemonUpdate::Connection(Connection::GaveUp) => panic!("gave up"),
+                    _ => {}
+                }
+            }
+            assert!(matches!(update_rx.recv().await, Some(DaemonUpdate::Snapshot(_))));
+        });
+        attached.await.expect("dashboard never attached");
+
+        // Live messages follow the snapshot on the same connection
+        let message = loop {
+            let mut worker = socket::connect(&listen_addr).await.unwrap();
+            worker.write_all(b"START tool=scan dir=/src/a\n").await.unwrap();
+            worker.shutdown().await.unwrap();
+            match tokio::time::timeout(Duration::from_millis(50), update_rx.recv()).await {
+                Ok(Some(DaemonUpdate::Message(message))) => break message,
+                Ok(None) => panic!("dashboard connection closed"),
+                _ => {}
+            }
+        };
+        assert_eq!(message.directory.as_deref(), Some("/src/a"));
+        client.abort();
+    }
+
+    /// Migrate workers on two hosts and the local machine.
+    fn app_on_hosts() -> App {
+        let mut app = App::new();
+        for (directory, host, bytes) in [
+            ("/src/a", Some("nas1"), Some(100)),
+            ("/src/b", None, None),
+            ("/src/c", Some("nas2"), Some(7)),
+            ("/src/d", Some("nas1"), Some(50)),
+            ("/src/e", None, Some(3)),
+        ] {
+            let mut worker = worker("migrate", directory, "running");
+            worker.host = host.map(str::to_string);
+            worker.bytes = bytes;
+            app.workers.push(worker);
+        }
+        app
+    }
+
+    #[test]
+    fn host_groups_bucket_workers_and_sum_their_bytes() {
+        let app = app_on_hosts();
+
+        let groups: Vec<_> = app
+            .host_groups()
+            .into_iter()
+            .map(|group| (group.host, group.workers, group.bytes))
+            .collect();
+
+        assert_eq!(
+            groups,
+            [
+                (LOCAL_HOST.to_string(), vec![1, 4], 3),
+                ("nas1".to_string(), vec![0, 3], 150),
+                ("nas2".to_string(), vec![2], 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn grouped_rows_put_workers_under_collapsible_host_headers() {
+        let mut app = app_on_hosts();
+        let header = |host: &str, count, bytes, collapsed| Row::Host {
+            host: host.to_string(),
+            count,
+            bytes,
+            collapsed,
+        };
+        assert_eq!(app.rows(), (0..5).map(Row::Worker).collect::<Vec<_>>());
+
+        app.toggle_grouped();
+        assert_eq!(
+            app.rows(),
+            [
+                header(LOCAL_HOST, 2, 3, false),
+                Row::Worker(1),
+                Row::Worker(4),
+                header("nas1", 2, 150, false),
+                Row::Worker(0),
+                Row::Worker(3),
+                header("nas2", 1, 7, false),
+                Row::Worker(2),
+            ]
+        );
+
+        app.selected = 4;
+        app.toggle_collapsed();
+        assert_eq!(app.rows()[3], header("nas1", 2, 150, true));
+        assert_eq!(app.rows().len(), 6);
+        assert_eq!(app.selected, 3);
+    }
+
+    #[test]
+    fn completion_fires_once_when_the_last_directory_finishes() {
+        let mut app = App::new();
+        assert!(!app.take_completion());
+
+        app.update_worker("scan", "/src/a", "running", None, None);
+        app.update_worker("scan", "/src/b", "running", None, None);
+        assert!(!app.take_completion());
+        // A finished scan still has its migration ahead of it
+        app.update_worker("scan", "/src/a", "completed", None, None);
+        app.update_worker("scan", "/src/b", "failed", None, None);
+        assert!(!app.take_completion());
+        app.update_worker("migrate", "/src/a", "running", None, None);
+        assert!(!app.take_completion());
+
+        app.update_worker("migrate", "/src/a", "completed", None, None);
+        assert!(app.take_completion());
+        assert!(!app.take_completion());
+        app.update_worker("migrate", "/src/a", "completed", None, None);
+        assert!(!app.take_completion());
+        assert_eq!(app.completion_summary(), "1 of 1 directories migrated, 1 failed");
+    }
+
+    #[test]
+    fn throughput_keeps_the_latest_samples_and_squeezes_them_to_fit() {
+        let mut app = app_with(&["/src/a"]);
+        let start = Instant::now();
+        app.sample_throughput(start);
+        assert_eq!(app.throughput_series(60), Vec::<u64>::new());
+
+        // One more KB/s each second, for longer than the window holds
+        let mut total = 0;
+        for second in 1..=THROUGHPUT_SAMPLES as u64 + 10 {
+            total += second * 1000;
+            app.workers[0].bytes = Some(total);
+            app.sample_throughput(start + Duration::from_secs(second));
+        }
+
+        let series = app.throughput_series(THROUGHPUT_SAMPLES);
+        assert_eq!(series.len(), THROUGHPUT_SAMPLES);
+        assert_eq!(series[0], 11_000);
+        assert_eq!(app.current_throughput(), 130_000);
+        let squeezed = app.throughput_series(60);
+        assert_eq!(squeezed.len(), 60);
+        assert_eq!(squeezed[0], 11_500);
+        assert_eq!(squeezed[59], 129_500);
+    }
+
+    const STATUSES: [&str; 6] = ["running", "completed", "planned", "failed", "cancelled", "idle"];
+
+    #[test]
+    fn color_themes_give_every_status_its_own_style() {
+        for name in [ThemeName::Dark, ThemeName::Light] {
+            let theme = name.theme();
+            let styles: Vec<Style> = STATUSES.iter().map(|status| theme.status(status)).collect();
+            for (i, style) in styles.iter().enumerate() {
+                assert!(style.fg.is_some(), "{:?} {}", name, STATUSES[i]);
+                for (j, other) in styles.iter().enumerate().skip(i + 1) {
+                    assert_ne!(style, other, "{:?}: {} vs {}", name, STATUSES[i], STATUSES[j]);
+                }
+            }
+        }
+        assert_ne!(Theme::dark(), Theme::light());
+    }
+
+    #[test]
+    fn mono_theme_marks_statuses_without_color() {
+        let theme = ThemeName::Mono.theme();
+        for status in STATUSES {
+            let style = theme.status(status);
+            assert_eq!((style.fg, style.bg), (None, None), "{}", status);
+        }
+        let plain = Style::default();
+        assert_ne!(theme.status("running"), plain);
+        assert_ne!(theme.status("failed"), plain);
+        assert_ne!(theme.status("failed"), theme.status("running"));
+        assert_ne!(theme.status("idle"), plain);
+    }
+
+    #[test]
+    fn worker_rows_show_the_files_count() {
+        let mut app = app_with(&["/src/a", "/src/b", "/src/c"]);
+        app.workers[0].files = Some(1_234_567);
+        app.workers[1].files = Some(1);
+
+        let screen = render(&mut app);
+
+        assert!(line_with(&screen, "src/a").unwrap().contains(" 1,234,567 files"));
+        assert!(line_with(&screen, "src/b").unwrap().contains(" 1 file"));
+        assert!(!line_with(&screen, "src/b").unwrap().contains(" 1 files"));
+        assert!(!line_with(&screen, "src/c").unwrap().contains(" file"));
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1000), "1,000");
+    }
+
+    #[test]
+    fn quitting_mid_migration_asks_first() {
+        let prompt = "Migration in progress — quit anyway? [y/N]";
+        let mut app = app_with(&["/src/a"]);
+
+        assert!(!app.request_quit(true));
+        assert!(app.confirming_quit());
+        assert!(line_with(&render(&mut app), prompt).is_some());
+        assert!(!app.answer_quit(false));
+        assert!(!app.confirming_quit());
+        assert!(line_with(&render(&mut app), prompt).is_none());
+
+        assert!(!app.request_quit(true));
+        assert!(app.answer_quit(true));
+    }
+
+    #[test]
+    fn quitting_is_immediate_when_nothing_runs_or_unguarded() {
+        let mut app = app_with(&["/src/a"]);
+        assert!(app.request_quit(false));
+        assert!(!app.confirming_quit());
+
+        app.update_worker("migrate", "/src/a", "completed", None, None);
+        assert!(app.request_quit(true));
+        assert!(!app.confirming_quit());
+        assert!(App::new().request_quit(true));
+    }
+
+    #[test]
+    fn quiet_daemon_is_flagged_stale_past_the_threshold() {
+        let threshold = Duration::from_secs(10);
+        let mut app = app_with(&["/src/a"]);
+        app.set_connection(Connection::Connected);
+        let heard = app.last_update;
+
+        assert_eq!(app.stale_for(heard + Duration::from_secs(9), threshold), None);
+        let stale = app.stale_for(heard + Duration::from_secs(12), threshold);
+        assert_eq!(stale, Some(Duration::from_secs(12)));
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(160, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app, &Theme::default(), stale)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let header: String = (0..160).map(|x| buffer.get(x, 2).symbol()).collect();
+        assert!(header.contains("data is 12 seconds stale"), "{}", header);
+
+        // Silence is expected once nothing runs, or while reconnecting
+        app.set_connection(Connection::Retrying);
+        assert_eq!(app.stale_for(heard + Duration::from_secs(12), threshold), None);
+        app.set_connection(Connection::Connected);
+        app.update_worker("migrate", "/src/a", "completed", None, None);
+        let heard = app.last_update;
+        assert_eq!(app.stale_for(heard + Duration::from_secs(60), threshold), None);
+    }
+
+    #[test]
+    fn spinner_cycles_through_its_frames_on_running_rows_only() {
+        let mut app = app_with(&["/src/a", "/src/b"]);
+        app.update_worker("migrate", "/src/b", "completed", None, None);
+        let first = app.spinner();
+
+        let mut seen = vec![first];
+        for _ in 1..SPINNER_FRAMES.len() {
+            app.advance_spinner();
+            seen.push(app.spinner());
+        }
+        assert_eq!(seen, SPINNER_FRAMES);
+        app.advance_spinner();
+        assert_eq!(app.spinner(), first);
+
+        app.advance_spinner();
+        let screen = render(&mut app);
+        assert!(line_with(&screen, "src/a").unwrap().contains(SPINNER_FRAMES[1]));
+        let finished = line_with(&screen, "src/b").unwrap();
+        assert!(!SPINNER_FRAMES.iter().any(|frame| finished.contains(frame)), "{}", finished);
+    }
+
+    #[test]
+    fn export_serializes_the_rows_on_the_board() {
+        let mut app = App::new();
+        let started = SystemTime::now();
+        let mut done = worker("migrate", "/src/a, b", "completed");
+        done.started_at = Some(started);
+        done.finished_at = Some(started + Duration::from_secs(90));
+        done.bytes = Some(2048);
+        done.bytes_total = Some(2048);
+        done.files = Some(3);
+        done.progress = Some(1.0);
+        done.message = Some("said \"done\"".to_string());
+        let mut failed = worker("scan", "/src/c", "failed");
+        failed.started_at = None;
+        failed.host = Some("nas1".to_string());
+        app.workers = vec![done, failed];
+
+        let rows = app.export_rows();
+        let csv = export::render(&rows, ExportFormat::Csv).unwrap();
+        let json = export::render(&rows, ExportFormat::Json).unwrap();
+
+        assert_eq!(
+            csv,
+            "host,tool,directory,status,elapsed_secs,bytes,bytes_total,files,progress,message\n\
+             local,migrate,\"/src/a, b\",completed,90,2048,2048,3,100,\"said \"\"done\"\"\"\n\
+             nas1,scan,/src/c,failed,,,,,,\n"
+        );
+        let parsed: Vec<ExportRow> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, rows);
+        assert_eq!(parsed[1].elapsed_secs, None);
+    }
+
+    #[test]
+    fn overall_progress_counts_only_sized_transfers() {
+        let mut app = app_with(&["/src/a", "/src/b", "/src/c"]);
+        assert_eq!(app.overall_progress(), None);
+
+        app.workers[0].bytes = Some(300);
+        app.workers[0].bytes_total = Some(1000);
+        app.workers[1].bytes = Some(5000);
+        app.workers[2].bytes = Some(100);
+        app.workers[2].bytes_total = Some(0);
+        let mut scan = worker("scan", "/src/d", "completed");
+        scan.bytes_total = Some(1_000_000);
+        app.workers.push(scan);
+
+        let overall = app.overall_progress().unwrap();
+        assert_eq!(overall, OverallProgress { ratio: 0.3, covered: 1, workers: 3 });
+        assert!(line_with(&render(&mut app), "30% (1/3 sized)").is_some());
+
+        // Overshooting a stale total doesn't push past 100%
+        app.workers[1].bytes_total = Some(1000);
+        let overall = app.overall_progress().unwrap();
+        assert_eq!((overall.ratio, overall.covered), (0.65, 2));
+    }
+
+    #[test]
+    fn handshaked_workers_show_as_idle_in_their_own_color() {
+        let mut app = app_with(&["/src/b"]);
+        app.update_worker("migrate", "/src/a", "connected", None, None);
+        assert_eq!(app.workers[1].status_label(), "idle");
+        assert_eq!(app.workers[0].status_label(), "running");
+
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(160, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app, &theme, None)).unwrap();
+        let buffer = terminal.backend().buffer();
+        // Color of the first cell of `label` on the row naming `name`
+        let style_of = |name: &str, label: &str| {
+            let row = |y| (0..160).map(|x| buffer.get(x, y).symbol()).collect::<Vec<_>>();
+            let y = (0..30).find(|&y| row(y).concat().contains(name)).unwrap();
+            let line = row(y);
+            let x = (0..line.len() - label.len())
+                .find(|&x| line[x..x + label.len()].concat() == label)
+                .unwrap();
+            buffer.get(x as u16, y).fg
+        };
+
+        assert_eq!(style_of("src/a", "idle"), theme.idle.fg.unwrap());
+        assert_eq!(style_of("src/b", "running"), theme.running.fg.unwrap());
+        assert_ne!(theme.idle.fg, theme.running.fg);
+    }
+
+    #[test]
+    fn tabs_filter_the_worker_list_by_tool() {
+        let mut app = app_with(&["/src/b", "/src/c"]);
+        app.update_worker("scan", "/src/a", "running", None, None);
+        let shown = |app: &mut App| {
+            let screen = render(app);
+            ["src/a", "src/b", "src/c"]
+                .into_iter()
+                .filter(|name| line_with(&screen, name).is_some())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(app.tab(), WorkerTab::All);
+        assert_eq!(shown(&mut app), ["src/a", "src/b", "src/c"]);
+        app.next_tab();
+        assert_eq!(app.tab(), WorkerTab::Scan);
+        assert_eq!(shown(&mut app), ["src/a"]);
+        app.next_tab();
+        assert_eq!(app.tab(), WorkerTab::Migrate);
+        assert_eq!(shown(&mut app), ["src/b", "src/c"]);
+        assert_eq!(app.selected_worker().unwrap().tool, "migrate");
+        app.previous_tab();
+        app.previous_tab();
+        assert_eq!(app.tab(), WorkerTab::All);
+    }
+
+    #[test]
+    fn clicks_select_the_worker_drawn_under_them() {
+        let names: Vec<String> = (0..40).map(|n| format!("/src/w{:02}", n)).collect();
+        let mut app = app_with(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let y_of = |screen: &[String], name: &str| {
+            screen.iter().position(|line| line.contains(name)).unwrap() as u16
+        };
+
+        let screen = render(&mut app);
+        app.click(app.list_area.x + 1, y_of(&screen, "src/w05"));
+        assert_eq!(selected_directory(&app), Some("/src/w05"));
+        // Outside the list, nothing changes
+        app.click(0, 0);
+        assert_eq!(selected_directory(&app), Some("/src/w05"));
+
+        // Once scrolled, the same cell holds a later worker
+        for _ in 0..30 {
+            app.next();
+        }
+        let screen = render(&mut app);
+        assert!(app.scroll_offset > 0);
+        let top = app.list_area.y;
+        assert_eq!(app.row_at(app.list_area.x, top), Some(app.scroll_offset));
+        let name = &names[app.scroll_offset + 2];
+        app.click(app.list_area.x + 1, y_of(&screen, &name[1..]));
+        assert_eq!(selected_directory(&app), Some(name.as_str()));
+
+        app.scroll(1);
+        assert_eq!(selected_directory(&app), Some(names[app.scroll_offset + 3].as_str()));
+        app.scroll(-100);
+        assert_eq!(selected_directory(&app), Some("/src/w00"));
+    }
+
+    #[test]
+    fn retry_count_matches_the_attempts_the_daemon_tracked() {
+        let message = |message_type, text: Option<&str>| WorkerMessage {
+            message_type,
+            tool: "migrate".to_string(),
+            directory: Some("/src/a".to_string()),
+            status: None,
+            bytes: None,
+            bytes_total: None,
+            files: None,
+            message: text.map(str::to_string),
+            host: None,
+            pid: None,
+        };
+        let messages = [
+            message(MessageType::Start, None),
+            message(MessageType::Progress, Some("Attempt 1/3")),
+            message(MessageType::Progress, Some("Attempt 2/3")),
+            message(MessageType::Progress, Some("copying")),
+        ];
+
+        // Streamed live, and rebuilt from the daemon's map after a reconnect
+        let mut live = App::new();
+        let mut daemon = HashMap::new();
+        let id = crate::worker::WorkerKey::new("migrate", "/src/a");
+        for message in messages.clone() {
+            crate::socket::apply_message(&mut daemon, &id, &message, None);
+            live.apply_message(message);
+        }
+        let mut reconnected = App::new();
+        reconnected.apply_snapshot(WorkersSnapshot { workers: daemon.clone(), started_at: None });
+
+        assert_eq!(daemon[&id].attempts, 2);
+        for app in [&mut live, &mut reconnected] {
+            assert_eq!((app.workers[0].retries, app.workers[0].max_retries), (1, Some(2)));
+            assert!(line_with(&render(app), "src/a").unwrap().contains(" retry 1/2"));
+        }
+
+        live.apply_message(message(MessageType::Progress, Some("Attempt 3/3")));
+        assert!(line_with(&render(&mut live), "src/a").unwrap().contains(" retry 2/2"));
+    }
+
+    #[tokio::test]
+    async fn connect_attaches_read_only_to_a_socket_at_a_custom_path() {
+        let dir = tempfile::tempdir().unwrap();
+        // Where `ssh -L` might have put a forwarded socket
+        let listen_addr = ListenAddr::Unix(dir.path().join("forwarded.sock"));
+        let (mut server, _) = crate::SocketServer::new(16);
+        server.set_listen_addr(listen_addr.clone());
+        tokio::spawn(async move { server.start().await });
+        let settings = DashboardSettings {
+            listen_addr,
+            read_only: true,
+            retry_interval: Duration::from_millis(50),
+            ..DashboardSettings::from_config(None)
+        };
+        let (update_tx, mut update_rx) = mpsc::unbounded_channel();
+        let client = tokio::spawn(maintain_connection(settings.clone(), update_tx));
+
+        let attached = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let DaemonUpdate::Snapshot(snapshot) = update_rx.recv().await.unwrap() {
+                    break snapshot;
+                }
+            }
+        });
+        assert!(attached.await.expect("never attached").workers.is_empty());
+        client.abort();
+
+        let mut app = App::new();
+        app.set_read_only(settings.read_only);
+        let screen = render(&mut app);
+        assert!(line_with(&screen, "Read-only").is_some());
+        assert!(line_with(&screen, "x: Cancel worker").is_none());
+    }
+
+    #[test]
+    fn compact_columns_fit_the_longest_values_and_the_terminal() {
+        let workers = [
+            worker("scan", "/src/short", "running"),
+            worker("migrate", "/src/a-rather-long-directory-name", "completed"),
+        ];
+        let tail = COMPACT_TAIL_WIDTH + 3;
+
+        // Wide enough for everything: each column is its longest value
+        let wide = compact_columns(&workers, 200);
+        assert_eq!(wide, CompactColumns { tool: 7, directory: 28, status: 9 });
+        // Narrower: the directory gives up what the other columns need
+        let narrow = compact_columns(&workers, 7 + 9 + tail + 12);
+        assert_eq!(narrow.directory, 12);
+        assert_eq!((narrow.tool, narrow.status), (7, 9));
+        // Never squeezed below the minimum, however small the terminal
+        assert_eq!(compact_columns(&workers, 20).directory, COMPACT_MIN_DIRECTORY);
+
+        let short = [worker("scan", "/src/ab", "connected")];
+        assert_eq!(
+            compact_columns(&short, 200),
+            CompactColumns { tool: 4, directory: COMPACT_MIN_DIRECTORY, status: 4 }
+        );
+        assert_eq!(
+            compact_columns(&[], 80),
+            CompactColumns { tool: 0, directory: COMPACT_MIN_DIRECTORY, status: 0 }
+        );
+    }
+
+    #[test]
+    fn long_non_ascii_names_are_cut_between_characters() {
+        let mut app = app_with(&[&format!("/src/{}", "é".repeat(200))]);
+        let screen = render(&mut app);
+        assert!(line_with(&screen, &format!(" src/{}... ", "é".repeat(13))).is_some());
+        app.toggle_compact();
+        let screen = render(&mut app);
+        assert!(line_with(&screen, "é... ").is_some());
+        assert_eq!(truncate("日本語のディレクトリ", 8), "日本語のデ...");
+    }
+
+    #[test]
+    fn queued_updates_land_in_one_pass_up_to_the_cap() {
+        let message = |message_type, directory: &str, bytes, status: Option<&str>| {
+            DaemonUpdate::Message(WorkerMessage {
+                message_type,
+                tool: "migrate".to_string(),
+                directory: Some(directory.to_string()),
+                status: status.map(str::to_string),
+                bytes,
+                bytes_total: None,
+                files: None,
+                message: None,
+                host: None,
+                pid: None,
+            })
+        };
+        let queue = || {
+            let (update_tx, update_rx) = mpsc::unbounded_channel();
+            for directory in ["/src/a", "/src/b", "/src/c"] {
+                update_tx.send(message(MessageType::Start, directory, None, None)).unwrap();
+                for bytes in 1..=50 {
+                    let progress = message(MessageType::Progress, directory, Some(bytes), None);
+                    update_tx.send(progress).unwrap();
+                }
+            }
+            update_tx.send(message(MessageType::Stop, "/src/a", Some(64), Some("ok"))).unwrap();
+            update_rx
+        };
+        let board = |app: &App| {
+            app.workers
+                .iter()
+                .map(|worker| (worker.directory.clone(), worker.status.clone(), worker.bytes))
+                .collect::<Vec<_>>()
+        };
+
+        let mut all_at_once = App::new();
+        let mut update_rx = queue();
+        assert_eq!(apply_pending(&mut all_at_once, &mut update_rx, 1000), 154);
+        assert!(update_rx.try_recv().is_err());
+        assert_eq!(
+            board(&all_at_once),
+            [
+                ("/src/a".to_string(), "completed".to_string(), Some(64)),
+                ("/src/b".to_string(), "running".to_string(), Some(50)),
+                ("/src/c".to_string(), "running".to_string(), Some(50)),
+            ]
+        );
+
+        // Capped, the rest wait for the next frames but end up the same
+        let mut capped = App::new();
+        let mut update_rx = queue();
+        assert_eq!(apply_pending(&mut capped, &mut update_rx, 100), 100);
+        assert_eq!(capped.workers.len(), 2);
+        assert_eq!(apply_pending(&mut capped, &mut update_rx, 100), 54);
+        assert_eq!(apply_pending(&mut capped, &mut update_rx, 100), 0);
+        assert_eq!(board(&capped), board(&all_at_once));
+    }
+
+    #[test]
+    fn daemon_notices_show_on_a_status_line_until_replaced() {
+        let notice = |level: NoticeLevel, text: &str| WorkerMessage {
+            message_type: MessageType::Notice,
+            tool: "daemon".to_string(),
+            directory: None,
+            status: Some(level.as_str().to_string()),
+            bytes: None,
+            bytes_total: None,
+            files: None,
+            message: Some(text.to_string()),
+            host: None,
+            pid: None,
+        };
+        let mut app = App::new();
+        assert!(line_with(&render(&mut app), "Daemon ").is_none());
+
+        app.apply_message(notice(NoticeLevel::Error, "Failed to start scan worker for /src/a"));
+        let screen = render(&mut app);
+        let line = line_with(&screen, "Daemon error: ").unwrap();
+        assert!(line.contains("Failed to start scan worker for /src/a"), "{}", line);
+        // A notice isn't a worker
+        assert!(app.workers.is_empty());
+
+        app.apply_message(notice(NoticeLevel::Warning, "Config reload rejected"));
+        let screen = render(&mut app);
+        assert!(line_with(&screen, "Daemon warning: Config reload rejected").is_some());
+        assert!(line_with(&screen, "Daemon error").is_none());
+    }
+
+    #[test]
+    fn display_names_follow_the_template_for_each_path_shape() {
+        for (directory, template, name) in [
+            ("/srv/data/projects/alpha", DEFAULT_NAME_TEMPLATE, "projects/alpha"),
+            ("/srv/data/projects/alpha/", DEFAULT_NAME_TEMPLATE, "projects/alpha"),
+            ("/srv/data/my dir/a b", DEFAULT_NAME_TEMPLATE, "my dir/a b"),
+            // Nothing above it, so no leading slash
+            ("/alpha", DEFAULT_NAME_TEMPLATE, "alpha"),
+            ("alpha", DEFAULT_NAME_TEMPLATE, "alpha"),
+            ("projects/alpha", DEFAULT_NAME_TEMPLATE, "projects/alpha"),
+            ("/srv/data/projects/alpha", "{name}", "alpha"),
+            ("/srv/data/projects/alpha", "{path}", "/srv/data/projects/alpha"),
+            ("/srv/data/projects/alpha", "{name} in {parent}", "alpha in projects"),
+        ] {
+            assert_eq!(display_name(directory, template), name, "{} as {}", directory, template);
+        }
+    }
+
+    #[test]
+    fn workers_with_the_same_name_stay_apart() {
+        let mut app = app_with(&["/srv/a/projects/alpha", "/srv/b/projects/alpha"]);
+        assert_eq!(app.workers.len(), 2);
+        assert!(app.workers.iter().all(|worker| worker.name == "projects/alpha"));
+
+        app.update_worker("migrate", "/srv/b/projects/alpha", "completed", None, None);
+        let statuses: Vec<_> = app.workers.iter().map(|w| w.status.as_str()).collect();
+        assert_eq!(statuses, ["running", "completed"]);
+
+        app.set_name_template("{path}".to_string());
+        let names: Vec<_> = app.workers.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["/srv/a/projects/alpha", "/srv/b/projects/alpha"]);
+    }
+
+    #[test]
+    fn header_shows_the_daemon_uptime_once_a_snapshot_says_when_it_started() {
+        let mut app = App::new();
+        app.set_connection(Connection::Connected);
+        assert!(line_with(&render(&mut app), "Freight NFS Migration Suite").is_some());
+        assert!(line_with(&render(&mut app), "uptime").is_none());
+
+        let started_at = Some(SystemTime::now() - Duration::from_secs(83 * 60 + 5));
+        app.apply_snapshot(WorkersSnapshot { workers: HashMap::new(), started_at });
+
+        let screen = render(&mut app);
+        assert!(line_with(&screen, "Freight NFS Migration Suite — uptime 1h23m").is_some());
     }
 }
@@ -0,0 +1,73 @@
+use crate::logging::use_color;
+use crate::Config;
+use crossterm::style::Stylize;
+use std::path::Path;
+
+/// Everything wrong with the project at `source`: its `.freight` markers,
+/// then its config as `Config::load` sees it (global config merged in),
+/// validated. Empty when the project is valid. Unlike `doctor`, nothing
+/// outside the project is looked at.
+pub fn check_project(source: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let freight_dir = source.join(".freight");
+    if !freight_dir.is_dir() {
+        problems.push(format!(
+            "{} does not exist; run `freight init` first",
+            freight_dir.display()
+        ));
+        return problems;
+    }
+    let marker = freight_dir.join(".freight-root");
+    if !marker.is_file() {
+        problems.push(format!("{} marker is missing", marker.display()));
+    }
+    let config_path = freight_dir.join("config.json");
+    if !config_path.is_file() {
+        problems.push(format!("{} does not exist", config_path.display()));
+        return problems;
+    }
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            problems.push(format!("{:#}", e));
+            return problems;
+        }
+    };
+    if let Err(e) = config.validate() {
+        problems.push(format!("{:#}", e));
+    }
+    // A copied or moved project would otherwise migrate its old source
+    let same_source = match (config.source_path.canonicalize(), source.canonicalize()) {
+        (Ok(configured), Ok(project)) => configured == project,
+        _ => false,
+    };
+    if !same_source {
+        problems.push(format!(
+            "source_path {} is not this project's directory {}",
+            config.source_path.display(),
+            source.display()
+        ));
+    }
+
+    problems
+}
+
+/// Print "valid" for a project without problems, otherwise one problem
+/// per line.
+pub fn print_problems(source: &Path, problems: &[String]) {
+    if problems.is_empty() {
+        let valid = if use_color(&std::io::stdout()) {
+            "valid".green().to_string()
+        } else {
+            "valid".to_string()
+        };
+        println!("{}: {}", source.display(), valid);
+        return;
+    }
+
+    for problem in problems {
+        println!("- {}", problem);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::report::MigrationReport;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookPayload {
+    /// A single directory's worker failed
+    DirectoryFailed {
+        tool: String,
+        directory: PathBuf,
+        status: String,
+        bytes: Option<u64>,
+        duration: Option<f64>,
+        error: Option<String>,
+    },
+    /// Every directory has finished; counts mirror the completion report
+    MigrationComplete {
+        source_path: PathBuf,
+        dest_path: PathBuf,
+        dry_run: bool,
+        directories: usize,
+        succeeded: usize,
+        failed: usize,
+        bytes: u64,
+    },
+}
+
+impl WebhookPayload {
+    pub fn summary(report: &MigrationReport) -> Self {
+        WebhookPayload::MigrationComplete {
+            source_path: report.source_path.clone(),
+            dest_path: report.dest_path.clone(),
+            dry_run: report.dry_run,
+            directories: report.directories.len(),
+            succeeded: report.succeeded(),
+            failed: report.failed(),
+            bytes: report.total_bytes(),
+        }
+    }
+}
+
+/// POST `payload` to `url` in the background. Like hooks, delivery failures
+/// are only logged; the migration never waits on the receiving end.
+pub fn spawn_webhook(url: String, payload: WebhookPayload) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Failed to build webhook client: {}", e);
+                return;
+            }
+        };
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            match client.post(&url).json(&payload).send().await {
+                Ok(response) if response.status().is_success() => {
+                    info!("Webhook delivered to {}", url);
+                    return;
+                }
+                // Client errors won't go away by asking again
+                Ok(response) if response.status().is_client_error() => {
+                    warn!("Webhook {} rejected payload: {}", url, response.status());
+                    return;
+                }
+                Ok(response) => {
+                    warn!("Webhook {} attempt {}/{} returned {}",
+                        url, attempt, MAX_ATTEMPTS, response.status());
+                }
+                Err(e) => {
+                    warn!("Webhook {} attempt {}/{} failed: {}", url, attempt, MAX_ATTEMPTS, e);
+                }
+            }
+
+            if attempt < MAX_ATTEMPTS {
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+            }
+        }
+
+        warn!("Giving up on webhook {} after {} attempts", url, MAX_ATTEMPTS);
+    })
+}
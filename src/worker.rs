@@ -1,18 +1,193 @@
-use crate::{Config, WorkerMessage};
-use anyhow::Result;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::doctor::find_on_path;
+use crate::history::{HistoryDb, HistoryRun};
+use crate::manifest::{self, DirectoryManifest};
+use crate::plan::MigrationPlan;
+use crate::progress::{self, ProgressParser};
+use crate::report::{self, DirectoryReport, MigrationReport, RunTotals};
+use crate::socket::{is_attempt_message, ListenAddr, NoticeLevel, AUTH_TOKEN_ENV};
+use crate::webhook::{self, WebhookPayload};
+use crate::tui::format_bytes;
+use crate::{Config, SocketServer, WorkerMessage};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::process::Command;
-use tokio::sync::broadcast;
-use tracing::{info, error};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, warn, error};
+
+pub const SCAN_BINARY: &str = "freight-scan";
+pub const MIGRATE_BINARY: &str = "freight-migrate";
+
+/// Default for `max_captured_output`.
+pub const DEFAULT_MAX_CAPTURED_OUTPUT: usize = 64 * 1024;
+
+/// How often running workers are checked against `worker_timeout`.
+const TIMEOUT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// How long after a tool exits its STOP may still be in flight; only then
+/// does the exit itself settle the worker.
+const EXIT_STOP_GRACE: Duration = Duration::from_secs(2);
+
+/// Fail fast when a tool binary is missing: every spawn would fail and the
+/// migration would wait forever for workers that never report.
+pub fn ensure_tool_binaries() -> Result<()> {
+    for binary in [SCAN_BINARY, MIGRATE_BINARY] {
+        if find_on_path(binary).is_none() {
+            anyhow::bail!(
+                "{} not found on PATH; add the freight bin/ directory to PATH \
+                 (run `freight doctor` to check the setup)",
+                binary
+            );
+        }
+    }
+    Ok(())
+}
+
+/// rsync flags a migrate worker runs with: `rsync_flags`, or
+/// `tool_flags.migrate`, plus whatever `existing_policy`, `resume_partial`
+/// and `checksum_algorithm` add.
+pub fn migrate_rsync_flags(config: &Config) -> String {
+    let mut rsync_flags = config.rsync_flags_for("migrate").to_string();
+    for flag in config.existing_policy.rsync_flags() {
+        rsync_flags.push(' ');
+        rsync_flags.push_str(flag);
+    }
+    if config.resume_partial {
+        add_partial_flags(&mut rsync_flags);
+    }
+    if let Some(algorithm) = config.checksum_algorithm {
+        add_checksum_flags(&mut rsync_flags, algorithm);
+    }
+    rsync_flags
+}
+
+/// Dry-run rsync with the migrate flags between two empty directories, so
+/// a typo or a flag this rsync doesn't know fails once at startup instead
+/// of in every worker. Nothing to check for the other backends, or when
+/// rsync isn't installed (`freight doctor` reports that).
+pub fn check_rsync_flags(config: &Config) -> Result<()> {
+    if config.backend != MigrationBackend::Rsync || find_on_path("rsync").is_none() {
+        return Ok(());
+    }
+    
+    let flags = migrate_rsync_flags(config);
+    let scratch = std::env::temp_dir().join(format!("freight-flags-{}", uuid::Uuid::new_v4()));
+    let source = scratch.join("source");
+    std::fs::create_dir_all(&source)
+        .with_context(|| format!("Failed to create {}", source.display()))?;
+    // Expanded by the shell, as freight-migrate does, so quoting matches
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("rsync {} --dry-run \"$0/\" \"$1\"", flags))
+        .arg(&source)
+        .arg(scratch.join("dest"))
+        .output();
+    let _ = std::fs::remove_dir_all(&scratch);
+    let output = output.context("Failed to run rsync to check rsync_flags")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // rsync names the bad flag on its first line, e.g.
+        // "rsync: --bogus: unknown option"
+        let problem = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+        anyhow::bail!(
+            "rsync rejected the migrate flags \"{}\": {}",
+            flags,
+            problem.trim()
+        );
+    }
+    Ok(())
+}
+
+/// Create the destination root once, with `dest_mode` and `dest_owner`,
+/// so a missing one that can't be made stops the run with one clear error
+/// instead of failing every worker. A dry run creates nothing; it only
+/// checks that the real run could. A remote root is left to rsync.
+pub fn prepare_dest_root(config: &Config) -> Result<()> {
+    let root = config.dest_root();
+    if config.dest_is_remote() || root.is_dir() {
+        return Ok(());
+    }
+    if root.exists() {
+        anyhow::bail!("Destination {} is not a directory", root.display());
+    }
+    if config.dry_run {
+        // A relative root runs out of ancestors at the empty path
+        let ancestor = root.ancestors().find(|path| path.exists()).unwrap_or(Path::new("."));
+        if !ancestor.is_dir() || !is_writable(ancestor) {
+            anyhow::bail!(
+                "Destination {} does not exist, and {} is not a writable directory to create it in",
+                root.display(),
+                ancestor.display()
+            );
+        }
+        info!("Dry run: would create destination {}", root.display());
+        return Ok(());
+    }
+    
+    info!("Creating destination {}", root.display());
+    let permissions = config.dest_permissions()?;
+    let created = if permissions.is_set() {
+        permissions.apply(root)
+    } else {
+        std::fs::create_dir_all(root).map_err(Into::into)
+    };
+    created.with_context(|| {
+        format!("Destination {} does not exist and could not be created", root.display())
+    })
+}
+
+/// Whether this process could create entries in `dir`.
+fn is_writable(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: access only reads the NUL-terminated path it is given
+    unsafe { libc::access(path.as_ptr(), libc::W_OK | libc::X_OK) == 0 }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum WorkerStatus {
     Pending,
     Running,
     Completed,
+    Planned,
     Failed,
+    Cancelled,
+    /// Not migrated: unchanged since its last successful migration
+    Skipped,
+}
+
+impl WorkerStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkerStatus::Pending => "pending",
+            WorkerStatus::Running => "running",
+            WorkerStatus::Completed => "completed",
+            WorkerStatus::Planned => "planned",
+            WorkerStatus::Failed => "failed",
+            WorkerStatus::Cancelled => "cancelled",
+            WorkerStatus::Skipped => "skipped",
+        }
+    }
+    
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            WorkerStatus::Completed
+                | WorkerStatus::Planned
+                | WorkerStatus::Failed
+                | WorkerStatus::Cancelled
+                | WorkerStatus::Skipped
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,11 +196,447 @@ pub struct WorkerInfo {
     pub directory: PathBuf,
     pub status: WorkerStatus,
     pub pid: Option<u32>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub bytes: Option<u64>,
+    pub attempts: u32,
+    pub error: Option<String>,
+    /// Start or latest PROGRESS, whichever came last
+    last_activity: Instant,
+    /// Failed by `worker_timeout` rather than by its own STOP
+    timed_out: bool,
+    /// Settled by its process exiting without ever sending STOP
+    exited: bool,
+    /// Settled by the manager without ever being spawned
+    unspawned: bool,
+}
+
+impl WorkerInfo {
+    fn running(tool: &str, directory: &Path, pid: Option<u32>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            directory: directory.to_path_buf(),
+            status: WorkerStatus::Running,
+            pid,
+            started_at: Utc::now(),
+            finished_at: None,
+            bytes: None,
+            attempts: 0,
+            error: None,
+            last_activity: Instant::now(),
+            timed_out: false,
+            exited: false,
+            unspawned: false,
+        }
+    }
+    
+    fn duration_secs(&self) -> Option<f64> {
+        self.finished_at
+            .map(|end| (end - self.started_at).num_milliseconds() as f64 / 1000.0)
+    }
+}
+
+/// Identifies a worker by tool and directory. Kept structured rather than
+/// as a "tool:dir" string so directory names containing colons or spaces
+/// can't collide or be mis-split.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorkerKey {
+    pub tool: String,
+    pub directory: PathBuf,
+}
+
+impl WorkerKey {
+    pub fn new(tool: &str, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            directory: directory.into(),
+        }
+    }
+    
+    /// The key a worker message refers to, if it names a directory.
+    pub(crate) fn for_message(message: &WorkerMessage) -> Option<Self> {
+        let directory = message.directory.as_deref()?;
+        Some(Self::new(&message.tool, directory))
+    }
+}
+
+impl std::fmt::Display for WorkerKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.tool, self.directory.display())
+    }
+}
+
+/// A spawned tool's process ending, reported by the task waiting on it.
+#[derive(Debug)]
+struct WorkerExit {
+    key: WorkerKey,
+    pid: Option<u32>,
+    status: std::process::ExitStatus,
+}
+
+/// Requests from the daemon to a running migration.
+#[derive(Debug, Clone)]
+pub enum ControlMessage {
+    /// Stop spawning workers and return once the running ones finish
+    Drain,
+    /// Apply a re-read config to workers spawned from now on
+    Reload(Box<Config>),
+    /// SIGTERM one running worker, leaving the rest of the migration alone
+    Cancel { tool: String, directory: String },
+}
+
+/// Order in which scanned directories are handed to migrate workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulePolicy {
+    /// Migrate in the order scans finish
+    #[default]
+    Discovery,
+    /// Biggest directories first, so the long pole starts early
+    LargestFirst,
+    SmallestFirst,
+    Alphabetical,
+}
+
+impl SchedulePolicy {
+    /// Whether `a` should be migrated before `b`. Sizes come from the scan
+    /// phase; a directory without one sorts after those that have one.
+    fn runs_before(&self, a: (&Path, Option<u64>), b: (&Path, Option<u64>)) -> bool {
+        match self {
+            SchedulePolicy::Discovery => false,
+            SchedulePolicy::LargestFirst => a.1.unwrap_or(0) > b.1.unwrap_or(0),
+            SchedulePolicy::SmallestFirst => match (a.1, b.1) {
+                (Some(a), Some(b)) => a < b,
+                (Some(_), None) => true,
+                _ => false,
+            },
+            SchedulePolicy::Alphabetical => a.0 < b.0,
+        }
+    }
+}
+
+/// What a migrate worker does about data already in its destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExistingPolicy {
+    /// Let rsync replace files that differ
+    #[default]
+    Overwrite,
+    /// Leave files already present in the destination untouched
+    SkipExisting,
+    /// Fail the worker if its destination directory is non-empty
+    ErrorIfPresent,
+}
+
+impl ExistingPolicy {
+    /// rsync flags to add on top of the configured ones.
+    pub fn rsync_flags(&self) -> &'static [&'static str] {
+        match self {
+            ExistingPolicy::Overwrite | ExistingPolicy::ErrorIfPresent => &[],
+            ExistingPolicy::SkipExisting => &["--ignore-existing"],
+        }
+    }
+}
+
+/// Where rsync keeps partly copied files, relative to each destination.
+pub const PARTIAL_DIR: &str = ".rsync-partial";
+
+/// Add `--partial` and `--partial-dir` to `flags` unless they already say
+/// otherwise, `-P` and `--no-partial` included.
+pub fn add_partial_flags(flags: &mut String) {
+    let mut partial = false;
+    let mut partial_dir = false;
+    for flag in flags.split_whitespace() {
+        partial |= flag == "--partial"
+            || flag == "--no-partial"
+            || (flag.starts_with('-') && !flag.starts_with("--") && flag.contains('P'));
+        partial_dir |= flag.starts_with("--partial-dir");
+    }
+    
+    if !partial {
+        flags.push_str(" --partial");
+    }
+    if !partial_dir {
+        flags.push_str(&format!(" --partial-dir={}", PARTIAL_DIR));
+    }
+}
+
+/// Mode and ownership for destination directories, from `dest_mode` and
+/// `dest_owner`, with users and groups already resolved.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DestPermissions {
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl DestPermissions {
+    pub fn is_set(&self) -> bool {
+        self.mode.is_some() || self.uid.is_some() || self.gid.is_some()
+    }
+    
+    /// Create `dest` if needed and set its mode and owner. A no-op when
+    /// neither is configured, leaving creation to the copy tool.
+    pub fn apply(&self, dest: &Path) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        
+        if !self.is_set() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(dest)
+            .with_context(|| format!("Failed to create {}", dest.display()))?;
+        // chown first: it clears setuid/setgid bits the mode may ask for
+        if self.uid.is_some() || self.gid.is_some() {
+            std::os::unix::fs::chown(dest, self.uid, self.gid)
+                .with_context(|| format!("Failed to set owner of {}", dest.display()))?;
+        }
+        if let Some(mode) = self.mode {
+            std::fs::set_permissions(dest, std::fs::Permissions::from_mode(mode))
+                .with_context(|| format!("Failed to set mode {:o} on {}", mode, dest.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// The copy tool freight-migrate drives for each directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationBackend {
+    /// rsync with `rsync_flags`; the only backend that can resume
+    #[default]
+    Rsync,
+    /// `tar -cf - . | tar -xpf -`, often quicker for first copies of
+    /// many small files between local mounts
+    Tar,
+    /// `cp -a --reflink=auto`, which clones instead of copying on
+    /// filesystems that support it
+    Cp,
+}
+
+impl MigrationBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationBackend::Rsync => "rsync",
+            MigrationBackend::Tar => "tar",
+            MigrationBackend::Cp => "cp",
+        }
+    }
+}
+
+/// Hash used to compare files, from `checksum_algorithm`: by rsync when
+/// it checksums during a migrate, and by freight-verify.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    /// 64-bit xxHash: much faster than the cryptographic hashes
+    Xxhash,
+}
+
+impl ChecksumAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Sha1 => "sha1",
+            ChecksumAlgorithm::Xxhash => "xxhash",
+        }
+    }
+    
+    /// The `--checksum-choice` value rsync knows this by, if it supports it.
+    pub fn rsync_choice(&self) -> Option<&'static str> {
+        match self {
+            ChecksumAlgorithm::Md5 => Some("md5"),
+            ChecksumAlgorithm::Sha1 => None,
+            ChecksumAlgorithm::Xxhash => Some("xxh64"),
+        }
+    }
+}
+
+/// Add `--checksum-choice` for `algorithm` to `flags`, unless they already
+/// pick one.
+pub fn add_checksum_flags(flags: &mut String, algorithm: ChecksumAlgorithm) {
+    let chosen = flags
+        .split_whitespace()
+        .any(|flag| flag.starts_with("--checksum-choice") || flag.starts_with("--cc"));
+    if let (false, Some(choice)) = (chosen, algorithm.rsync_choice()) {
+        flags.push_str(&format!(" --checksum-choice={}", choice));
+    }
+}
+
+/// How the wait between freight-migrate's attempts grows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryBackoff {
+    /// `retry_base_delay` every time
+    #[default]
+    Fixed,
+    /// `retry_base_delay` times the number of failed attempts
+    Linear,
+    /// `retry_base_delay` doubled after each failed attempt
+    Exponential,
+}
+
+impl RetryBackoff {
+    /// Seconds to wait after failed attempt `attempt` (1-based), before
+    /// any cap or jitter.
+    pub fn delay_secs(&self, base: u64, attempt: u32) -> u64 {
+        match self {
+            RetryBackoff::Fixed => base,
+            RetryBackoff::Linear => base.saturating_mul(u64::from(attempt)),
+            RetryBackoff::Exponential => {
+                let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+                base.saturating_mul(factor)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Scan,
+    Migrate,
+}
+
+impl Phase {
+    fn tool(&self) -> &'static str {
+        match self {
+            Phase::Scan => "scan",
+            Phase::Migrate => "migrate",
+        }
+    }
+    
+    fn for_tool(tool: &str) -> Self {
+        match tool {
+            "scan" => Phase::Scan,
+            _ => Phase::Migrate,
+        }
+    }
+}
+
+/// What `discover_directories` found.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    /// Directories to migrate, plus top-level files when
+    /// `include_top_level_files` is set
+    pub directories: Vec<PathBuf>,
+    /// Selected directories that can't be read, with why; nothing in them
+    /// can be migrated
+    pub unreadable: Vec<(PathBuf, String)>,
+}
+
+/// Where the tools keep a unit's logs and results: `<dir>/.freight/` for a
+/// directory, and for a top-level file `.freight/files/<name>/` beside it,
+/// out of the way of the project's own files there. Matches
+/// `get_freight_dir` in lib/common.sh.
+pub fn unit_freight_dir(unit: &Path) -> PathBuf {
+    match (unit.is_file(), unit.parent(), unit.file_name()) {
+        (true, Some(parent), Some(name)) => parent.join(".freight/files").join(name),
+        _ => unit.join(".freight"),
+    }
+}
+
+/// Top-level directories of `source_path` that the config selects for
+/// migration, and top-level files too with `include_top_level_files`.
+/// Directories named with `--only` win over `include_patterns`.
+/// Only an unreadable `source_path` is an error: entries that can't be
+/// read are logged and skipped, and selected directories that can't be
+/// listed are collected in `unreadable`.
+pub async fn discover_directories(config: &Config) -> Result<Discovery> {
+    let mut discovery = Discovery::default();
+    let include = config.include_set()?;
+    let only: Vec<&Path> = config
+        .only_directories
+        .iter()
+        .map(|name| Path::new(name.as_str()))
+        .collect();
+    
+    let mut entries = tokio::fs::read_dir(&config.source_path)
+        .await
+        .with_context(|| format!("Failed to read {}", config.source_path.display()))?;
+    loop {
+        let entry = match entries.next_entry().await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Skipping unreadable entry in {}: {}", config.source_path.display(), e);
+                continue;
+            }
+        };
+        let path = entry.path();
+        let file_name = entry.file_name();
+        let name = file_name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        if !only.is_empty() {
+            if !only.iter().any(|dir| dir.as_os_str() == file_name) {
+                continue;
+            }
+        } else if include.as_ref().is_some_and(|include| !include.is_match(name.as_ref())) {
+            continue;
+        }
+        
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(metadata) if metadata.is_file() && config.include_top_level_files => {
+                discovery.directories.push(path);
+                continue;
+            }
+            Ok(_) => continue,
+            Err(e) => {
+                warn!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        }
+        // Opening can succeed where listing doesn't, so read an entry too
+        let listed = match tokio::fs::read_dir(&path).await {
+            Ok(mut entries) => entries.next_entry().await.map(drop),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = listed {
+            warn!("Skipping unreadable directory {}: {}", path.display(), e);
+            discovery.unreadable.push((path, e.to_string()));
+            continue;
+        }
+        discovery.directories.push(path);
+    }
+    
+    Ok(discovery)
 }
 
 pub struct WorkerManager {
     config: Option<Config>,
-    workers: HashMap<String, WorkerInfo>,
+    workers: HashMap<WorkerKey, WorkerInfo>,
+    pending: VecDeque<(Phase, PathBuf)>,
+    /// Directory sizes reported by completed scans, used for scheduling
+    sizes: HashMap<PathBuf, u64>,
+    /// Destinations fixed by a replayed plan, preferred over `dest_dir_for`
+    planned_dests: HashMap<PathBuf, PathBuf>,
+    /// Where finished workers are recorded, when `history_db` is set
+    history: Option<HistoryDb>,
+    /// Manifest hashes from this run's scans, saved once the migrate succeeds
+    manifests: HashMap<PathBuf, String>,
+    /// For telling the daemon and its subscribers about workers the manager
+    /// settles itself, such as skipped directories
+    socket_server: Option<Arc<SocketServer>>,
+    /// Where wait tasks report tool exits, while a migration runs
+    exit_tx: Option<mpsc::UnboundedSender<WorkerExit>>,
+    draining: bool,
+    /// Times each worker has been cut off by `worker_timeout` this run
+    timeouts: HashMap<WorkerKey, u32>,
+    /// Timed-out workers waiting out their backoff before being queued again
+    retries: Vec<(Instant, Phase, PathBuf)>,
+    /// When `start_migration` began, for the run's duration
+    started_at: Option<DateTime<Utc>>,
+    /// Earliest the next worker may spawn under `startup_stagger_ms`
+    next_spawn_at: Option<Instant>,
+}
+
+impl Default for WorkerManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WorkerManager {
@@ -33,6 +644,18 @@ impl WorkerManager {
         Self {
             config: None,
             workers: HashMap::new(),
+            pending: VecDeque::new(),
+            sizes: HashMap::new(),
+            planned_dests: HashMap::new(),
+            history: None,
+            manifests: HashMap::new(),
+            socket_server: None,
+            exit_tx: None,
+            draining: false,
+            timeouts: HashMap::new(),
+            retries: Vec::new(),
+            started_at: None,
+            next_spawn_at: None,
         }
     }
     
@@ -40,6 +663,14 @@ impl WorkerManager {
         self.config = Some(config);
     }
     
+    pub fn set_history(&mut self, history: HistoryDb) {
+        self.history = Some(history);
+    }
+    
+    pub fn set_socket_server(&mut self, socket_server: Arc<SocketServer>) {
+        self.socket_server = Some(socket_server);
+    }
+    
     pub async fn start(&self, mut message_rx: broadcast::Receiver<WorkerMessage>) {
         info!("Worker manager started");
         
@@ -49,170 +680,1006 @@ impl WorkerManager {
         }
     }
     
-    pub async fn start_migration(&mut self, mut message_rx: broadcast::Receiver<WorkerMessage>) {
+    /// Run the migration until every worker finishes, keeping at most
+    /// `parallel_workers` running at once. After a `Drain` request no
+    /// further workers are spawned and this returns as soon as the
+    /// in-flight ones have stopped.
+    pub async fn start_migration(
+        &mut self,
+        mut message_rx: broadcast::Receiver<WorkerMessage>,
+        mut control_rx: mpsc::UnboundedReceiver<ControlMessage>,
+    ) -> Result<()> {
         info!("Starting migration workflow");
+        self.started_at = Some(Utc::now());
+        
+        let config = self
+            .config
+            .clone()
+            .context("No configuration provided for migration")?;
         
-        let config = match &self.config {
-            Some(config) => config.clone(),
+        ensure_tool_binaries()?;
+        check_rsync_flags(&config)?;
+        prepare_dest_root(&config)?;
+        
+        // Discover directories to migrate, or take them from a plan
+        let mut directories = match &config.plan_path {
+            Some(plan_path) => self.replay_plan(&config, plan_path)?,
             None => {
-                error!("No configuration provided for migration");
-                return;
+                let discovery = discover_directories(&config)
+                    .await
+                    .context("Failed to discover directories")?;
+                for (directory, reason) in discovery.unreadable {
+                    self.settle_unspawned(
+                        Phase::Scan,
+                        directory,
+                        WorkerStatus::Failed,
+                        format!("Unreadable: {}", reason),
+                    )
+                    .await;
+                }
+                discovery.directories
             }
         };
         
-        // Discover directories to migrate
-        let directories = match self.discover_directories(&config.source_path).await {
-            Ok(dirs) => dirs,
-            Err(e) => {
-                error!("Failed to discover directories: {}", e);
-                return;
+        info!("Found {} directories to migrate", directories.len());
+        if config.schedule_policy == SchedulePolicy::Alphabetical {
+            directories.sort();
+        }
+        
+        let (exit_tx, mut exit_rx) = mpsc::unbounded_channel();
+        self.exit_tx = Some(exit_tx);
+        
+        // Start scanning phase
+        self.pending
+            .extend(directories.into_iter().map(|dir| (Phase::Scan, dir)));
+        self.fill_slots().await;
+        
+        let mut timeout_check = tokio::time::interval(TIMEOUT_CHECK_INTERVAL);
+        
+        // Listen for worker messages and coordinate migration phases. The
+        // checks come first so a run with nothing to do ends straight away
+        loop {
+            if self.all_workers_terminal() {
+                info!("All workers finished");
+                // Built from the live config, which a reload may have changed
+                if let Some(report) = self.report() {
+                    log_summary(&report);
+                    self.write_report(&report);
+                    // The loop is done, so waiting here only delays daemon
+                    // exit until the summary has had its chance to go out
+                    if let Some(delivery) = self.notify_complete(&report) {
+                        let _ = delivery.await;
+                    }
+                }
+                break;
+            }
+            
+            if self.draining && self.running_workers() == 0 {
+                info!("Drain complete");
+                break;
+            }
+            
+            tokio::select! {
+                message = message_rx.recv() => match message {
+                    Ok(message) => self.handle_worker_message(message).await,
+                    // Missed STOPs leave workers looking busy; raising
+                    // broadcast_capacity is the fix
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(
+                            "Migration fell behind and skipped {} worker messages; \
+                             consider raising broadcast_capacity",
+                            skipped
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                Some(control) = control_rx.recv() => match control {
+                    ControlMessage::Drain => {
+                        self.draining = true;
+                        info!(
+                            "Draining {} running workers, {} queued will not start",
+                            self.running_workers(),
+                            self.pending.len()
+                        );
+                    }
+                    ControlMessage::Reload(new_config) => self.apply_reload(*new_config),
+                    ControlMessage::Cancel { tool, directory } => {
+                        self.cancel_worker(&tool, &directory);
+                    }
+                },
+                Some(exit) = exit_rx.recv() => self.handle_worker_exit(exit).await,
+                _ = timeout_check.tick() => self.check_timeouts().await,
+                // Timed-out workers whose backoff is over go out from
+                // fill_slots below
+                _ = wait_until(self.next_retry_at()), if !self.retries.is_empty() => {}
+                // Spawns held back by the stagger go out from fill_slots below
+                _ = wait_until(self.next_spawn_at), if self.spawn_held() => {}
+            }
+            
+            self.fill_slots().await;
+        }
+        
+        Ok(())
+    }
+    
+    fn next_retry_at(&self) -> Option<Instant> {
+        self.retries.iter().map(|(at, _, _)| *at).min()
+    }
+    
+    /// Queue the timed-out workers whose backoff is over.
+    fn release_retries(&mut self) {
+        let now = Instant::now();
+        let (due, waiting) = std::mem::take(&mut self.retries)
+            .into_iter()
+            .partition(|(at, _, _)| *at <= now);
+        self.retries = waiting;
+        for (_, phase, directory) in due {
+            self.pending.push_front((phase, directory));
+        }
+    }
+    
+    /// Whether queued work is waiting only on the startup stagger.
+    fn spawn_held(&self) -> bool {
+        !self.pending.is_empty() && self.next_spawn_at.is_some_and(|at| Instant::now() < at)
+    }
+    
+    fn running_workers(&self) -> usize {
+        self.workers
+            .values()
+            .filter(|w| !w.status.is_terminal())
+            .count()
+    }
+    
+    /// Spawn queued workers until the concurrency limit is reached. With
+    /// `startup_stagger_ms`, spawns are spaced `startup_stagger_ms /
+    /// parallel_workers` apart, and those not due yet wait for a later call.
+    async fn fill_slots(&mut self) {
+        if self.draining {
+            return;
+        }
+        self.release_retries();
+        
+        let limit = self
+            .config
+            .as_ref()
+            .map_or(1, |c| c.thresholds.parallel_workers as usize)
+            .max(1);
+        let stagger = self
+            .config
+            .as_ref()
+            .map_or(Duration::ZERO, |c| Duration::from_millis(c.startup_stagger_ms))
+            / limit as u32;
+        
+        while self.running_workers() < limit {
+            if self.next_spawn_at.is_some_and(|at| Instant::now() < at) {
+                break;
+            }
+            let Some((phase, directory)) = self.pending.pop_front() else {
+                break;
+            };
+            
+            // Two workers on one directory would race on the destination
+            let worker_id = WorkerKey::new(phase.tool(), &directory);
+            if self
+                .workers
+                .get(&worker_id)
+                .is_some_and(|w| !w.status.is_terminal())
+            {
+                warn!("Worker {} is already running, ignoring duplicate", worker_id);
+                continue;
+            }
+            
+            let result = match phase {
+                Phase::Scan => self.start_scan_worker(&directory).await,
+                Phase::Migrate => self.start_migrate_worker(&directory).await,
+            };
+            if let Err(e) = result {
+                error!("Failed to start {:?} worker for {}: {}", phase, directory.display(), e);
+                let text = format!(
+                    "Failed to start {} worker for {}: {}",
+                    phase.tool(),
+                    directory.display(),
+                    e
+                );
+                self.notify(NoticeLevel::Error, text.clone());
+                // Fail the directory, or the report would take it from its scan
+                self.settle_unspawned(phase, directory, WorkerStatus::Failed, text).await;
+            }
+            if !stagger.is_zero() {
+                self.next_spawn_at = Some(Instant::now() + stagger);
             }
+        }
+    }
+    
+    fn cancel_worker(&mut self, tool: &str, directory: &str) {
+        let worker_id = WorkerKey::new(tool, directory);
+        let Some(worker) = self.workers.get_mut(&worker_id) else {
+            warn!("Cancel requested for unknown worker {}", worker_id);
+            return;
         };
+        if worker.status.is_terminal() {
+            info!("Worker {} already finished, nothing to cancel", worker_id);
+            return;
+        }
         
-        info!("Found {} directories to migrate", directories.len());
+        // Workers run in their own process group so rsync goes down with
+        // the wrapper script rather than being orphaned
+        if let Some(pid) = worker.pid {
+            if let Err(e) = signal_process_group(pid, libc::SIGTERM) {
+                warn!("Failed to signal worker {} (pid {}): {}", worker_id, pid, e);
+            }
+        }
         
-        // Start scanning phase
-        for dir in &directories {
-            if let Err(e) = self.start_scan_worker(dir).await {
-                error!("Failed to start scan worker for {}: {}", dir.display(), e);
+        worker.status = WorkerStatus::Cancelled;
+        worker.finished_at = Some(Utc::now());
+        info!("Cancelled worker {}", worker_id);
+        record_history(self.history.as_ref(), worker);
+    }
+    
+    /// Stop workers that have gone `worker_timeout` without a PROGRESS or
+    /// STOP, e.g. an rsync stuck on a dead NFS mount. The process group is
+    /// SIGTERMed and the worker is queued again after the `retry_*` backoff,
+    /// until it has timed out `retry_attempts` times; then it fails as if it
+    /// had reported it itself.
+    async fn check_timeouts(&mut self) {
+        let Some(config) = &self.config else { return };
+        let Some(timeout) = config.worker_timeout.map(Duration::from_secs) else {
+            return;
+        };
+        let retry_attempts = config.retry_attempts;
+        let retry_delays = config.retry_delays();
+        
+        let stalled: Vec<WorkerKey> = self
+            .workers
+            .iter()
+            .filter(|(_, w)| !w.status.is_terminal() && w.last_activity.elapsed() >= timeout)
+            .map(|(key, _)| key.clone())
+            .collect();
+        
+        for worker_id in stalled {
+            warn!("Worker {} made no progress for {:?}, terminating it", worker_id, timeout);
+            if let Some(pid) = self.workers.get(&worker_id).and_then(|w| w.pid) {
+                if let Err(e) = signal_process_group(pid, libc::SIGTERM) {
+                    warn!("Failed to signal worker {} (pid {}): {}", worker_id, pid, e);
+                }
+            }
+            
+            let timeouts = self.timeouts.entry(worker_id.clone()).or_default();
+            *timeouts += 1;
+            let text = format!("Timed out after {}s without progress", timeout.as_secs());
+            
+            if *timeouts < retry_attempts && !self.draining {
+                let delay = retry_delays.get(*timeouts as usize - 1).copied().unwrap_or_default();
+                warn!(
+                    "Retrying worker {} in {}s (attempt {}/{})",
+                    worker_id,
+                    delay,
+                    *timeouts + 1,
+                    retry_attempts
+                );
+                if let Some(worker) = self.workers.get_mut(&worker_id) {
+                    worker.status = WorkerStatus::Failed;
+                    worker.finished_at = Some(Utc::now());
+                    worker.error = Some(text.clone());
+                    worker.timed_out = true;
+                }
+                self.retries.push((
+                    Instant::now() + Duration::from_secs(delay),
+                    Phase::for_tool(&worker_id.tool),
+                    worker_id.directory.clone(),
+                ));
+                if let Some(socket_server) = &self.socket_server {
+                    socket_server
+                        .publish(WorkerMessage {
+                            message_type: crate::socket::MessageType::Progress,
+                            tool: worker_id.tool.clone(),
+                            directory: Some(worker_id.directory.to_string_lossy().into_owned()),
+                            status: None,
+                            bytes: None,
+                            bytes_total: None,
+                            files: None,
+                            message: Some(format!("{}, retrying in {}s", text, delay)),
+                            host: None,
+                            pid: None,
+                        })
+                        .await;
+                }
+                continue;
+            }
+            
+            let message = WorkerMessage {
+                message_type: crate::socket::MessageType::Stop,
+                tool: worker_id.tool.clone(),
+                directory: Some(worker_id.directory.to_string_lossy().into_owned()),
+                status: Some("failed".to_string()),
+                bytes: None,
+                bytes_total: None,
+                files: None,
+                message: Some(text),
+                host: None,
+                pid: None,
+            };
+            self.handle_worker_message(message.clone()).await;
+            if let Some(worker) = self.workers.get_mut(&worker_id) {
+                worker.timed_out = true;
+            }
+            
+            // Subscribers would otherwise keep whatever the killed tool last
+            // said; the echo is ignored like any STOP for a finished worker
+            if let Some(socket_server) = &self.socket_server {
+                socket_server.publish(message).await;
             }
         }
+    }
+    
+    /// Settle a worker whose process exited without sending STOP, from its
+    /// exit status, so a tool that never reports still moves the directory
+    /// on to its next phase.
+    async fn handle_worker_exit(&mut self, exit: WorkerExit) {
+        let Some(worker) = self.workers.get(&exit.key) else { return };
+        if worker.status.is_terminal() || worker.pid != exit.pid {
+            return;
+        }
         
-        // Listen for worker messages and coordinate migration phases
-        while let Ok(message) = message_rx.recv().await {
-            self.handle_worker_message(message).await;
+        let (status, text) = if exit.status.success() {
+            warn!("Worker {} exited without sending STOP, treating it as done", exit.key);
+            ("ok", None)
+        } else {
+            warn!("Worker {} exited without sending STOP: {}", exit.key, exit.status);
+            ("failed", Some(format!("Exited ({}) without sending STOP", exit.status)))
+        };
+        let message = WorkerMessage {
+            message_type: crate::socket::MessageType::Stop,
+            tool: exit.key.tool.clone(),
+            directory: Some(exit.key.directory.to_string_lossy().into_owned()),
+            status: Some(status.to_string()),
+            bytes: None,
+            bytes_total: None,
+            files: None,
+            message: text,
+            host: None,
+            pid: None,
+        };
+        self.handle_worker_message(message.clone()).await;
+        if let Some(worker) = self.workers.get_mut(&exit.key) {
+            worker.exited = true;
+        }
+        
+        // Subscribers never saw a STOP either; the echo is ignored above
+        if let Some(socket_server) = &self.socket_server {
+            socket_server.publish(message).await;
         }
     }
     
-    async fn discover_directories(&self, source_path: &PathBuf) -> Result<Vec<PathBuf>> {
-        let mut directories = Vec::new();
+    /// Take the parts of a reloaded config that are safe to change mid-run.
+    /// Running workers keep what they were started with; everything else
+    /// needs a daemon restart.
+    fn apply_reload(&mut self, new_config: Config) {
+        let Some(config) = self.config.as_mut() else {
+            return;
+        };
+        
+        let mut changed = Vec::new();
+        
+        macro_rules! apply_live {
+            ($($field:ident).+) => {
+                if config.$($field).+ != new_config.$($field).+ {
+                    changed.push(format!(
+                        "{}: {:?} -> {:?}",
+                        stringify!($($field).+),
+                        config.$($field).+,
+                        new_config.$($field).+
+                    ));
+                    config.$($field).+ = new_config.$($field).+.clone();
+                }
+            };
+        }
+        
+        apply_live!(thresholds.parallel_workers);
+        apply_live!(thresholds.large_directory_size);
+        apply_live!(rsync_flags);
+        apply_live!(tool_flags);
+        apply_live!(dest_map);
+        apply_live!(bandwidth_limit);
+        apply_live!(retry_attempts);
+        apply_live!(retry_backoff);
+        apply_live!(retry_base_delay);
+        apply_live!(retry_max_delay);
+        apply_live!(retry_jitter);
+        apply_live!(worker_timeout);
+        apply_live!(schedule_policy);
+        apply_live!(phase_overlap);
+        apply_live!(startup_stagger_ms);
+        apply_live!(existing_policy);
+        apply_live!(resume_partial);
+        apply_live!(intra_dir_parallelism);
+        apply_live!(checksum_algorithm);
+        apply_live!(ssh_options);
+        apply_live!(dest_mode);
+        apply_live!(dest_owner);
+        apply_live!(backend);
+        apply_live!(on_complete_hook);
+        apply_live!(on_failure_hook);
+        apply_live!(webhook_url);
+        
+        let mut restart = Vec::new();
+        macro_rules! needs_restart {
+            ($field:ident) => {
+                if config.$field != new_config.$field {
+                    warn!("{} changed but requires a daemon restart", stringify!($field));
+                    restart.push(stringify!($field));
+                }
+            };
+        }
+        
+        needs_restart!(source_path);
+        needs_restart!(dest_path);
+        needs_restart!(transport);
+        needs_restart!(tcp_address);
+        needs_restart!(auth_token);
+        needs_restart!(socket_mode);
+        needs_restart!(socket_group);
+        needs_restart!(socket_address_mode);
+        needs_restart!(metrics_port);
+        needs_restart!(worker_retention);
+        needs_restart!(max_line_length);
+        needs_restart!(max_messages_per_sec);
+        needs_restart!(history_db);
+        
+        if changed.is_empty() {
+            info!("Config reloaded, no live settings changed");
+        } else {
+            info!("Config reloaded: {}", changed.join(", "));
+        }
+        if !restart.is_empty() {
+            self.notify(
+                NoticeLevel::Warning,
+                format!("Config reloaded, but {} only change on a daemon restart", restart.join(", ")),
+            );
+        }
+    }
+    
+    /// Queue a scanned directory for migration, ahead of any queued
+    /// migrations the schedule policy says should run after it, and of
+    /// queued scans too with `phase_overlap`.
+    fn enqueue_migrate(&mut self, directory: PathBuf) {
+        // A repeated scan STOP would otherwise queue the directory twice
+        if self
+            .pending
+            .iter()
+            .any(|(phase, queued)| *phase == Phase::Migrate && *queued == directory)
+        {
+            return;
+        }
+        
+        let policy = self
+            .config
+            .as_ref()
+            .map(|c| c.schedule_policy)
+            .unwrap_or_default();
+        let overlap = self.config.as_ref().is_some_and(|c| c.phase_overlap);
+        let size = self.sizes.get(&directory).copied();
+        
+        let position = self
+            .pending
+            .iter()
+            .position(|(phase, queued)| match phase {
+                Phase::Scan => overlap,
+                Phase::Migrate => policy.runs_before(
+                    (&directory, size),
+                    (queued, self.sizes.get(queued).copied()),
+                ),
+            })
+            .unwrap_or(self.pending.len());
+        self.pending.insert(position, (Phase::Migrate, directory));
+    }
+    
+    /// Nothing queued, waiting to retry or running, which includes a run that had
+    /// no directories to migrate at all.
+    fn all_workers_terminal(&self) -> bool {
+        self.pending.is_empty()
+            && self.retries.is_empty()
+            && self.workers.values().all(|w| w.status.is_terminal())
+    }
+    
+    /// Summarize the migration, one entry per directory using its latest phase.
+    pub fn build_report(&self, config: &Config) -> MigrationReport {
+        let mut latest: BTreeMap<&PathBuf, &WorkerInfo> = BTreeMap::new();
+        for worker in self.workers.values() {
+            let replace = match latest.get(&worker.directory) {
+                Some(existing) => worker.started_at > existing.started_at,
+                None => true,
+            };
+            if replace {
+                latest.insert(&worker.directory, worker);
+            }
+        }
+        
+        let directories: Vec<DirectoryReport> = latest
+            .into_values()
+            .map(|worker| DirectoryReport {
+                directory: worker.directory.clone(),
+                phase: worker.tool.clone(),
+                status: worker.status.as_str().to_string(),
+                bytes_transferred: worker.bytes,
+                duration_secs: worker.duration_secs(),
+                retries: worker.attempts.saturating_sub(1),
+                error: worker.error.clone(),
+            })
+            .collect();
+        
+        let generated_at = Utc::now();
+        MigrationReport {
+            started_at: self.started_at,
+            generated_at,
+            source_path: config.source_path.clone(),
+            dest_path: config.dest_root().to_path_buf(),
+            dry_run: config.dry_run,
+            totals: RunTotals::new(&directories, self.started_at, generated_at),
+            directories,
+        }
+    }
+    
+    /// The report for everything this manager has run so far.
+    pub fn report(&self) -> Option<MigrationReport> {
+        self.config.as_ref().map(|config| self.build_report(config))
+    }
+    
+    fn write_report(&self, report: &MigrationReport) {
+        let Some(config) = self.config.as_ref() else {
+            return;
+        };
+        let path = config
+            .report_path
+            .clone()
+            .unwrap_or_else(|| report::default_report_path(&config.freight_dir(), config.report_format));
+        
+        match report.write(&path, config.report_format) {
+            Ok(()) => info!("Migration report written to {}", path.display()),
+            Err(e) => {
+                error!("Failed to write migration report: {}", e);
+                self.notify(NoticeLevel::Error, format!("Failed to write migration report: {}", e));
+            }
+        }
+    }
+    
+    /// Pass a problem on to the daemon's subscribers as well as the log.
+    fn notify(&self, level: NoticeLevel, text: String) {
+        if let Some(socket_server) = &self.socket_server {
+            socket_server.notify(level, text);
+        }
+    }
+    
+    fn notify_complete(&self, report: &MigrationReport) -> Option<tokio::task::JoinHandle<()>> {
+        let config = self.config.as_ref()?;
+        let url = config.webhook_url.clone()?;
+        let payload = WebhookPayload::summary(report);
+        Some(webhook::spawn_webhook(url, payload))
+    }
+    
+    /// Load a plan written by `freight plan`, seeding sizes and destinations
+    /// from it. Planned directories that have since disappeared are skipped.
+    fn replay_plan(&mut self, config: &Config, plan_path: &Path) -> Result<Vec<PathBuf>> {
+        let plan = MigrationPlan::load(plan_path)?;
+        if plan.source_path != config.source_path {
+            anyhow::bail!(
+                "Plan {} was made for {}, not {}",
+                plan_path.display(),
+                plan.source_path.display(),
+                config.source_path.display()
+            );
+        }
+        
+        let missing = plan.missing_directories();
+        for directory in &missing {
+            warn!("Planned directory {} no longer exists, skipping it", directory.display());
+        }
+        if !missing.is_empty() {
+            warn!(
+                "Source has drifted since the plan was made on {}",
+                plan.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+        }
         
-        let mut entries = tokio::fs::read_dir(source_path).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if path.is_dir() && !path.file_name().unwrap().to_str().unwrap().starts_with('.') {
-                directories.push(path);
+        let mut directories = Vec::new();
+        for planned in plan.directories {
+            if !planned.directory.exists() {
+                continue;
+            }
+            if let Some(size) = planned.size {
+                self.sizes.insert(planned.directory.clone(), size);
             }
+            self.planned_dests.insert(planned.directory.clone(), planned.dest);
+            directories.push(planned.directory);
         }
         
+        info!("Replaying plan {}", plan_path.display());
         Ok(directories)
     }
     
     async fn start_scan_worker(&mut self, directory: &PathBuf) -> Result<()> {
-        info!("Starting scan worker for {}", directory.display());
+        info!(tool = "scan", directory = %directory.display(), "Starting worker");
         
-        let mut cmd = Command::new("freight-scan");
+        let mut cmd = Command::new(SCAN_BINARY);
+        if let Some(config) = &self.config {
+            set_daemon_env(&mut cmd, config);
+            set_priority(&mut cmd, config)?;
+        }
         cmd.arg(directory)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true);
         
         let child = cmd.spawn()?;
         let pid = child.id();
         
-        let worker_info = WorkerInfo {
-            tool: "scan".to_string(),
-            directory: directory.clone(),
-            status: WorkerStatus::Running,
-            pid,
-        };
+        let worker_info = WorkerInfo::running("scan", directory, pid);
         
-        let worker_id = format!("scan:{}", directory.display());
-        self.workers.insert(worker_id, worker_info);
+        self.workers
+            .insert(WorkerKey::new("scan", directory), worker_info);
         
         // Spawn task to wait for completion
         let directory_clone = directory.clone();
+        let exit_tx = self.exit_tx.clone();
+        let capture_limit = self.config.as_ref().map_or(DEFAULT_MAX_CAPTURED_OUTPUT, |config| {
+            config.max_captured_output
+        });
         tokio::spawn(async move {
-            match child.wait_with_output().await {
-                Ok(output) => {
-                    if output.status.success() {
-                        info!("Scan completed for {}", directory_clone.display());
+            let result =
+                wait_streaming(child, "scan", &directory_clone, capture_limit, None).await;
+            let status = match result {
+                Ok((status, stderr)) => {
+                    if status.success() {
+                        info!(tool = "scan", directory = %directory_clone.display(), "Worker exited");
                     } else {
-                        error!("Scan failed for {}: {}", 
-                            directory_clone.display(), 
-                            String::from_utf8_lossy(&output.stderr)
+                        error!(
+                            tool = "scan",
+                            directory = %directory_clone.display(),
+                            status = %status,
+                            "Worker failed: {}",
+                            stderr
                         );
                     }
+                    status
                 }
                 Err(e) => {
                     error!("Failed to wait for scan worker: {}", e);
+                    return;
                 }
-            }
+            };
+            report_exit(exit_tx, WorkerKey::new("scan", directory_clone), pid, status).await;
         });
         
         Ok(())
     }
     
     async fn start_migrate_worker(&mut self, directory: &PathBuf) -> Result<()> {
-        info!("Starting migrate worker for {}", directory.display());
+        info!(tool = "migrate", directory = %directory.display(), "Starting worker");
         
+        let dest_dir = self.dest_for(directory);
         let config = self.config.as_ref().unwrap();
-        let dest_dir = config.dest_path.join(directory.file_name().unwrap());
+        // dest_mode and dest_owner are for directories; a file keeps its own
+        if !config.dry_run && directory.is_dir() {
+            config.dest_permissions()?.apply(&dest_dir)?;
+        }
         
-        let mut cmd = Command::new("freight-migrate");
+        let mut cmd = Command::new(MIGRATE_BINARY);
+        if config.dry_run {
+            cmd.arg("--dry-run");
+        }
+        if let Some(kbps) = config.bwlimit_kbps()? {
+            cmd.arg("--bwlimit").arg(kbps.to_string());
+        }
+        if config.existing_policy == ExistingPolicy::ErrorIfPresent {
+            cmd.arg("--require-empty-dest");
+        }
+        let rsync_flags = migrate_rsync_flags(config);
+        let retry_delays: Vec<String> =
+            config.retry_delays().iter().map(u64::to_string).collect();
+        if !retry_delays.is_empty() {
+            cmd.arg("--retry-delays").arg(retry_delays.join(","));
+        }
+        cmd.arg("--rsync-flags")
+            .arg(rsync_flags)
+            .arg("--retry")
+            .arg(config.retry_attempts.to_string())
+            .arg("--backend")
+            .arg(config.backend.as_str());
+        if let Some(ssh_options) = config.ssh_options.as_ref().filter(|_| config.dest_is_remote()) {
+            cmd.arg("--ssh-options").arg(ssh_options);
+        }
+        if config.intra_dir_parallelism > 1 && config.backend == MigrationBackend::Rsync {
+            cmd.arg("--shards").arg(config.intra_dir_parallelism.to_string());
+        }
+        // Only rsync has a meter to stream, and only subscribers to tell
+        let progress = self.socket_server.clone().map(|server| ProgressSink {
+            parser: progress::parser_for(config.backend),
+            server,
+            interval: Duration::from_millis(config.progress_interval_ms),
+            last_sent: None,
+            held: None,
+        });
+        if progress.is_some() && config.backend == MigrationBackend::Rsync {
+            cmd.arg("--stream-progress");
+        }
+        set_daemon_env(&mut cmd, config);
+        set_priority(&mut cmd, config)?;
         cmd.arg(directory)
             .arg(&dest_dir)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true);
         
         let child = cmd.spawn()?;
         let pid = child.id();
         
-        let worker_info = WorkerInfo {
-            tool: "migrate".to_string(),
-            directory: directory.clone(),
-            status: WorkerStatus::Running,
-            pid,
-        };
+        let worker_info = WorkerInfo::running("migrate", directory, pid);
         
-        let worker_id = format!("migrate:{}", directory.display());
-        self.workers.insert(worker_id, worker_info);
+        self.workers
+            .insert(WorkerKey::new("migrate", directory), worker_info);
         
         // Spawn task to wait for completion
         let directory_clone = directory.clone();
+        let exit_tx = self.exit_tx.clone();
+        let capture_limit = self.config.as_ref().map_or(DEFAULT_MAX_CAPTURED_OUTPUT, |config| {
+            config.max_captured_output
+        });
         tokio::spawn(async move {
-            match child.wait_with_output().await {
-                Ok(output) => {
-                    if output.status.success() {
-                        info!("Migration completed for {}", directory_clone.display());
+            let result =
+                wait_streaming(child, "migrate", &directory_clone, capture_limit, progress).await;
+            let status = match result {
+                Ok((status, stderr)) => {
+                    if status.success() {
+                        info!(tool = "migrate", directory = %directory_clone.display(), "Worker exited");
                     } else {
-                        error!("Migration failed for {}: {}", 
-                            directory_clone.display(), 
-                            String::from_utf8_lossy(&output.stderr)
+                        error!(
+                            tool = "migrate",
+                            directory = %directory_clone.display(),
+                            status = %status,
+                            "Worker failed: {}",
+                            stderr
                         );
                     }
+                    status
                 }
                 Err(e) => {
                     error!("Failed to wait for migrate worker: {}", e);
+                    return;
                 }
-            }
+            };
+            report_exit(exit_tx, WorkerKey::new("migrate", directory_clone), pid, status).await;
         });
         
         Ok(())
     }
     
+    /// Where `directory` migrates to: the plan's destination if one was
+    /// replayed, otherwise the config's.
+    fn dest_for(&self, directory: &Path) -> PathBuf {
+        match (self.planned_dests.get(directory), &self.config) {
+            (Some(dest), _) => dest.clone(),
+            (None, Some(config)) => config.dest_dir_for(directory),
+            (None, None) => PathBuf::new(),
+        }
+    }
+    
+    /// Whether `directory` still matches the manifest saved when it last
+    /// migrated to the same, still present, destination.
+    fn unchanged_since_migration(&self, directory: &Path, manifest: &str) -> bool {
+        let Some(config) = self.config.as_ref().filter(|c| !c.force) else {
+            return false;
+        };
+        let path = manifest::manifest_path(&config.freight_dir(), &config.source_path, directory);
+        if !path.exists() {
+            return false;
+        }
+        let previous = match DirectoryManifest::load(&path) {
+            Ok(previous) => previous,
+            Err(e) => {
+                warn!("{:#}; migrating {} anyway", e, directory.display());
+                return false;
+            }
+        };
+        let dest = self.dest_for(directory);
+        previous.manifest == manifest
+            && previous.dest == dest
+            && dest.exists()
+            && dest.is_dir() == directory.is_dir()
+    }
+    
+    /// Settle `directory`'s migrate as skipped without spawning it.
+    async fn skip_migrate(&mut self, directory: PathBuf) {
+        info!("{} is unchanged since its last migration, skipping it", directory.display());
+        self.settle_unspawned(
+            Phase::Migrate,
+            directory,
+            WorkerStatus::Skipped,
+            "Unchanged since last migration".to_string(),
+        )
+        .await;
+    }
+    
+    /// Record `phase` for `directory` as finished with `status` without
+    /// spawning it, and tell the daemon's subscribers.
+    async fn settle_unspawned(
+        &mut self,
+        phase: Phase,
+        directory: PathBuf,
+        status: WorkerStatus,
+        message: String,
+    ) {
+        let mut worker = WorkerInfo::running(phase.tool(), &directory, None);
+        worker.status = status.clone();
+        worker.finished_at = Some(worker.started_at);
+        worker.unspawned = true;
+        if status == WorkerStatus::Failed {
+            worker.error = Some(message.clone());
+        }
+        record_history(self.history.as_ref(), &worker);
+        self.workers.insert(WorkerKey::new(phase.tool(), &directory), worker);
+        
+        if let Some(socket_server) = &self.socket_server {
+            socket_server
+                .publish(WorkerMessage {
+                    message_type: crate::socket::MessageType::Stop,
+                    tool: phase.tool().to_string(),
+                    directory: Some(directory.to_string_lossy().into_owned()),
+                    status: Some(status.as_str().to_string()),
+                    bytes: None,
+                    bytes_total: None,
+                    files: None,
+                    message: Some(message),
+                    host: None,
+                    pid: None,
+                })
+                .await;
+        }
+    }
+    
+    /// rsync -a copies the source directory's own mode and owner onto the
+    /// destination, undoing what was set before the copy; set them again.
+    fn reapply_dest_permissions(&self, directory: &Path) {
+        let Some(config) = self.config.as_ref().filter(|c| !c.dry_run) else {
+            return;
+        };
+        // As before the copy, only directories get them
+        if !directory.is_dir() {
+            return;
+        }
+        let result = config
+            .dest_permissions()
+            .and_then(|permissions| permissions.apply(&self.dest_for(directory)));
+        if let Err(e) = result {
+            warn!("{:#}", e);
+        }
+    }
+    
+    /// Remember what a successfully migrated directory looked like, so an
+    /// unchanged re-run can skip it.
+    fn save_manifest(&self, directory: &Path) {
+        let (Some(config), Some(manifest)) = (&self.config, self.manifests.get(directory)) else {
+            return;
+        };
+        if config.dry_run {
+            return;
+        }
+        let saved = DirectoryManifest {
+            directory: directory.to_path_buf(),
+            dest: self.dest_for(directory),
+            manifest: manifest.clone(),
+            migrated_at: Utc::now(),
+        };
+        let path = manifest::manifest_path(&config.freight_dir(), &config.source_path, directory);
+        if let Err(e) = saved.write(&path) {
+            warn!("{:#}", e);
+        }
+    }
+    
     async fn handle_worker_message(&mut self, message: WorkerMessage) {
         match message.message_type {
             crate::socket::MessageType::Stop => {
-                let worker_id = format!("{}:{}", 
-                    message.tool, 
-                    message.directory.as_deref().unwrap_or("unknown")
-                );
+                let Some(worker_id) = WorkerKey::for_message(&message) else { return };
                 
                 if let Some(worker) = self.workers.get_mut(&worker_id) {
-                    worker.status = if message.status.as_deref() == Some("ok") {
-                        WorkerStatus::Completed
-                    } else {
-                        WorkerStatus::Failed
+                    // A cancelled worker may still report failure as it dies,
+                    // and the daemon's own "cancelled" STOP is only for TUI
+                    // clients; either way the Cancel control message decides.
+                    // A timed-out worker's dying STOP is likewise old news,
+                    // as is a late STOP from one already settled by its exit.
+                    // An unspawned worker's STOP is the manager's own, echoed back.
+                    if worker.status == WorkerStatus::Cancelled
+                        || worker.unspawned
+                        || worker.timed_out
+                        || worker.exited
+                        || message.status.as_deref() == Some("cancelled")
+                    {
+                        return;
+                    }
+                    
+                    worker.status = match message.status.as_deref() {
+                        Some("ok") => WorkerStatus::Completed,
+                        Some("planned") => WorkerStatus::Planned,
+                        _ => WorkerStatus::Failed,
+                    };
+                    worker.finished_at = Some(Utc::now());
+                    if message.bytes.is_some() {
+                        worker.bytes = message.bytes;
+                    }
+                    if worker.status == WorkerStatus::Failed {
+                        worker.error = message.message.clone().or_else(|| message.status.clone());
+                    }
+                    info!(
+                        tool = %worker.tool,
+                        directory = %worker.directory.display(),
+                        status = worker.status.as_str(),
+                        bytes = worker.bytes,
+                        retries = worker.attempts.saturating_sub(1),
+                        "Worker finished"
+                    );
+                    record_history(self.history.as_ref(), worker);
+                    
+                    let hook = match (&self.config, &worker.status) {
+                        (Some(config), WorkerStatus::Failed) => config.on_failure_hook.clone(),
+                        (Some(config), _) if worker.tool == "migrate" => {
+                            config.on_complete_hook.clone()
+                        }
+                        _ => None,
                     };
+                    if let Some(hook) = hook {
+                        spawn_hook(hook, worker);
+                    }
+                    
+                    if worker.status == WorkerStatus::Failed {
+                        if let Some(url) = self.config.as_ref().and_then(|c| c.webhook_url.clone()) {
+                            webhook::spawn_webhook(url, WebhookPayload::DirectoryFailed {
+                                tool: worker.tool.clone(),
+                                directory: worker.directory.clone(),
+                                status: worker.status.as_str().to_string(),
+                                bytes: worker.bytes,
+                                duration: worker.duration_secs(),
+                                error: worker.error.clone(),
+                            });
+                        }
+                    }
                     
                     // Check if we should start next phase
-                    if message.tool == "scan" && worker.status == WorkerStatus::Completed {
+                    if message.tool == "scan"
+                        && worker.status == WorkerStatus::Completed
+                        && !self.draining
+                    {
                         if let Some(directory) = &message.directory {
-                            let dir_path = PathBuf::from(directory);
-                            if let Err(e) = self.start_migrate_worker(&dir_path).await {
-                                error!("Failed to start migration for {}: {}", directory, e);
+                            let directory = PathBuf::from(directory);
+                            if let Some(bytes) = message.bytes_total.or(message.bytes) {
+                                self.sizes.insert(directory.clone(), bytes);
                             }
+                            if let Some(manifest) = manifest::scanned_manifest(&directory) {
+                                if self.unchanged_since_migration(&directory, &manifest) {
+                                    self.skip_migrate(directory).await;
+                                    return;
+                                }
+                                self.manifests.insert(directory.clone(), manifest);
+                            }
+                            self.enqueue_migrate(directory);
                         }
+                    } else if message.tool == "migrate" && worker.status == WorkerStatus::Completed {
+                        self.reapply_dest_permissions(&worker_id.directory);
+                        self.save_manifest(&worker_id.directory);
+                    }
+                }
+            }
+            crate::socket::MessageType::Progress => {
+                let Some(worker_id) = WorkerKey::for_message(&message) else { return };
+                
+                if let Some(worker) = self.workers.get_mut(&worker_id) {
+                    // freight-migrate announces each rsync attempt as "Attempt N/M"
+                    if is_attempt_message(message.message.as_deref()) {
+                        worker.attempts += 1;
+                    }
+                    worker.last_activity = Instant::now();
+                    if message.bytes.is_some() {
+                        worker.bytes = message.bytes;
                     }
                 }
             }
@@ -221,4 +1688,720 @@ impl WorkerManager {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Log the finished run's totals as one event, with each number in a field
+/// of its own for `log_format: json`.
+fn log_summary(report: &MigrationReport) {
+    let totals = &report.totals;
+    let duration_secs = totals.duration_secs.unwrap_or_default();
+    let bytes_per_sec = totals.bytes_per_sec.unwrap_or_default();
+    info!(
+        bytes = totals.bytes_transferred,
+        duration_secs,
+        bytes_per_sec,
+        succeeded = report.succeeded(),
+        failed = report.failed(),
+        cancelled = report.cancelled(),
+        retries = totals.retries,
+        "Migration summary: {} in {:.1}s ({}/s), {} succeeded, {} failed, {} cancelled, {} retries",
+        format_bytes(totals.bytes_transferred),
+        duration_secs,
+        format_bytes(bytes_per_sec as u64),
+        report.succeeded(),
+        report.failed(),
+        report.cancelled(),
+        totals.retries
+    );
+}
+
+/// The last lines of a stream, holding at most `limit` bytes.
+pub struct OutputTail {
+    lines: VecDeque<String>,
+    len: usize,
+    limit: usize,
+}
+
+impl OutputTail {
+    pub fn new(limit: usize) -> Self {
+        Self { lines: VecDeque::new(), len: 0, limit }
+    }
+    
+    /// Keep `line`, dropping the oldest lines to make room. A line longer
+    /// than the limit keeps only its end.
+    pub fn push(&mut self, line: &str) {
+        let mut start = line.len().saturating_sub(self.limit);
+        while !line.is_char_boundary(start) {
+            start += 1;
+        }
+        let line = &line[start..];
+        // Every line counts its newline, but the last one isn't written
+        self.len += line.len() + 1;
+        self.lines.push_back(line.to_string());
+        while self.len > self.limit + 1 {
+            match self.lines.pop_front() {
+                Some(dropped) => self.len -= dropped.len() + 1,
+                None => break,
+            }
+        }
+    }
+    
+    pub fn text(&self) -> String {
+        self.lines.iter().map(String::as_str).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Turns a worker's stdout into PROGRESS for the daemon's subscribers, no
+/// more often than `progress_interval_ms`, like the tools' own.
+struct ProgressSink {
+    parser: Box<dyn ProgressParser>,
+    server: Arc<SocketServer>,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    /// The latest update held back by the interval
+    held: Option<progress::ProgressUpdate>,
+}
+
+impl ProgressSink {
+    async fn line(&mut self, tool: &str, directory: &Path, line: &str) {
+        let Some(update) = self.parser.parse_line(line) else { return };
+        if self.last_sent.is_some_and(|sent| sent.elapsed() < self.interval) {
+            self.held = Some(update);
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+        self.send(tool, directory, update).await;
+    }
+    
+    /// Send whatever the interval held back, once the output has ended.
+    async fn flush(&mut self, tool: &str, directory: &Path) {
+        if let Some(update) = self.held.take() {
+            self.send(tool, directory, update).await;
+        }
+    }
+    
+    async fn send(&mut self, tool: &str, directory: &Path, update: progress::ProgressUpdate) {
+        self.server
+            .publish(WorkerMessage {
+                message_type: crate::socket::MessageType::Progress,
+                tool: tool.to_string(),
+                directory: Some(directory.to_string_lossy().into_owned()),
+                status: None,
+                bytes: update.bytes,
+                bytes_total: None,
+                files: update.files,
+                message: update.message,
+                host: None,
+                pid: None,
+            })
+            .await;
+    }
+}
+
+/// Wait for a tool to exit, passing each line of its stdout and stderr to
+/// the daemon log at debug level as it arrives, and stdout to `progress`
+/// too if given. Returns the exit status and the last `limit` bytes of
+/// stderr, for reporting a failure; nothing else is buffered, however much
+/// the tool prints.
+async fn wait_streaming(
+    mut child: Child,
+    tool: &'static str,
+    directory: &Path,
+    limit: usize,
+    mut progress: Option<ProgressSink>,
+) -> std::io::Result<(std::process::ExitStatus, String)> {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let mut tail = OutputTail::new(limit);
+    
+    tokio::join!(
+        forward_lines(stdout, tool, directory, "stdout", None, progress.as_mut()),
+        forward_lines(stderr, tool, directory, "stderr", Some(&mut tail), None),
+    );
+    let status = child.wait().await?;
+    
+    Ok((status, tail.text()))
+}
+
+/// Log each line read from `pipe`, keeping it in `tail` and passing it to
+/// `progress` too if given. Blank lines are dropped.
+async fn forward_lines(
+    pipe: Option<impl AsyncRead + Unpin>,
+    tool: &str,
+    directory: &Path,
+    stream: &str,
+    mut tail: Option<&mut OutputTail>,
+    mut progress: Option<&mut ProgressSink>,
+) {
+    let Some(pipe) = pipe else { return };
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    // A read error means the tool is gone; its exit status tells the rest
+    while let Ok(read) = read_line_or_redraw(&mut reader, &mut line).await {
+        if read == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if text.trim().is_empty() {
+            line.clear();
+            continue;
+        }
+        debug!(tool, directory = %directory.display(), stream, "{}", text);
+        if let Some(tail) = tail.as_deref_mut() {
+            tail.push(text);
+        }
+        if let Some(progress) = progress.as_deref_mut() {
+            progress.line(tool, directory, text).await;
+        }
+        line.clear();
+    }
+    if let Some(progress) = progress {
+        progress.flush(tool, directory).await;
+    }
+}
+
+/// Read up to and including the next `\n` or `\r`, so a meter that redraws
+/// its line with `\r`, like rsync's, arrives a redraw at a time rather than
+/// all at once when the transfer ends.
+async fn read_line_or_redraw(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(read);
+        }
+        let (used, done) = match available.iter().position(|b| matches!(b, b'\n' | b'\r')) {
+            Some(end) => (end + 1, true),
+            None => (available.len(), false),
+        };
+        line.extend_from_slice(&available[..used]);
+        reader.consume(used);
+        read += used;
+        if done {
+            return Ok(read);
+        }
+    }
+}
+
+/// Resolve at `at`, or never without one.
+async fn wait_until(at: Option<Instant>) {
+    match at {
+        Some(at) => tokio::time::sleep_until(at.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Tell the manager a tool has exited, once its STOP has had time to
+/// arrive. Nothing listens outside a migration run.
+async fn report_exit(
+    exit_tx: Option<mpsc::UnboundedSender<WorkerExit>>,
+    key: WorkerKey,
+    pid: Option<u32>,
+    status: std::process::ExitStatus,
+) {
+    let Some(exit_tx) = exit_tx else { return };
+    tokio::time::sleep(EXIT_STOP_GRACE).await;
+    let _ = exit_tx.send(WorkerExit { key, pid, status });
+}
+
+/// Add a finished worker to the history database. A failed write is only
+/// logged; history must never hold up the migration.
+fn record_history(history: Option<&HistoryDb>, worker: &WorkerInfo) {
+    let Some(history) = history else {
+        return;
+    };
+    let run = HistoryRun {
+        directory: worker.directory.clone(),
+        tool: worker.tool.clone(),
+        started_at: worker.started_at,
+        finished_at: worker.finished_at,
+        bytes: worker.bytes,
+        status: worker.status.as_str().to_string(),
+        retries: worker.attempts.saturating_sub(1),
+        error: worker.error.clone(),
+    };
+    if let Err(e) = history.record(&run) {
+        warn!("{:#}", e);
+    }
+}
+
+/// Lower a spawned tool's CPU and I/O priority as configured. The child
+/// inherits both, so rsync runs at the same priority as its wrapper.
+fn set_priority(cmd: &mut Command, config: &Config) -> Result<()> {
+    let nice = config.cpu_nice;
+    let io_priority = config.io_priority()?;
+    if nice.is_none() && io_priority.is_none() {
+        return Ok(());
+    }
+    
+    // SAFETY: the closure runs between fork and exec, so it only makes
+    // async-signal-safe syscalls and doesn't allocate
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(nice) = nice {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if let Some(io_priority) = io_priority {
+                set_io_priority(io_priority)?;
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(io_priority: i32) -> std::io::Result<()> {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    // SAFETY: ioprio_set takes plain integers; pid 0 means the caller
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, io_priority) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// I/O classes are a Linux feature; elsewhere `io_class` has no effect.
+#[cfg(not(target_os = "linux"))]
+fn set_io_priority(_io_priority: i32) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Tell a spawned tool how to reach and authenticate with this daemon.
+fn set_daemon_env(cmd: &mut Command, config: &Config) {
+    match config.listen_addr() {
+        ListenAddr::Tcp(addr) => {
+            cmd.env("FREIGHT_DAEMON_ADDR", addr);
+        }
+        ListenAddr::Abstract(name) => {
+            cmd.env("FREIGHT_DAEMON_ABSTRACT", name);
+        }
+        ListenAddr::Unix(_) => {}
+    }
+    if let Some(token) = config.resolved_auth_token() {
+        cmd.env(AUTH_TOKEN_ENV, token);
+    }
+    cmd.env("FREIGHT_PROGRESS_INTERVAL_MS", config.progress_interval_ms.to_string());
+}
+
+fn signal_process_group(pid: u32, signal: libc::c_int) -> std::io::Result<()> {
+    let pgid = libc::pid_t::try_from(pid)
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    // SAFETY: kill has no memory-safety preconditions; a negative pid
+    // addresses the process group
+    if unsafe { libc::kill(-pgid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Run a user hook for a worker that just finished. The hook runs in the
+/// background and its outcome is only logged, so a broken hook can never
+/// stall or abort the migration.
+fn spawn_hook(hook: String, worker: &WorkerInfo) {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(&hook)
+        .env("FREIGHT_TOOL", &worker.tool)
+        .env("FREIGHT_DIR", &worker.directory)
+        .env("FREIGHT_STATUS", worker.status.as_str())
+        .env("FREIGHT_BYTES", worker.bytes.unwrap_or(0).to_string())
+        .stdin(Stdio::null());
+    if let Some(error) = &worker.error {
+        cmd.env("FREIGHT_ERROR", error);
+    }
+    
+    let directory = worker.directory.clone();
+    tokio::spawn(async move {
+        match cmd.output().await {
+            Ok(output) if output.status.success() => {
+                info!("Hook '{}' succeeded for {}", hook, directory.display());
+            }
+            Ok(output) => {
+                warn!("Hook '{}' failed for {} ({}): {}",
+                    hook,
+                    directory.display(),
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            Err(e) => {
+                warn!("Failed to run hook '{}' for {}: {}", hook, directory.display(), e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Enqueue `a`..`d` for migration as their scans finish, in that
+    /// order, with `c` never sized.
+    fn migrate_queue(policy: SchedulePolicy) -> Vec<String> {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.schedule_policy = policy;
+        let mut manager = WorkerManager::new();
+        manager.set_config(config);
+        for (name, size) in [("b", 10), ("d", 30), ("a", 20)] {
+            manager.sizes.insert(PathBuf::from(name), size);
+        }
+        manager.pending.push_back((Phase::Scan, PathBuf::from("e")));
+        for name in ["b", "c", "d", "a"] {
+            manager.enqueue_migrate(PathBuf::from(name));
+        }
+        manager
+            .pending
+            .iter()
+            .map(|(phase, directory)| format!("{} {}", phase.tool(), directory.display()))
+            .collect()
+    }
+
+    #[test]
+    fn schedule_policy_orders_the_migrate_queue() {
+        let queue = |names: [&str; 4]| -> Vec<String> {
+            let mut queue = vec!["scan e".to_string()];
+            queue.extend(names.iter().map(|name| format!("migrate {}", name)));
+            queue
+        };
+
+        assert_eq!(migrate_queue(SchedulePolicy::Discovery), queue(["b", "c", "d", "a"]));
+        assert_eq!(migrate_queue(SchedulePolicy::LargestFirst), queue(["d", "a", "b", "c"]));
+        assert_eq!(migrate_queue(SchedulePolicy::SmallestFirst), queue(["b", "a", "d", "c"]));
+        assert_eq!(migrate_queue(SchedulePolicy::Alphabetical), queue(["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn phase_overlap_puts_migrations_ahead_of_queued_scans() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.phase_overlap = true;
+        let mut manager = WorkerManager::new();
+        manager.set_config(config);
+        manager.pending.push_back((Phase::Scan, PathBuf::from("b")));
+
+        manager.enqueue_migrate(PathBuf::from("a"));
+        manager.enqueue_migrate(PathBuf::from("a"));
+
+        assert_eq!(
+            Vec::from(manager.pending),
+            [(Phase::Migrate, PathBuf::from("a")), (Phase::Scan, PathBuf::from("b"))]
+        );
+    }
+
+    /// What `nice` and `ionice` print when spawned with `config`'s priority.
+    #[cfg(target_os = "linux")]
+    async fn spawned_priority(config: &Config) -> String {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "nice; ionice"]);
+        set_priority(&mut cmd, config).unwrap();
+        let output = cmd.output().await.unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    // ionice, like I/O classes, is Linux-only
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn spawned_tools_run_at_the_configured_priority() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        let inherited = std::process::Command::new("sh")
+            .args(["-c", "nice; ionice"])
+            .output()
+            .unwrap();
+        assert_eq!(spawned_priority(&config).await, String::from_utf8(inherited.stdout).unwrap());
+
+        config.cpu_nice = Some(15);
+        config.io_class = Some("best-effort:7".to_string());
+        assert_eq!(spawned_priority(&config).await, "15\nbest-effort: prio 7\n");
+
+        config.io_class = Some("idle".to_string());
+        assert_eq!(spawned_priority(&config).await, "15\nidle\n");
+    }
+
+    #[tokio::test]
+    async fn queued_duplicates_of_a_running_worker_are_dropped() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.thresholds.parallel_workers = 4;
+        let mut manager = WorkerManager::new();
+        manager.set_config(config);
+        let directory = PathBuf::from("/src/a");
+        manager.workers.insert(
+            WorkerKey::new("migrate", &directory),
+            WorkerInfo::running("migrate", &directory, Some(1)),
+        );
+        manager.pending.push_back((Phase::Migrate, directory.clone()));
+        manager.pending.push_back((Phase::Migrate, directory.clone()));
+
+        manager.fill_slots().await;
+
+        assert!(manager.pending.is_empty());
+        assert_eq!(manager.workers.len(), 1);
+        assert_eq!(manager.running_workers(), 1);
+        assert_eq!(manager.workers[&WorkerKey::new("migrate", &directory)].pid, Some(1));
+    }
+
+    /// Names `discover_directories` picks under a source holding `proj-a`,
+    /// `proj-b`, `other` and `.freight`.
+    async fn discovered(include: &[&str], only: &[&str]) -> Vec<String> {
+        let source = tempfile::tempdir().unwrap();
+        for name in ["proj-a", "proj-b", "other", ".freight"] {
+            std::fs::create_dir(source.path().join(name)).unwrap();
+        }
+        let mut config = Config::default_with_paths(source.path().to_str().unwrap(), "/dst");
+        config.include_patterns = include.iter().map(|pattern| pattern.to_string()).collect();
+        config.only_directories = only.iter().map(|name| name.to_string()).collect();
+        let mut names: Vec<String> = discover_directories(&config)
+            .await
+            .unwrap()
+            .directories
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn include_patterns_narrow_discovery() {
+        assert_eq!(discovered(&[], &[]).await, ["other", "proj-a", "proj-b"]);
+        assert_eq!(discovered(&["proj-*"], &[]).await, ["proj-a", "proj-b"]);
+        assert_eq!(discovered(&["proj-b", "oth?r"], &[]).await, ["other", "proj-b"]);
+        assert!(discovered(&["nothing-*"], &[]).await.is_empty());
+        // --only names directories outright, patterns or not
+        assert_eq!(discovered(&["proj-*"], &["other"]).await, ["other"]);
+    }
+
+    #[test]
+    fn checksum_algorithms_map_to_rsync_checksum_choice() {
+        for (algorithm, flag) in [
+            (ChecksumAlgorithm::Md5, Some(" --checksum-choice=md5")),
+            (ChecksumAlgorithm::Xxhash, Some(" --checksum-choice=xxh64")),
+            (ChecksumAlgorithm::Sha1, None),
+        ] {
+            let mut flags = "-a".to_string();
+            add_checksum_flags(&mut flags, algorithm);
+            assert_eq!(flags, format!("-a{}", flag.unwrap_or_default()), "{:?}", algorithm);
+        }
+
+        // A choice already in the flags wins
+        for flags in ["-a --checksum-choice=md5", "-a --cc=xxh128"] {
+            let mut chosen = flags.to_string();
+            add_checksum_flags(&mut chosen, ChecksumAlgorithm::Xxhash);
+            assert_eq!(chosen, flags);
+        }
+    }
+
+    #[test]
+    fn checksum_algorithm_rsync_cannot_use_is_rejected_early() {
+        let mut config = Config::default_with_paths("/src", "/dst");
+        config.checksum_algorithm = Some(ChecksumAlgorithm::Sha1);
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains("checksum_algorithm sha1 isn't supported"), "{}", error);
+
+        config.backend = MigrationBackend::Tar;
+        config.validate().unwrap();
+        config.backend = MigrationBackend::Rsync;
+        config.checksum_algorithm = Some(ChecksumAlgorithm::Xxhash);
+        config.validate().unwrap();
+        assert!(migrate_rsync_flags(&config).ends_with(" --checksum-choice=xxh64"));
+    }
+
+    #[tokio::test]
+    async fn output_beyond_the_capture_limit_keeps_only_its_tail() {
+        // About 1 MB of stderr against a 1 KB limit
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg("seq 1 150000 >&2; echo last line >&2; exit 3")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let (status, stderr) =
+            wait_streaming(child, "migrate", Path::new("/src/a"), 1024, None).await.unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        assert!(stderr.len() <= 1024, "kept {} bytes", stderr.len());
+        assert!(stderr.ends_with("149999\n150000\nlast line"), "{}", stderr);
+    }
+
+    #[test]
+    fn output_tail_trims_a_line_longer_than_the_limit_to_its_end() {
+        let mut tail = OutputTail::new(8);
+        tail.push("first");
+        tail.push("0123456789abcdef");
+        assert_eq!(tail.text(), "89abcdef");
+    }
+
+    /// Keeps each event's fields as `event_json` renders them.
+    #[derive(Clone, Default)]
+    struct Recorded(Arc<std::sync::Mutex<Vec<serde_json::Map<String, serde_json::Value>>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Recorded {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            self.0.lock().unwrap().push(crate::logging::event_json(event));
+        }
+    }
+
+    #[test]
+    fn summary_event_carries_the_run_totals_as_fields() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let directory = |status: &str, bytes, retries| DirectoryReport {
+            directory: PathBuf::from(format!("/src/{}", status)),
+            phase: "migrate".to_string(),
+            status: status.to_string(),
+            bytes_transferred: bytes,
+            duration_secs: None,
+            retries,
+            error: None,
+        };
+        let directories = vec![
+            directory("completed", Some(3000), 1),
+            directory("failed", Some(1000), 2),
+            directory("cancelled", None, 0),
+        ];
+        let generated_at = Utc::now();
+        let started_at = generated_at - chrono::Duration::seconds(4);
+        let report = MigrationReport {
+            started_at: Some(started_at),
+            generated_at,
+            source_path: PathBuf::from("/src"),
+            dest_path: PathBuf::from("/dst"),
+            dry_run: false,
+            totals: RunTotals::new(&directories, Some(started_at), generated_at),
+            directories,
+        };
+        let recorded = Recorded::default();
+        let subscriber = tracing_subscriber::registry().with(recorded.clone());
+
+        tracing::subscriber::with_default(subscriber, || log_summary(&report));
+
+        let events = recorded.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event["bytes"], 4000);
+        assert_eq!(event["duration_secs"], 4.0);
+        assert_eq!(event["bytes_per_sec"], 1000.0);
+        assert_eq!(event["succeeded"], 1);
+        assert_eq!(event["failed"], 1);
+        assert_eq!(event["cancelled"], 1);
+        assert_eq!(event["retries"], 3);
+        let message = event["message"].as_str().unwrap();
+        assert!(message.starts_with("Migration summary: "), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn rsync_meter_redraws_reach_subscribers_as_progress() {
+        let (server, mut messages) = SocketServer::new(16);
+        let progress = ProgressSink {
+            parser: progress::parser_for(MigrationBackend::Rsync),
+            server: Arc::new(server),
+            interval: Duration::ZERO,
+            last_sent: None,
+            held: None,
+        };
+        // One meter line redrawn with \r, as rsync --info=progress2 does
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "printf '  1,024  25%%  1.00kB/s  0:00:03\\r  2,048  50%%  1.00kB/s  0:00:02\\r\
+                 \\n  4,096 100%%  2.00kB/s  0:00:02 (xfr#1, to-chk=0/1)\\n'",
+            )
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let (status, _) =
+            wait_streaming(child, "migrate", Path::new("/src/a"), 1024, Some(progress))
+                .await
+                .unwrap();
+
+        assert!(status.success());
+        let mut updates = Vec::new();
+        while let Ok(message) = messages.try_recv() {
+            assert!(matches!(message.message_type, crate::socket::MessageType::Progress));
+            assert_eq!(message.directory.as_deref(), Some("/src/a"));
+            updates.push((message.bytes, message.files, message.message));
+        }
+        assert_eq!(
+            updates,
+            [
+                (Some(1024), None, Some("25% at 1.00kB/s".to_string())),
+                (Some(2048), None, Some("50% at 1.00kB/s".to_string())),
+                (Some(4096), Some(1), Some("100% at 2.00kB/s".to_string())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn spawn_error_reaches_a_subscribed_client() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut server, _rx) = SocketServer::new(16);
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let addr = ListenAddr::Tcp(address.to_string());
+        server.set_listen_addr(addr.clone());
+        let server = Arc::new(server);
+        tokio::spawn({
+            let server = Arc::clone(&server);
+            async move { server.start().await }
+        });
+        server.wait_listening().await;
+        let mut subscriber = crate::socket::connect(&addr).await.unwrap();
+        let subscribe = format!("{}\n", crate::socket::SUBSCRIBE_COMMAND);
+        subscriber.write_all(subscribe.as_bytes()).await.unwrap();
+        let mut subscriber = BufReader::new(subscriber);
+
+        let mut manager = WorkerManager::new();
+        manager.set_config(Config::default_with_paths("/src", "/dst"));
+        manager.set_socket_server(Arc::clone(&server));
+        // No process can be given an argument with a NUL in it
+        let unspawnable = PathBuf::from("/src/a\0b");
+        // Nothing confirms the subscription, so keep failing until it's heard
+        let received = tokio::time::timeout(Duration::from_secs(10), async {
+            let mut line = String::new();
+            loop {
+                manager.pending.push_back((Phase::Scan, unspawnable.clone()));
+                manager.fill_slots().await;
+                tokio::select! {
+                    read = subscriber.read_line(&mut line) => {
+                        read.unwrap();
+                        break line;
+                    }
+                    _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+                }
+            }
+        })
+        .await
+        .expect("subscriber heard nothing");
+
+        let notice: WorkerMessage = serde_json::from_str(&received).unwrap();
+        assert!(matches!(notice.message_type, crate::socket::MessageType::Notice));
+        assert_eq!(notice.status.as_deref(), Some("error"));
+        let text = notice.message.unwrap();
+        assert!(text.starts_with("Failed to start scan worker for /src/a"), "{}", text);
+        let worker = &manager.workers[&WorkerKey::new("scan", &unspawnable)];
+        assert_eq!(worker.status, WorkerStatus::Failed);
+        assert_eq!(worker.error.as_deref(), Some(text.as_str()));
+
+        let mut line = String::new();
+        subscriber.read_line(&mut line).await.unwrap();
+        let stop: WorkerMessage = serde_json::from_str(&line).unwrap();
+        assert!(matches!(stop.message_type, crate::socket::MessageType::Stop));
+        assert_eq!(stop.status.as_deref(), Some("failed"));
+        let workers = server.get_workers().await;
+        assert_eq!(workers[&WorkerKey::new("scan", &unspawnable)].status, "failed");
+    }
+}
@@ -0,0 +1,459 @@
+//! The `freight` binary, run as a user would.
+
+mod common;
+
+use common::{Project, RUN_TIMEOUT};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// The binary, without the user's global config.
+fn freight() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_freight"));
+    command.env("XDG_CONFIG_HOME", "/nonexistent");
+    command
+}
+
+/// A project whose config logs to `.freight/daemon.log`.
+fn project_logging_to_a_file() -> Project {
+    let project = Project::new(&[]);
+    let mut config = project.config();
+    config.log_file = Some("daemon.log".into());
+    config.save(&config.config_path()).unwrap();
+    project
+}
+
+#[test]
+fn logs_prints_the_tail_of_the_newest_log_file() {
+    let project = project_logging_to_a_file();
+    let freight_dir = project.source.join(".freight");
+    fs::write(freight_dir.join("daemon.log.2026-10-16"), "yesterday\n").unwrap();
+    let today: String = (1..=30).map(|n| format!("line {}\n", n)).collect();
+    fs::write(freight_dir.join("daemon.log.2026-10-17"), today).unwrap();
+
+    let output = freight()
+        .args(["logs", "-n", "3"])
+        .current_dir(&project.source)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "line 28\nline 29\nline 30\n");
+}
+
+#[test]
+fn logs_before_the_daemon_has_logged_anything() {
+    let project = project_logging_to_a_file();
+
+    let output = freight().arg("logs").current_dir(&project.source).output().unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No log file yet"));
+}
+
+#[tokio::test]
+async fn logs_follow_streams_appended_lines() {
+    let project = project_logging_to_a_file();
+    let log = project.source.join(".freight/daemon.log.2026-10-17");
+    fs::write(&log, "old\nnewest\n").unwrap();
+    let mut follow = tokio::process::Command::new(env!("CARGO_BIN_EXE_freight"))
+        .args(["logs", "--follow", "-n", "1"])
+        .current_dir(&project.source)
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(follow.stdout.take().unwrap()).lines();
+    let mut next_line = async || {
+        tokio::time::timeout(RUN_TIMEOUT, stdout.next_line())
+            .await
+            .expect("nothing printed")
+            .unwrap()
+            .unwrap()
+    };
+
+    assert_eq!(next_line().await, "newest");
+    let mut file = OpenOptions::new().append(true).open(&log).unwrap();
+    writeln!(file, "appended 1").unwrap();
+    assert_eq!(next_line().await, "appended 1");
+    writeln!(file, "appended 2\nappended 3").unwrap();
+    assert_eq!(next_line().await, "appended 2");
+    assert_eq!(next_line().await, "appended 3");
+
+    follow.kill().await.unwrap();
+}
+
+#[test]
+fn init_writes_a_placeholder_config() {
+    let root = tempfile::tempdir().unwrap();
+
+    let output = freight().arg("init").current_dir(root.path()).output().unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("initialized successfully"));
+    assert!(root.path().join(".freight/.freight-root").exists());
+    let config = freight::Config::load(&root.path().join(".freight/config.json")).unwrap();
+    assert_eq!(config.source_path, root.path().canonicalize().unwrap());
+    assert_eq!(config.dest_path, std::path::Path::new("/path/to/destination"));
+}
+
+#[test]
+fn init_refuses_an_existing_project_without_force() {
+    let project = Project::new(&[]);
+    let config_path = project.source.join(".freight/config.json");
+    let mut config = project.config();
+    config.thresholds.parallel_workers = 9;
+    config.save(&config_path).unwrap();
+
+    let output = freight().arg("init").current_dir(&project.source).output().unwrap();
+
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("is already a freight project"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(freight::Config::load(&config_path).unwrap().thresholds.parallel_workers, 9);
+
+    let output = freight()
+        .args(["init", "--force"])
+        .current_dir(&project.source)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let config = freight::Config::load(&config_path).unwrap();
+    assert_eq!(config.dest_path, std::path::Path::new("/path/to/destination"));
+}
+
+/// The raw config `freight init` wrote in `root`, so the test process's
+/// own global config can't colour it.
+fn written_config(root: &std::path::Path) -> serde_json::Value {
+    let content = fs::read_to_string(root.join(".freight/config.json")).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+#[test]
+fn init_takes_defaults_from_the_global_config() {
+    let config_home = tempfile::tempdir().unwrap();
+    fs::create_dir(config_home.path().join("freight")).unwrap();
+    fs::write(
+        config_home.path().join("freight/config.toml"),
+        "rsync_flags = \"-aHAX\"\n[thresholds]\nparallel_workers = 2\n",
+    )
+    .unwrap();
+    let with_global = tempfile::tempdir().unwrap();
+    let without_global = tempfile::tempdir().unwrap();
+
+    let output = freight()
+        .arg("init")
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .current_dir(with_global.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = freight().arg("init").current_dir(without_global.path()).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let defaults =
+        serde_json::to_value(freight::Config::default_with_paths("/src", "/dst")).unwrap();
+    let config = written_config(with_global.path());
+    assert_eq!(config["rsync_flags"], "-aHAX");
+    assert_eq!(config["thresholds"]["parallel_workers"], 2);
+    // Whatever the global config leaves out keeps its default
+    assert_eq!(
+        config["thresholds"]["large_directory_size"],
+        defaults["thresholds"]["large_directory_size"]
+    );
+    assert_eq!(config["retry_attempts"], defaults["retry_attempts"]);
+    let config = written_config(without_global.path());
+    assert_eq!(config["rsync_flags"], defaults["rsync_flags"]);
+    assert_eq!(config["thresholds"], defaults["thresholds"]);
+}
+
+#[test]
+fn completions_generate_for_every_shell() {
+    for shell in ["bash", "zsh", "fish", "elvish", "powershell"] {
+        let output = freight().args(["completions", shell]).output().unwrap();
+
+        assert!(output.status.success(), "{}: {}", shell, String::from_utf8_lossy(&output.stderr));
+        let script = String::from_utf8_lossy(&output.stdout);
+        for subcommand in ["init", "migrate", "logs"] {
+            assert!(script.contains(subcommand), "{} completions lack {}", shell, subcommand);
+        }
+    }
+
+    assert!(!freight().args(["completions", "tcsh"]).output().unwrap().status.success());
+}
+
+#[tokio::test]
+async fn migrate_stops_at_once_without_the_tool_binaries() {
+    let project = Project::new(&["alpha"]);
+    let empty_path = tempfile::tempdir().unwrap();
+
+    let output = tokio::time::timeout(
+        RUN_TIMEOUT,
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_freight"))
+            .arg("migrate")
+            .arg(&project.source)
+            .arg(&project.dest)
+            .env("PATH", empty_path.path())
+            .env("XDG_CONFIG_HOME", "/nonexistent")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .expect("migrate hung without its tools")
+    .unwrap();
+
+    assert_eq!(output.status.code(), Some(freight::exit::FAILURE));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("freight-scan not found on PATH"), "{}", stderr);
+    assert!(project.runs("alpha", "scan").is_empty());
+}
+
+#[test]
+fn failures_exit_with_the_code_for_their_cause() {
+    let project = Project::new(&[]);
+    let mut config = project.config();
+    config.retry_attempts = 0;
+    config.save(&config.config_path()).unwrap();
+
+    let output = freight()
+        .arg("migrate")
+        .arg(&project.source)
+        .arg(&project.dest)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(freight::exit::CONFIG_ERROR));
+
+    // Nothing listens on a port the OS just handed back
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let mut config = project.config();
+    config.transport = freight::socket::Transport::Tcp;
+    config.tcp_address = port.to_string();
+    config.save(&config.config_path()).unwrap();
+
+    let output = freight().arg("status").current_dir(&project.source).output().unwrap();
+    assert_eq!(
+        output.status.code(),
+        Some(freight::exit::DAEMON_NOT_RUNNING),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Run `freight migrate` on a pseudo-terminal, quit the dashboard with `q`
+/// once `ready` holds, and return the exit code.
+async fn migrate_and_quit(project: &Project, ready: impl Fn() -> bool) -> Option<i32> {
+    let migrate = format!(
+        "{} migrate {} {} --no-confirm-quit",
+        env!("CARGO_BIN_EXE_freight"),
+        project.source.display(),
+        project.dest.display()
+    );
+    let mut script = tokio::process::Command::new("script")
+        .args(["-qec", &migrate, "/dev/null"])
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while !ready() {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("migration never got there");
+    let mut stdin = script.stdin.take().unwrap();
+    tokio::io::AsyncWriteExt::write_all(&mut stdin, b"q").await.unwrap();
+    let status = tokio::time::timeout(RUN_TIMEOUT, script.wait())
+        .await
+        .expect("dashboard did not quit")
+        .unwrap();
+    status.code()
+}
+
+#[tokio::test]
+async fn migrate_exits_by_the_final_report() {
+    common::install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    project.control("beta", "migrate", "STATUS=failed");
+    let config = project.config();
+    config.save(&config.config_path()).unwrap();
+    let report = config.report_path.clone().unwrap();
+
+    let code = migrate_and_quit(&project, || report.exists()).await;
+
+    assert_eq!(code, Some(freight::exit::MIGRATION_FAILED));
+}
+
+#[tokio::test]
+async fn quitting_the_dashboard_mid_run_is_a_failure() {
+    common::install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    project.control("alpha", "migrate", "HANG=1");
+    let config = project.config();
+    config.save(&config.config_path()).unwrap();
+
+    let code = migrate_and_quit(&project, || !project.runs("alpha", "migrate").is_empty()).await;
+
+    assert_eq!(code, Some(freight::exit::FAILURE));
+}
+
+#[test]
+fn quiet_leaves_out_progress_logging_and_banners() {
+    let root = tempfile::tempdir().unwrap();
+
+    let loud = freight().arg("init").current_dir(root.path()).output().unwrap();
+    assert!(String::from_utf8_lossy(&loud.stdout).contains("initialized successfully"));
+    fs::remove_dir_all(root.path().join(".freight")).unwrap();
+
+    let quiet = freight().args(["init", "--quiet"]).current_dir(root.path()).output().unwrap();
+    assert!(quiet.status.success(), "{}", String::from_utf8_lossy(&quiet.stderr));
+    assert!(quiet.stdout.is_empty(), "{}", String::from_utf8_lossy(&quiet.stdout));
+    assert!(!String::from_utf8_lossy(&quiet.stderr).contains("INFO"));
+    assert!(root.path().join(".freight/config.json").exists());
+}
+
+/// `freight status` run on a pseudo-terminal, as a user would see it.
+async fn status_on_a_terminal(project: &Project, no_color: Option<&str>) -> String {
+    let status = format!("{} status", env!("CARGO_BIN_EXE_freight"));
+    let mut script = tokio::process::Command::new("script");
+    script
+        .args(["-qec", &status, "/dev/null"])
+        .current_dir(&project.source)
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .env_remove("NO_COLOR")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    if let Some(value) = no_color {
+        script.env("NO_COLOR", value);
+    }
+    let output = tokio::time::timeout(RUN_TIMEOUT, script.output())
+        .await
+        .expect("status hung")
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[tokio::test]
+async fn no_color_strips_styling_from_the_status_table() {
+    let (_server, addr) = common::serve(freight::SocketServer::new(16).0).await;
+    common::send_lines(&addr, &["START tool=migrate dir=/src/a"]).await;
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while freight::socket::query_workers(&addr, None).await.unwrap().workers.is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("daemon never heard the worker");
+    let project = Project::new(&[]);
+    let mut config = project.config();
+    let freight::socket::ListenAddr::Tcp(tcp_address) = &addr else {
+        unreachable!()
+    };
+    config.tcp_address = tcp_address.clone();
+    config.save(&config.config_path()).unwrap();
+
+    let colored = status_on_a_terminal(&project, None).await;
+    assert!(colored.contains("/src/a"), "{}", colored);
+    assert!(colored.contains('\x1b'), "{:?}", colored);
+
+    let plain = status_on_a_terminal(&project, Some("1")).await;
+    assert!(plain.contains("/src/a"), "{}", plain);
+    assert!(!plain.contains('\x1b'), "{:?}", plain);
+}
+
+#[tokio::test]
+async fn migrate_rejects_a_bogus_rsync_flag_before_any_worker_spawns() {
+    common::install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.rsync_flags = "-a --bogus".to_string();
+    config.save(&config.config_path()).unwrap();
+    // An rsync that knows every flag but --bogus
+    let rsync_dir = tempfile::tempdir().unwrap();
+    let rsync = rsync_dir.path().join("rsync");
+    fs::write(
+        &rsync,
+        "#!/bin/sh\nfor arg; do\n    case \"$arg\" in\n        --bogus)\n            \
+         echo \"rsync: $arg: unknown option\" >&2\n            exit 1 ;;\n    esac\n\
+         done\n",
+    )
+    .unwrap();
+    fs::set_permissions(&rsync, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", rsync_dir.path().display(), std::env::var("PATH").unwrap());
+
+    let output = tokio::time::timeout(
+        RUN_TIMEOUT,
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_freight"))
+            .arg("migrate")
+            .arg(&project.source)
+            .arg(&project.dest)
+            .env("PATH", path)
+            .env("XDG_CONFIG_HOME", "/nonexistent")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .expect("migrate hung on a bogus rsync flag")
+    .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("rsync rejected the migrate flags"), "{}", stderr);
+    assert!(stderr.contains("rsync: --bogus: unknown option"), "{}", stderr);
+    assert!(project.runs("alpha", "scan").is_empty());
+    assert!(project.runs("alpha", "migrate").is_empty());
+}
+
+#[test]
+fn validate_passes_a_valid_project_and_lists_what_is_wrong_with_others() {
+    let validate = |project: &Project| {
+        let output = freight().arg("validate").current_dir(&project.source).output().unwrap();
+        (output.status.code(), String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let initialized = |edit: fn(&mut freight::Config)| {
+        let project = Project::new(&[]);
+        let mut config = project.config();
+        edit(&mut config);
+        freight::Config::init_project_with(project.source.to_str().unwrap(), &config).unwrap();
+        project
+    };
+
+    let project = initialized(|_| {});
+    let (code, stdout) = validate(&project);
+    assert_eq!(code, Some(0), "{}", stdout);
+    assert!(stdout.trim_end().ends_with(": valid"), "{}", stdout);
+
+    let (code, stdout) = validate(&Project::new(&[]));
+    assert_eq!(code, Some(freight::exit::CONFIG_ERROR));
+    assert!(stdout.contains("run `freight init` first"), "{}", stdout);
+
+    let project = initialized(|_| {});
+    fs::remove_file(project.source.join(".freight/.freight-root")).unwrap();
+    let (code, stdout) = validate(&project);
+    assert_eq!(code, Some(freight::exit::CONFIG_ERROR));
+    assert!(stdout.contains(".freight-root marker is missing"), "{}", stdout);
+
+    let project = initialized(|config| config.retry_attempts = 0);
+    let (code, stdout) = validate(&project);
+    assert_eq!(code, Some(freight::exit::CONFIG_ERROR));
+    assert!(stdout.contains("retry_attempts must be at least 1"), "{}", stdout);
+
+    // A project copied from elsewhere, still pointing at its old source
+    let project = initialized(|_| {});
+    let elsewhere = Project::new(&[]);
+    let mut config = freight::Config::load(&project.source.join(".freight/config.json")).unwrap();
+    config.source_path = elsewhere.source.clone();
+    config.save(&project.source.join(".freight/config.json")).unwrap();
+    let (code, stdout) = validate(&project);
+    assert_eq!(code, Some(freight::exit::CONFIG_ERROR));
+    assert!(stdout.contains("is not this project's directory"), "{}", stdout);
+}
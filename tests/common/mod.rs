@@ -0,0 +1,204 @@
+//! Fake tools and configs for driving real migrations in tests.
+//!
+//! The fake `freight-scan` and `freight-migrate` speak the daemon protocol
+//! over TCP like the real tools, but copy nothing. A unit can steer them
+//! with a `.fake-scan` or `.fake-migrate` file, sourced by bash, setting:
+//!
+//! - `DELAY`: seconds to wait between START and STOP
+//! - `STATUS`: the STOP status, `ok` by default
+//! - `BYTES`: bytes reported in STOP (as `bytes_total` for scan)
+//! - `SILENT=1`: send nothing and exit 0
+//! - `HANG=1`: send START and never finish
+//!
+//! Every run appends `<start ms> <args…>` to `.fake-<tool>.ran` in the unit.
+
+#![allow(dead_code)]
+
+use freight::socket::ListenAddr;
+use freight::{Config, SocketServer};
+use std::fs;
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::io::AsyncWriteExt;
+use std::time::Duration;
+
+/// Long enough for any test run, short enough that a hang fails fast.
+pub const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+const FAKE_TOOL: &str = r#"#!/usr/bin/env bash
+tool="${0##*/freight-}"
+if [[ "$tool" == "scan" ]]; then
+    unit="${@: -1}"
+else
+    unit="${@: -2:1}"
+fi
+case "$unit" in
+    */) unit="${unit%/}" ;;
+esac
+if [[ -d "$unit" ]]; then
+    control="$unit/.fake-$tool"
+    ran="$unit/.fake-$tool.ran"
+else
+    control="${unit}.fake-$tool"
+    ran="${unit}.fake-$tool.ran"
+fi
+DELAY=0
+STATUS=ok
+BYTES=0
+SILENT=
+HANG=
+[[ -f "$control" ]] && source "$control"
+echo "$(( $(date +%s%N) / 1000000 )) $*" >> "$ran"
+[[ -n "$SILENT" ]] && exit 0
+
+dir="${unit//%/%25}"
+dir="${dir// /%20}"
+send() {
+    exec 3<>"/dev/tcp/${FREIGHT_DAEMON_ADDR%:*}/${FREIGHT_DAEMON_ADDR##*:}" || return
+    [[ -n "${FREIGHT_AUTH_TOKEN:-}" ]] && echo "AUTH $FREIGHT_AUTH_TOKEN" >&3
+    echo "$1" >&3
+    exec 3>&-
+}
+
+send "START tool=$tool dir=$dir"
+if [[ -n "$HANG" ]]; then
+    sleep 600
+    exit 1
+fi
+sleep "$DELAY"
+if [[ "$tool" == "scan" ]]; then
+    send "STOP tool=$tool dir=$dir status=$STATUS bytes_total=$BYTES"
+else
+    send "STOP tool=$tool dir=$dir status=$STATUS bytes=$BYTES"
+fi
+[[ "$STATUS" == "ok" ]]
+"#;
+
+/// Put the fake tools first on `PATH`, once per test binary. Call it before
+/// anything spawns a process, so no other thread reads the environment
+/// while it changes.
+pub fn install_fake_tools() {
+    static TOOLS: OnceLock<PathBuf> = OnceLock::new();
+    TOOLS.get_or_init(|| {
+        let dir = tempfile::tempdir().unwrap().keep();
+        for tool in ["freight-scan", "freight-migrate"] {
+            let path = dir.join(tool);
+            fs::write(&path, FAKE_TOOL).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let path = std::env::var("PATH").unwrap_or_default();
+        std::env::set_var("PATH", format!("{}:{}", dir.display(), path));
+        dir
+    });
+}
+
+/// Run a migration through the library to the end.
+pub async fn run(config: Config) -> freight::MigrationSummary {
+    tokio::time::timeout(RUN_TIMEOUT, freight::Freight::new(config).run_migration())
+        .await
+        .expect("migration did not finish")
+        .expect("migration failed")
+}
+
+/// The arguments the one migrate run on `name` was given.
+pub fn migrate_args(project: &Project, name: &str) -> String {
+    let runs = project.runs(name, "migrate");
+    assert_eq!(runs.len(), 1, "expected one migrate run on {}: {:?}", name, runs);
+    runs[0].1.clone()
+}
+
+/// Start `server` on a free TCP port and wait until it accepts connections.
+pub async fn serve(mut server: SocketServer) -> (Arc<SocketServer>, ListenAddr) {
+    let addr = ListenAddr::Tcp(free_tcp_address());
+    server.set_listen_addr(addr.clone());
+    let server = Arc::new(server);
+    let listening = Arc::clone(&server);
+    tokio::spawn(async move { listening.start().await });
+    tokio::time::timeout(RUN_TIMEOUT, server.wait_listening())
+        .await
+        .expect("server never started listening");
+    (server, addr)
+}
+
+/// Send `lines` on one connection, as a tool would, and close it.
+pub async fn send_lines(addr: &ListenAddr, lines: &[&str]) {
+    let mut stream = freight::socket::connect(addr).await.unwrap();
+    for line in lines {
+        stream.write_all(format!("{}\n", line).as_bytes()).await.unwrap();
+    }
+    stream.shutdown().await.unwrap();
+}
+
+/// A loopback address nothing is listening on right now.
+pub fn free_tcp_address() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+/// A source tree with a directory per name, and an empty destination, in
+/// a fresh temporary directory.
+pub struct Project {
+    pub root: tempfile::TempDir,
+    pub source: PathBuf,
+    pub dest: PathBuf,
+}
+
+impl Project {
+    pub fn new(directories: &[&str]) -> Self {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("source");
+        let dest = root.path().join("dest");
+        fs::create_dir(&source).unwrap();
+        fs::create_dir(&dest).unwrap();
+        for directory in directories {
+            fs::create_dir(source.join(directory)).unwrap();
+        }
+        Self { root, source, dest }
+    }
+
+    pub fn unit(&self, name: &str) -> PathBuf {
+        self.source.join(name)
+    }
+
+    /// Steer the fake `tool` for directory `name`.
+    pub fn control(&self, name: &str, tool: &str, script: &str) {
+        fs::write(self.unit(name).join(format!(".fake-{}", tool)), script).unwrap();
+    }
+
+    /// Start times in milliseconds of every run of `tool` on `name`.
+    pub fn runs(&self, name: &str, tool: &str) -> Vec<(u64, String)> {
+        read_runs(&self.unit(name), tool)
+    }
+
+    /// A config for this project on a free TCP port, with the report in
+    /// the project's temporary directory.
+    pub fn config(&self) -> Config {
+        let mut config = Config::default_with_paths(
+            self.source.to_str().unwrap(),
+            self.dest.to_str().unwrap(),
+        );
+        config.transport = freight::socket::Transport::Tcp;
+        config.tcp_address = free_tcp_address();
+        config.report_path = Some(self.root.path().join("report.json"));
+        config.retry_attempts = 1;
+        config
+    }
+}
+
+fn read_runs(unit: &Path, tool: &str) -> Vec<(u64, String)> {
+    let ran = if unit.is_dir() {
+        unit.join(format!(".fake-{}.ran", tool))
+    } else {
+        PathBuf::from(format!("{}.fake-{}.ran", unit.display(), tool))
+    };
+    fs::read_to_string(ran)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| {
+            let (at, args) = line.split_once(' ').unwrap_or((line, ""));
+            (at.parse().unwrap(), args.to_string())
+        })
+        .collect()
+}
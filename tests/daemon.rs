@@ -0,0 +1,44 @@
+//! Standalone daemons started through `freight::daemon`.
+
+mod common;
+
+use common::RUN_TIMEOUT;
+use freight::daemon::start_daemon;
+use freight::socket::{claim_socket_path, ping, AlreadyRunning, ListenAddr};
+use std::time::Duration;
+
+#[tokio::test]
+async fn second_daemon_on_a_live_socket_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr = ListenAddr::Unix(dir.path().join("freight.sock"));
+    let first = tokio::spawn({
+        let addr = addr.clone();
+        async move { start_daemon(addr, false, None).await }
+    });
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while !ping(&addr, None, Duration::from_millis(100)).await {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("first daemon never answered");
+
+    let error = start_daemon(addr.clone(), false, None).await.unwrap_err();
+
+    assert!(error.downcast_ref::<AlreadyRunning>().is_some(), "{:#}", error);
+    assert!(ping(&addr, None, Duration::from_secs(1)).await, "first daemon was disturbed");
+    first.abort();
+}
+
+#[tokio::test]
+async fn stale_socket_file_is_reclaimed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("freight.sock");
+    // A listener that has gone away leaves its socket file behind
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    claim_socket_path(&path).await.unwrap();
+
+    assert!(!path.exists());
+}
@@ -0,0 +1,150 @@
+//! What hooks and webhooks hear about a migration.
+
+mod common;
+
+use common::{install_fake_tools, run, Project, RUN_TIMEOUT};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// Hooks run in the background, so they may still be going when the run
+/// returns.
+async fn read_lines_when(path: &Path, count: usize) -> Vec<String> {
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        loop {
+            let mut lines: Vec<String> = std::fs::read_to_string(path)
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_string)
+                .collect();
+            if lines.len() >= count {
+                lines.sort();
+                return lines;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{} never got {} lines", path.display(), count))
+}
+
+/// A webhook receiver that answers 200 to every POST and passes on its
+/// JSON body.
+async fn mock_webhook() -> (String, mpsc::UnboundedReceiver<serde_json::Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (body_tx, body_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let body_tx = body_tx.clone();
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+                let _ = body_tx.send(serde_json::from_slice(&body).unwrap());
+                let response = "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+                stream.get_mut().write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    (url, body_rx)
+}
+
+#[tokio::test]
+async fn hooks_receive_the_finished_worker_in_their_environment() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    project.control("alpha", "migrate", "BYTES=42");
+    project.control("beta", "migrate", "STATUS=failed");
+    let log = project.root.path().join("hooks.log");
+    let mut config = project.config();
+    config.on_complete_hook = Some(format!(
+        r#"echo "complete $FREIGHT_TOOL $FREIGHT_STATUS $FREIGHT_BYTES $FREIGHT_DIR" >> {:?}"#,
+        log
+    ));
+    config.on_failure_hook = Some(format!(
+        r#"echo "failure $FREIGHT_TOOL $FREIGHT_STATUS $FREIGHT_BYTES $FREIGHT_ERROR $FREIGHT_DIR" >> {:?}"#,
+        log
+    ));
+
+    run(config).await;
+
+    assert_eq!(
+        read_lines_when(&log, 2).await,
+        [
+            format!("complete migrate completed 42 {}", project.unit("alpha").display()),
+            format!("failure migrate failed 0 failed {}", project.unit("beta").display()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn failing_hook_does_not_fail_the_migration() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.on_complete_hook = Some("exit 3".to_string());
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.succeeded(), 1);
+}
+
+#[tokio::test]
+async fn webhook_receives_failures_and_the_completion_summary() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    project.control("alpha", "migrate", "BYTES=42");
+    project.control("beta", "migrate", "STATUS=failed");
+    let (url, mut bodies) = mock_webhook().await;
+    let mut config = project.config();
+    config.webhook_url = Some(url);
+
+    run(config).await;
+
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let body = tokio::time::timeout(RUN_TIMEOUT, bodies.recv())
+            .await
+            .expect("webhook not delivered")
+            .unwrap();
+        received.push(body);
+    }
+    received.sort_by_key(|body| body["event"].as_str().unwrap().to_string());
+
+    let failed = &received[0];
+    assert_eq!(failed["event"], "directory_failed");
+    assert_eq!(failed["tool"], "migrate");
+    assert_eq!(failed["directory"], project.unit("beta").to_str().unwrap());
+    assert_eq!(failed["status"], "failed");
+    assert!(failed["duration"].is_f64(), "{}", failed);
+    for field in ["bytes", "error"] {
+        assert!(failed.get(field).is_some(), "{} is missing {}", failed, field);
+    }
+
+    let complete = &received[1];
+    assert_eq!(complete["event"], "migration_complete");
+    assert_eq!(complete["source_path"], project.source.to_str().unwrap());
+    assert_eq!(complete["dest_path"], project.dest.to_str().unwrap());
+    assert_eq!(complete["dry_run"], false);
+    assert_eq!(complete["directories"], 2);
+    assert_eq!(complete["succeeded"], 1);
+    assert_eq!(complete["failed"], 1);
+    assert_eq!(complete["bytes"], 42);
+}
@@ -0,0 +1,31 @@
+//! Logging set up the way `main` does it. Tracing's subscriber is global,
+//! so this binary installs it once.
+
+use freight::logging;
+use freight::Config;
+
+#[test]
+fn log_lines_land_in_the_configured_file() {
+    let root = tempfile::tempdir().unwrap();
+    let mut config = Config::default_with_paths(root.path().to_str().unwrap(), "/dst");
+    config.log_file = Some("logs/daemon.log".into());
+    config.log_level = "debug".to_string();
+    let log_file = config.resolved_log_file().unwrap();
+    assert_eq!(log_file, root.path().join(".freight/logs/daemon.log"));
+
+    let guard = logging::init(Some(&config), None, true).unwrap().unwrap();
+    tracing::debug!(directory = "/src/a", "written to the log file");
+    tracing::trace!("below the configured level");
+    drop(guard);
+
+    let written = logging::latest_log_file(&log_file).unwrap().unwrap();
+    let name = written.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("daemon.log."), "not a dated file: {}", name);
+    let content = std::fs::read_to_string(&written).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 1, "{:?}", lines);
+    // No terminal colors in the file, whatever stderr gets
+    assert!(!lines[0].contains('\x1b'), "{:?}", lines[0]);
+    assert!(lines[0].contains(" DEBUG "), "{}", lines[0]);
+    assert!(lines[0].contains("written to the log file directory=\"/src/a\""), "{}", lines[0]);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use freight::metrics::serve_metrics;
+use freight::SocketServer;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+async fn scrape(port: u16) -> String {
+    for _ in 0..50 {
+        if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)).await {
+            stream
+                .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            return response;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("metrics endpoint never came up on port {}", port);
+}
+
+#[tokio::test]
+async fn metrics_endpoint_serves_every_family_with_its_type() {
+    let (server, _rx) = SocketServer::new(16);
+    let port: u16 = common::free_tcp_address().rsplit(':').next().unwrap().parse().unwrap();
+    let endpoint = tokio::spawn(serve_metrics(
+        port,
+        server.workers_handle(),
+        server.counters_handle(),
+    ));
+
+    let response = scrape(port).await;
+    endpoint.abort();
+
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("text/plain; version=0.0.4"), "{}", response);
+    for (name, kind) in [
+        ("freight_workers", "gauge"),
+        ("freight_bytes_transferred_total", "counter"),
+        ("freight_retries_total", "counter"),
+        ("freight_directory_duration_seconds", "histogram"),
+    ] {
+        assert!(
+            response.contains(&format!("# TYPE {} {}\n", name, kind)),
+            "missing {} {} in:\n{}",
+            name,
+            kind,
+            response
+        );
+    }
+    assert!(response.contains("freight_directory_duration_seconds_bucket{le=\"+Inf\"} 0\n"));
+    assert!(response.contains("freight_bytes_transferred_total 0\n"));
+}
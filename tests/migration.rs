@@ -0,0 +1,641 @@
+//! End-to-end migrations against the fake tools in `common`.
+
+mod common;
+
+use common::{install_fake_tools, run, Project, RUN_TIMEOUT};
+use freight::Freight;
+
+#[tokio::test]
+async fn completed_run_writes_a_report_with_every_directory() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    project.control("alpha", "migrate", "BYTES=1000");
+    project.control("beta", "migrate", "BYTES=24");
+    let config = project.config();
+    let report_path = config.report_path.clone().unwrap();
+
+    run(config).await;
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(report_path).unwrap()).unwrap();
+    for field in ["started_at", "generated_at", "source_path", "dest_path", "dry_run", "totals"] {
+        assert!(report.get(field).is_some(), "report is missing {}", field);
+    }
+    assert_eq!(report["totals"]["bytes_transferred"], 1024);
+    assert_eq!(report["totals"]["directories_by_status"]["completed"], 2);
+
+    let directories = report["directories"].as_array().unwrap();
+    assert_eq!(directories.len(), 2);
+    for directory in directories {
+        for field in ["directory", "phase", "status", "bytes_transferred", "duration_secs", "retries", "error"] {
+            assert!(directory.get(field).is_some(), "directory entry is missing {}", field);
+        }
+        assert_eq!(directory["phase"], "migrate");
+        assert_eq!(directory["status"], "completed");
+    }
+}
+
+#[tokio::test]
+async fn run_with_nothing_to_migrate_finishes_with_an_empty_report() {
+    install_fake_tools();
+    let project = Project::new(&[]);
+    let config = project.config();
+    let report_path = config.report_path.clone().unwrap();
+
+    let summary = run(config).await;
+
+    assert!(summary.directories.is_empty());
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(report_path).unwrap()).unwrap();
+    assert_eq!(report["directories"], serde_json::json!([]));
+    assert_eq!(report["totals"]["bytes_transferred"], 0);
+}
+
+#[tokio::test]
+async fn include_patterns_matching_nothing_finishes() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.include_patterns = vec!["nothing-*".to_string()];
+
+    let summary = run(config).await;
+
+    assert!(summary.directories.is_empty());
+    assert!(project.runs("alpha", "scan").is_empty());
+}
+
+#[tokio::test]
+async fn dry_run_plans_every_directory_without_touching_the_destination() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    for name in ["alpha", "beta"] {
+        // What freight-migrate reports when told not to transfer
+        project.control(name, "migrate", r#"[[ " $* " == *" --dry-run "* ]] && STATUS=planned"#);
+    }
+    let mut config = project.config();
+    config.dry_run = true;
+    config.dest_mode = Some("0700".to_string());
+
+    let summary = run(config).await;
+
+    assert!(summary.dry_run);
+    assert!(summary.directories.iter().all(|directory| directory.status == "planned"));
+    for name in ["alpha", "beta"] {
+        let runs = project.runs(name, "migrate");
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].1.starts_with("--dry-run "), "{}", runs[0].1);
+    }
+    assert_eq!(std::fs::read_dir(&project.dest).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn dry_run_into_a_destination_that_does_not_exist_yet() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    project.control("alpha", "migrate", r#"[[ " $* " == *" --dry-run "* ]] && STATUS=planned"#);
+    let dest = project.root.path().join("new/dest");
+    let mut config = project.config();
+    config.dest_path = dest.clone();
+    config.dry_run = true;
+
+    let summary = run(config.clone()).await;
+
+    assert_eq!(summary.directories[0].status, "planned");
+    assert!(!project.root.path().join("new").exists());
+
+    // Still refused where the real run couldn't create it
+    let blocker = project.root.path().join("blocker");
+    std::fs::write(&blocker, "").unwrap();
+    config.dest_path = blocker.join("dest");
+    let error = freight::worker::prepare_dest_root(&config).unwrap_err();
+    assert!(format!("{:#}", error).contains("is not a writable directory"), "{:#}", error);
+}
+
+#[tokio::test]
+async fn cancel_stops_only_the_targeted_worker() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    project.control("alpha", "migrate", r#"echo $$ > "$unit/.pid"; HANG=1"#);
+    project.control("beta", "migrate", r#"touch "$unit/.started"; DELAY=1"#);
+    let mut config = project.config();
+    config.thresholds.parallel_workers = 2;
+    let listen_addr = config.listen_addr();
+    let migration = tokio::spawn(run(config));
+
+    let pid_file = project.unit("alpha").join(".pid");
+    let started = project.unit("beta").join(".started");
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while !pid_file.exists() || !started.exists() {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("migrations never started");
+    let cancel = format!(
+        "CANCEL tool=migrate dir={}",
+        freight::socket::escape_value(project.unit("alpha").to_str().unwrap())
+    );
+    common::send_lines(&listen_addr, &[&cancel]).await;
+    let summary = migration.await.unwrap();
+
+    let status = |name: &str| {
+        summary
+            .directories
+            .iter()
+            .find(|directory| directory.directory == project.unit(name))
+            .map(|directory| directory.status.clone())
+    };
+    assert_eq!(status("alpha").as_deref(), Some("cancelled"));
+    assert_eq!(status("beta").as_deref(), Some("completed"));
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while std::path::Path::new("/proc").join(pid.trim()).exists() {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("cancelled worker is still running");
+}
+
+#[tokio::test]
+async fn stalled_worker_is_retried_after_the_backoff() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    // Hang on the first run only
+    project.control("alpha", "migrate", r#"[[ -s "$ran" ]] || HANG=1"#);
+    let mut config = project.config();
+    config.worker_timeout = Some(1);
+    config.retry_attempts = 2;
+    config.retry_base_delay = 1;
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.directories[0].status, "completed");
+    let runs = project.runs("alpha", "migrate");
+    assert_eq!(runs.len(), 2);
+    // The timeout, then the backoff, before the second run
+    assert!(runs[1].0 - runs[0].0 >= 2000, "retried too soon: {:?}", runs);
+}
+
+#[tokio::test]
+async fn stalled_worker_fails_once_its_attempts_are_used_up() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    project.control("alpha", "migrate", "HANG=1");
+    let mut config = project.config();
+    config.worker_timeout = Some(1);
+    config.retry_attempts = 2;
+    config.retry_base_delay = 0;
+
+    let summary = run(config).await;
+
+    let directory = &summary.directories[0];
+    assert_eq!(directory.status, "failed");
+    assert!(directory.error.as_deref().unwrap().starts_with("Timed out after 1s"));
+    assert_eq!(project.runs("alpha", "migrate").len(), 2);
+}
+
+#[tokio::test]
+async fn subscribers_see_a_worker_fail_once_its_attempts_are_used_up() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    // Like the real tools, report being interrupted when killed
+    project.control(
+        "alpha",
+        "migrate",
+        r#"HANG=1
+trap 'send "STOP tool=$tool dir=$dir status=interrupted"; exit 143' TERM"#,
+    );
+    let mut config = project.config();
+    config.worker_timeout = Some(1);
+    config.retry_attempts = 1;
+    let freight = Freight::new(config);
+    let mut messages = freight.subscribe();
+
+    tokio::time::timeout(RUN_TIMEOUT, freight.run_migration())
+        .await
+        .expect("migration did not finish")
+        .expect("migration failed");
+
+    let stop = std::iter::from_fn(|| messages.try_recv().ok())
+        .find(|message| {
+            matches!(message.message_type, freight::socket::MessageType::Stop)
+                && message.tool == "migrate"
+        })
+        .expect("subscriber never saw the migrate worker stop");
+    assert_eq!(stop.status.as_deref(), Some("failed"));
+    assert!(stop.message.unwrap().starts_with("Timed out after 1s"));
+}
+
+#[tokio::test]
+async fn progress_keeps_a_slow_worker_from_timing_out() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    // Twice the timeout in all, but never a second without a PROGRESS
+    project.control(
+        "alpha",
+        "migrate",
+        r#"DELAY=2.5
+(
+    for bytes in 1 2 3 4 5 6; do
+        sleep 0.4
+        exec 3<>"/dev/tcp/${FREIGHT_DAEMON_ADDR%:*}/${FREIGHT_DAEMON_ADDR##*:}"
+        echo "PROGRESS tool=migrate dir=$unit bytes=$bytes" >&3
+        exec 3>&-
+    done
+) &"#,
+    );
+    let mut config = project.config();
+    config.worker_timeout = Some(1);
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.directories[0].status, "completed");
+    assert_eq!(project.runs("alpha", "migrate").len(), 1);
+}
+
+#[tokio::test]
+async fn only_migrates_the_named_directories() {
+    install_fake_tools();
+    let project = Project::new(&["a", "b", "c"]);
+    let mut config = project.config();
+    config.only_directories = vec!["a".to_string(), "b".to_string()];
+    config.validate().unwrap();
+
+    let summary = run(config).await;
+
+    let mut migrated: Vec<_> = summary.directories.iter().map(|d| d.directory.clone()).collect();
+    migrated.sort();
+    assert_eq!(migrated, [project.unit("a"), project.unit("b")]);
+    assert_eq!(summary.succeeded(), 2);
+    assert!(project.runs("c", "scan").is_empty());
+    assert!(project.runs("c", "migrate").is_empty());
+}
+
+#[test]
+fn only_rejects_directories_not_directly_under_the_source() {
+    let project = Project::new(&["a"]);
+    std::fs::create_dir(project.unit("a").join("nested")).unwrap();
+    for name in ["missing", "a/nested", "../a"] {
+        let mut config = project.config();
+        config.only_directories = vec![name.to_string()];
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains(&format!("--only {}: no such directory", name)), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn replayed_plan_migrates_its_directories_verbatim() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    let mut config = project.config();
+    let mut plan = freight::plan::MigrationPlan::build(&config).await.unwrap();
+    plan.directories.sort_by(|a, b| a.directory.cmp(&b.directory));
+    plan.directories[0].dest = project.dest.join("archive/alpha");
+    let plan_path = freight::plan::default_plan_path(&config.freight_dir());
+    plan.write(&plan_path).unwrap();
+    // The source drifts: one planned directory goes, another appears
+    std::fs::remove_dir_all(project.unit("beta")).unwrap();
+    std::fs::create_dir(project.unit("gamma")).unwrap();
+    config.plan_path = Some(plan_path);
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.directories.len(), 1);
+    assert_eq!(summary.directories[0].directory, project.unit("alpha"));
+    let args = common::migrate_args(&project, "alpha");
+    let planned_dest = project.dest.join("archive/alpha");
+    assert!(args.ends_with(&format!(" {}", planned_dest.display())), "{}", args);
+    assert!(project.runs("gamma", "scan").is_empty());
+}
+
+#[tokio::test]
+async fn unchanged_directories_are_skipped_on_the_next_run() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    for name in ["alpha", "beta"] {
+        std::fs::write(project.unit(name).join(".state"), "v1").unwrap();
+        std::fs::create_dir(project.dest.join(name)).unwrap();
+        // Stands in for freight-scan's hash of the directory's entries
+        project.control(
+            name,
+            "scan",
+            r#"mkdir -p "$unit/.freight"
+printf '{"manifest": "%s"}\n' "$(cat "$unit/.state")" > "$unit/.freight/scan.json""#,
+        );
+    }
+    let summary = run(project.config()).await;
+    assert_eq!(summary.succeeded(), 2);
+
+    std::fs::write(project.unit("beta").join(".state"), "v2").unwrap();
+    let summary = run(project.config()).await;
+
+    let status = |name: &str| {
+        let unit = project.unit(name);
+        let directory = summary
+            .directories
+            .iter()
+            .find(|directory| directory.directory == unit)
+            .unwrap();
+        directory.status.clone()
+    };
+    assert_eq!(status("alpha"), "skipped");
+    assert_eq!(status("beta"), "completed");
+    assert_eq!(project.runs("alpha", "migrate").len(), 1);
+    assert_eq!(project.runs("beta", "migrate").len(), 2);
+
+    let mut config = project.config();
+    config.force = true;
+    run(config).await;
+    assert_eq!(project.runs("alpha", "migrate").len(), 2);
+}
+
+#[tokio::test]
+async fn destination_directories_get_the_configured_mode_before_the_copy() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    // What the copy finds when it starts
+    project.control("alpha", "migrate", r#"stat -c %a "${@: -1}" > "$unit/.dest-mode""#);
+    let mut config = project.config();
+    config.dest_mode = Some("0750".to_string());
+    // Any owner but our own would need root
+    let meta = std::fs::metadata(project.root.path()).unwrap();
+    config.dest_owner = Some(format!("{}:{}", meta.uid(), meta.gid()));
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.succeeded(), 1);
+    let seen = std::fs::read_to_string(project.unit("alpha").join(".dest-mode")).unwrap();
+    assert_eq!(seen.trim(), "750");
+    let dest = std::fs::metadata(project.dest.join("alpha")).unwrap();
+    assert_eq!(dest.permissions().mode() & 0o7777, 0o750);
+    assert_eq!((dest.uid(), dest.gid()), (meta.uid(), meta.gid()));
+}
+
+#[tokio::test]
+async fn scan_that_exits_cleanly_without_reporting_still_leads_to_migrate() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    // A minimal third-party scanner: no START, no STOP, exit 0
+    project.control("alpha", "scan", "SILENT=1");
+    project.control("beta", "scan", "SILENT=1; exit 3");
+
+    let summary = run(project.config()).await;
+
+    assert_eq!(project.runs("alpha", "migrate").len(), 1);
+    assert!(project.runs("beta", "migrate").is_empty());
+    let status = |name: &str| {
+        let unit = project.unit(name);
+        summary
+            .directories
+            .iter()
+            .find(|directory| directory.directory == unit)
+            .map(|directory| (directory.phase.clone(), directory.status.clone()))
+            .unwrap()
+    };
+    assert_eq!(status("alpha"), ("migrate".to_string(), "completed".to_string()));
+    assert_eq!(status("beta"), ("scan".to_string(), "failed".to_string()));
+}
+
+#[tokio::test]
+async fn library_run_streams_messages_and_returns_the_summary() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta", "gamma"]);
+    project.control("beta", "migrate", "STATUS=failed");
+    let freight = Freight::new(project.config());
+    let mut messages = freight.subscribe();
+
+    let summary = tokio::time::timeout(RUN_TIMEOUT, freight.run_migration())
+        .await
+        .expect("migration did not finish")
+        .expect("migration failed");
+
+    assert_eq!(summary.directories.len(), 3);
+    assert_eq!(summary.succeeded(), 2);
+    assert_eq!(summary.failed(), 1);
+    let failed: Vec<_> = summary
+        .directories
+        .iter()
+        .filter(|directory| directory.status == "failed")
+        .map(|directory| directory.directory.clone())
+        .collect();
+    assert_eq!(failed, [project.unit("beta")]);
+
+    let mut stops = 0;
+    while let Ok(message) = messages.try_recv() {
+        if matches!(message.message_type, freight::socket::MessageType::Stop) {
+            stops += 1;
+        }
+    }
+    // A scan and a migrate for each directory
+    assert_eq!(stops, 6);
+}
+
+#[tokio::test]
+async fn unreadable_directory_fails_alone_and_the_rest_migrate() {
+    use std::os::unix::fs::PermissionsExt;
+
+    install_fake_tools();
+    let project = Project::new(&["alpha", "locked", "beta"]);
+    let locked = project.unit("locked");
+    let mode = |mode| std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(mode));
+    mode(0o000).unwrap();
+    if std::fs::read_dir(&locked).is_ok() {
+        // Root lists it anyway, but not another process's map_files
+        mode(0o755).unwrap();
+        std::fs::remove_dir(&locked).unwrap();
+        std::os::unix::fs::symlink("/proc/1/map_files", &locked).unwrap();
+        if std::fs::read_dir(&locked).unwrap().next().is_none_or(|entry| entry.is_ok()) {
+            eprintln!("skipping: no directory here is unreadable to this user");
+            return;
+        }
+    }
+    let dangling = project.source.join("dangling");
+    std::os::unix::fs::symlink(project.root.path().join("missing"), dangling).unwrap();
+
+    let summary = run(project.config()).await;
+    if locked.is_dir() && !locked.is_symlink() {
+        mode(0o755).unwrap();
+    }
+
+    assert_eq!(summary.directories.len(), 3);
+    assert_eq!(summary.succeeded(), 2);
+    let failed = summary.directories.iter().find(|d| d.directory == locked).unwrap();
+    assert_eq!((failed.phase.as_str(), failed.status.as_str()), ("scan", "failed"));
+    assert!(failed.error.as_deref().unwrap().starts_with("Unreadable: "), "{:?}", failed.error);
+    for name in ["alpha", "beta"] {
+        assert_eq!(project.runs(name, "migrate").len(), 1);
+    }
+}
+
+#[tokio::test]
+async fn report_totals_count_bytes_statuses_retries_and_throughput() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta", "gamma"]);
+    project.control("alpha", "migrate", "BYTES=1000");
+    // Announce two attempts, as freight-migrate does when rsync is retried
+    project.control(
+        "beta",
+        "migrate",
+        r#"BYTES=24
+for attempt in 1 2; do
+    exec 3<>"/dev/tcp/${FREIGHT_DAEMON_ADDR%:*}/${FREIGHT_DAEMON_ADDR##*:}"
+    echo "PROGRESS tool=migrate dir=$unit msg=Attempt%20$attempt/2" >&3
+    exec 3>&-
+done
+DELAY=1"#,
+    );
+    project.control("gamma", "migrate", "STATUS=failed");
+    let mut config = project.config();
+    config.retry_attempts = 2;
+    let report_path = config.report_path.clone().unwrap();
+
+    run(config).await;
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(report_path).unwrap()).unwrap();
+    let totals = &report["totals"];
+    assert_eq!(totals["bytes_transferred"], 1024);
+    assert_eq!(totals["directories_by_status"], serde_json::json!({"completed": 2, "failed": 1}));
+    assert_eq!(totals["retries"], 1);
+    // At least as long as beta's delay
+    let duration_secs = totals["duration_secs"].as_f64().unwrap();
+    assert!(duration_secs >= 1.0, "{}", duration_secs);
+    let bytes_per_sec = totals["bytes_per_sec"].as_f64().unwrap();
+    assert!((bytes_per_sec - 1024.0 / duration_secs).abs() < 1e-6, "{}", bytes_per_sec);
+}
+
+#[tokio::test]
+async fn top_level_files_are_units_of_their_own_only_when_asked() {
+    install_fake_tools();
+    let mixed = || {
+        let project = Project::new(&["alpha"]);
+        for file in ["alpha.txt", "notes"] {
+            std::fs::write(project.source.join(file), "data").unwrap();
+        }
+        project
+    };
+
+    let project = mixed();
+    let summary = run(project.config()).await;
+    assert_eq!(summary.directories.len(), 1);
+
+    let project = mixed();
+    let mut config = project.config();
+    config.include_top_level_files = true;
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.directories.len(), 3);
+    assert_eq!(summary.succeeded(), 3);
+    // A file and a directory of related names each get their own run
+    for name in ["alpha", "alpha.txt", "notes"] {
+        let args = common::migrate_args(&project, name);
+        let dest = project.dest.join(name);
+        let expected = format!(" {} {}", project.unit(name).display(), dest.display());
+        assert!(args.ends_with(&expected), "{}", args);
+    }
+}
+
+#[tokio::test]
+async fn dest_that_cannot_be_created_stops_the_run_before_any_worker() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    // Even root can't make a directory under a regular file
+    let blocker = project.root.path().join("blocker");
+    std::fs::write(&blocker, "").unwrap();
+    let mut config = project.config();
+    config.dest_path = blocker.join("dest");
+
+    let result = tokio::time::timeout(RUN_TIMEOUT, Freight::new(config).run_migration())
+        .await
+        .expect("migration did not finish");
+
+    let error = format!("{:#}", result.expect_err("migration should fail"));
+    let expected = format!("Destination {} does not exist", blocker.join("dest").display());
+    assert!(error.contains(&expected), "{}", error);
+    assert!(error.contains("could not be created"), "{}", error);
+    for name in ["alpha", "beta"] {
+        assert!(project.runs(name, "scan").is_empty());
+    }
+}
+
+#[tokio::test]
+async fn missing_dest_is_created_once_with_the_configured_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let dest = project.root.path().join("new/dest");
+    let mut config = project.config();
+    config.dest_path = dest.clone();
+    config.dest_mode = Some("0750".to_string());
+
+    let summary = run(config).await;
+
+    assert_eq!(summary.succeeded(), 1);
+    let mode = std::fs::metadata(&dest).unwrap().permissions().mode() & 0o7777;
+    assert_eq!(mode, 0o750, "{:o}", mode);
+}
+
+#[tokio::test]
+async fn phase_overlap_starts_a_migrate_while_other_scans_still_run() {
+    install_fake_tools();
+    // Two slots: alpha's scan finishes at once while beta's holds the
+    // other slot, and gamma's scan is still queued
+    let started = |phase_overlap| async move {
+        let project = Project::new(&["alpha", "beta", "gamma"]);
+        project.control("beta", "scan", "DELAY=1");
+        let mut config = project.config();
+        config.thresholds.parallel_workers = 2;
+        config.phase_overlap = phase_overlap;
+
+        run(config).await;
+
+        let start = |name, tool| project.runs(name, tool)[0].0;
+        (start("alpha", "migrate"), start("beta", "scan"), start("gamma", "scan"))
+    };
+
+    let (migrate_alpha, scan_beta, scan_gamma) = started(true).await;
+    assert!(migrate_alpha < scan_gamma, "{} vs {}", migrate_alpha, scan_gamma);
+    assert!(migrate_alpha < scan_beta + 1000, "alpha waited for beta's scan");
+
+    let (migrate_alpha, _, scan_gamma) = started(false).await;
+    assert!(migrate_alpha >= scan_gamma, "{} vs {}", migrate_alpha, scan_gamma);
+}
+
+#[tokio::test]
+async fn startup_stagger_spreads_spawns_across_the_window() {
+    install_fake_tools();
+    let names = ["alpha", "beta", "gamma", "delta"];
+    let scan_starts = |stagger_ms| async move {
+        let project = Project::new(&names);
+        for name in names {
+            project.control(name, "scan", "DELAY=2");
+        }
+        let mut config = project.config();
+        config.thresholds.parallel_workers = 4;
+        config.startup_stagger_ms = stagger_ms;
+
+        run(config).await;
+
+        let mut starts: Vec<u64> =
+            names.iter().map(|name| project.runs(name, "scan")[0].0).collect();
+        starts.sort();
+        starts
+    };
+
+    // 1200ms over four slots is a spawn every 300ms
+    let starts = scan_starts(1200).await;
+    for pair in starts.windows(2) {
+        // Bash starting up blurs each timestamp a little
+        assert!(pair[1] - pair[0] >= 200, "too close: {:?}", starts);
+    }
+    // Only the moment of spawn moved: all four scans still ran at once
+    assert!(starts[3] - starts[0] < 2000, "{:?}", starts);
+
+    let starts = scan_starts(0).await;
+    assert!(starts[3] - starts[0] < 500, "{:?}", starts);
+}
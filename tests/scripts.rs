@@ -0,0 +1,459 @@
+//! The real bash tools in bin/, reporting to a real `SocketServer`.
+//!
+//! Neither rsync nor nc has to be installed, as both are faked: `nc`
+//! forwards stdin over bash's `/dev/tcp`, and `rsync` appends its
+//! arguments to `$FAKE_RSYNC_LOG` and exits `$FAKE_RSYNC_STATUS`.
+
+mod common;
+
+use common::{serve, RUN_TIMEOUT};
+use freight::socket::{ListenAddr, MessageType, WorkerState};
+use freight::{SocketServer, WorkerMessage};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const FAKE_NC: &str = r#"#!/usr/bin/env bash
+while [[ "$1" == -* ]]; do shift 2; done
+exec 3<>"/dev/tcp/$1/$2" || exit 1
+cat >&3
+"#;
+
+const FAKE_RSYNC: &str = r#"#!/usr/bin/env bash
+printf '%s\n' "$*" >> "${FAKE_RSYNC_LOG:-/dev/null}"
+exit "${FAKE_RSYNC_STATUS:-0}"
+"#;
+
+/// A directory holding the fake `nc` and `rsync`, made once per binary.
+fn fake_tools() -> &'static Path {
+    static TOOLS: OnceLock<PathBuf> = OnceLock::new();
+    TOOLS.get_or_init(|| {
+        let dir = tempfile::tempdir().unwrap().keep();
+        for (name, script) in [("nc", FAKE_NC), ("rsync", FAKE_RSYNC)] {
+            let path = dir.join(name);
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        dir
+    })
+}
+
+/// A daemon to report to, and a scratch directory for the tool to work in.
+struct Harness {
+    addr: ListenAddr,
+    root: tempfile::TempDir,
+    /// Everything the daemon heard, in order
+    messages: broadcast::Receiver<WorkerMessage>,
+    _server: std::sync::Arc<SocketServer>,
+}
+
+impl Harness {
+    async fn new() -> Self {
+        let (server, messages) = SocketServer::new(1024);
+        let (server, addr) = serve(server).await;
+        Self {
+            addr,
+            root: tempfile::tempdir().unwrap(),
+            messages,
+            _server: server,
+        }
+    }
+
+    /// Make `name` under the scratch directory, with one small file in it.
+    fn dir(&self, name: &str) -> PathBuf {
+        let dir = self.root.path().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file"), "data").unwrap();
+        dir
+    }
+
+    fn rsync_log(&self) -> PathBuf {
+        self.root.path().join("rsync.log")
+    }
+
+    /// Each fake rsync run's arguments, in order.
+    fn rsync_runs(&self) -> Vec<String> {
+        fs::read_to_string(self.rsync_log())
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Run `bin/<tool>` with `args` and the fakes first on PATH.
+    async fn run(&self, tool: &str, args: &[&str], env: &[(&str, &str)]) -> Output {
+        let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("bin").join(tool);
+        self.output(tokio::process::Command::new(script).args(args), env).await
+    }
+
+    /// Run `script` in bash with the tools' libraries sourced, as a tool
+    /// would after starting up.
+    async fn shell(&self, script: &str, env: &[(&str, &str)]) -> Output {
+        let lib = Path::new(env!("CARGO_MANIFEST_DIR")).join("lib");
+        let script = format!(
+            "set -euo pipefail\nfor lib in common logging socket; do source {:?}/$lib.sh; done\n{}",
+            lib, script
+        );
+        self.output(tokio::process::Command::new("bash").args(["-c", &script]), env).await
+    }
+
+    async fn output(&self, command: &mut tokio::process::Command, env: &[(&str, &str)]) -> Output {
+        let ListenAddr::Tcp(addr) = &self.addr else {
+            unreachable!()
+        };
+        let path = format!("{}:{}", fake_tools().display(), std::env::var("PATH").unwrap());
+        command
+            .env("PATH", path)
+            .env("FREIGHT_DAEMON_ADDR", addr)
+            .env("FAKE_RSYNC_LOG", self.rsync_log())
+            .env_remove("FREIGHT_AUTH_TOKEN");
+        for (key, value) in env {
+            command.env(key, value);
+        }
+        tokio::time::timeout(RUN_TIMEOUT, command.output())
+            .await
+            .expect("tool did not finish")
+            .unwrap()
+    }
+
+    /// What the daemon heard up to and including the first STOP.
+    async fn messages_until_stop(&mut self) -> Vec<WorkerMessage> {
+        let mut messages = Vec::new();
+        tokio::time::timeout(RUN_TIMEOUT, async {
+            loop {
+                let message = self.messages.recv().await.unwrap();
+                let stop = matches!(message.message_type, MessageType::Stop);
+                messages.push(message);
+                if stop {
+                    break;
+                }
+            }
+        })
+        .await
+        .expect("daemon never heard a STOP");
+        messages
+    }
+
+    /// The daemon's view of `tool` on `directory` once it has stopped.
+    async fn stopped(&self, tool: &str, directory: &Path) -> WorkerState {
+        let directory = directory.to_str().unwrap();
+        tokio::time::timeout(RUN_TIMEOUT, async {
+            loop {
+                if let Ok(snapshot) = freight::socket::query_workers(&self.addr, None).await {
+                    let stopped = snapshot.workers.into_values().find(|worker| {
+                        worker.tool == tool
+                            && worker.directory.as_deref() == Some(directory)
+                            && worker.finished_at.is_some()
+                    });
+                    if let Some(worker) = stopped {
+                        return worker;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("daemon never heard the tool stop")
+    }
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[tokio::test]
+async fn migrate_succeeds_with_info_logging_on() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    let dest = harness.root.path().join("a");
+
+    let output = harness
+        .run(
+            "freight-migrate",
+            &["--retry", "1", source.to_str().unwrap(), dest.to_str().unwrap()],
+            &[],
+        )
+        .await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(harness.rsync_runs().len(), 1);
+    assert_eq!(harness.stopped("migrate", &source).await.status, "ok");
+}
+
+#[tokio::test]
+async fn require_empty_dest_fails_before_copying_into_a_populated_dest() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    let dest = harness.dir("dest/a");
+    let args = [
+        "--retry",
+        "1",
+        "--require-empty-dest",
+        source.to_str().unwrap(),
+        dest.to_str().unwrap(),
+    ];
+
+    let output = harness.run("freight-migrate", &args, &[]).await;
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Destination already has data"), "{}", stderr(&output));
+    assert!(harness.rsync_runs().is_empty());
+    assert_eq!(harness.stopped("migrate", &source).await.status, "failed");
+}
+
+#[tokio::test]
+async fn progress_bursts_are_thinned_but_the_last_update_is_sent() {
+    let mut harness = Harness::new().await;
+    let script = r#"socket_init migrate /src/a
+socket_start migrate /src/a
+for bytes in $(seq 1 200); do
+    socket_progress migrate /src/a copying "$bytes"
+done
+socket_stop migrate /src/a ok 200"#;
+
+    let output = harness.shell(script, &[("FREIGHT_PROGRESS_INTERVAL_MS", "200")]).await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let messages = harness.messages_until_stop().await;
+    let progress: Vec<u64> = messages
+        .iter()
+        .filter(|message| matches!(message.message_type, MessageType::Progress))
+        .map(|message| message.bytes.unwrap())
+        .collect();
+    assert!(!progress.is_empty() && progress.len() < 50, "{:?}", progress);
+    assert_eq!(progress.last(), Some(&200));
+    assert!(progress.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", progress);
+    assert!(matches!(messages[0].message_type, MessageType::Hello | MessageType::Start));
+    assert!(matches!(messages.last().unwrap().message_type, MessageType::Stop));
+}
+
+#[tokio::test]
+async fn every_backend_copies_the_directory() {
+    for backend in ["rsync", "tar", "cp"] {
+        let harness = Harness::new().await;
+        let source = harness.dir("source/a");
+        harness.dir("source/a/nested");
+        let dest = harness.root.path().join("a");
+        let args = [
+            "--retry",
+            "1",
+            "--backend",
+            backend,
+            source.to_str().unwrap(),
+            dest.to_str().unwrap(),
+        ];
+
+        let output = harness.run("freight-migrate", &args, &[]).await;
+
+        assert!(output.status.success(), "{}: {}", backend, stderr(&output));
+        assert_eq!(harness.stopped("migrate", &source).await.status, "ok", "{}", backend);
+        if backend == "rsync" {
+            let runs = harness.rsync_runs();
+            assert_eq!(runs.len(), 1);
+            let expected = format!("{}/ {}", source.display(), dest.display());
+            assert!(runs[0].ends_with(&expected), "{}", runs[0]);
+        } else {
+            assert!(harness.rsync_runs().is_empty(), "{}", backend);
+            assert_eq!(fs::read_to_string(dest.join("nested/file")).unwrap(), "data");
+            assert_eq!(fs::read_to_string(dest.join("file")).unwrap(), "data");
+        }
+    }
+}
+
+#[tokio::test]
+async fn migrate_passes_the_partial_flags_to_rsync_once() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    let dest = harness.root.path().join("a");
+    let args = [
+        "--retry",
+        "1",
+        "--rsync-flags",
+        "-a --partial --partial-dir=.rsync-partial",
+        source.to_str().unwrap(),
+        dest.to_str().unwrap(),
+    ];
+
+    let output = harness.run("freight-migrate", &args, &[]).await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let runs = harness.rsync_runs();
+    assert_eq!(runs.len(), 1);
+    let flags: Vec<&str> = runs[0].split_whitespace().collect();
+    assert_eq!(flags.iter().filter(|flag| **flag == "--partial").count(), 1, "{}", runs[0]);
+    assert_eq!(flags.iter().filter(|flag| flag.starts_with("--partial-dir")).count(), 1);
+}
+
+#[tokio::test]
+async fn shards_partition_the_top_level_entries_disjointly_and_completely() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    let mut entries = vec!["file".to_string()];
+    for (name, size) in [("big", 40_000), ("mid", 20_000), ("with space", 9_000), ("small", 10)] {
+        fs::write(source.join(name), vec![0u8; size]).unwrap();
+        entries.push(name.to_string());
+    }
+    for name in ["dir one", "dir two"] {
+        harness.dir(&format!("source/a/{}", name));
+        entries.push(name.to_string());
+    }
+    let work = harness.root.path().join("shards");
+    fs::create_dir(&work).unwrap();
+    let migrate = Path::new(env!("CARGO_MANIFEST_DIR")).join("bin/freight-migrate");
+    let script = format!(
+        "source <(sed -n '/^partition_entries()/,/^}}/p' {:?})\npartition_entries {:?} 3 {:?}",
+        migrate, source, work
+    );
+
+    let output = harness.shell(&script, &[]).await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lists: Vec<Vec<String>> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|list| {
+            let content = fs::read_to_string(list).unwrap();
+            content.split_terminator('\0').map(str::to_string).collect()
+        })
+        .collect();
+    assert_eq!(lists.len(), 3);
+    assert!(lists.iter().all(|list| !list.is_empty()), "{:?}", lists);
+    let mut all: Vec<String> = lists.concat();
+    all.sort();
+    entries.sort();
+    assert_eq!(all, entries, "every entry in exactly one shard");
+    // Largest first onto the emptiest list, so the two biggest never share
+    let shard_of = |name: &str| lists.iter().position(|list| list.iter().any(|e| e == name));
+    assert_ne!(shard_of("big"), shard_of("mid"));
+}
+
+#[tokio::test]
+async fn sharded_migrate_runs_an_rsync_per_shard() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    harness.dir("source/a/one");
+    harness.dir("source/a/two");
+    let dest = harness.root.path().join("a");
+    let args = ["--retry", "1", "--shards", "2", source.to_str().unwrap(), dest.to_str().unwrap()];
+
+    let output = harness.run("freight-migrate", &args, &[]).await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let runs = harness.rsync_runs();
+    assert_eq!(runs.len(), 2, "{:?}", runs);
+    assert!(runs.iter().all(|run| run.contains(" -r --from0 --files-from=")), "{:?}", runs);
+    assert_eq!(harness.stopped("migrate", &source).await.status, "ok");
+}
+
+#[tokio::test]
+async fn verify_hashes_with_the_chosen_algorithm() {
+    let harness = Harness::new().await;
+    let verify = Path::new(env!("CARGO_MANIFEST_DIR")).join("bin/freight-verify");
+    let script = format!(
+        "source <(sed -n '/^checksum_command()/,/^}}/p' {:?})\n\
+         for CHECKSUM_ALGORITHM in md5 sha1 xxhash; do echo \"$(checksum_command)\"; done",
+        verify
+    );
+
+    let output = harness.shell(&script, &[]).await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "md5sum\nsha1sum\nxxhsum -H1\n");
+}
+
+#[tokio::test]
+async fn verify_rejects_an_unknown_checksum_algorithm() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    let dest = harness.dir("dest/a");
+    let args = [
+        "--mode",
+        "checksum",
+        "--checksum",
+        "crc32",
+        source.to_str().unwrap(),
+        dest.to_str().unwrap(),
+    ];
+
+    let output = harness.run("freight-verify", &args, &[]).await;
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Invalid checksum algorithm: crc32"), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn remote_dest_reaches_rsync_over_ssh_with_its_options() {
+    let harness = Harness::new().await;
+    let source = harness.dir("source/a");
+    // An rsync ahead of the usual fake that logs an argument per line, to
+    // see where the ssh command's boundaries fall
+    let rsync_dir = harness.root.path().join("rsync-bin");
+    fs::create_dir(&rsync_dir).unwrap();
+    let rsync = rsync_dir.join("rsync");
+    let script = "#!/usr/bin/env bash\nprintf '%s\\n' \"$@\" >> \"$FAKE_RSYNC_LOG\"\n";
+    fs::write(&rsync, script).unwrap();
+    fs::set_permissions(&rsync, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}:{}",
+        rsync_dir.display(),
+        fake_tools().display(),
+        std::env::var("PATH").unwrap()
+    );
+    let args = [
+        "--retry",
+        "1",
+        "--ssh-options",
+        "-i /keys/migrate -p 2222",
+        source.to_str().unwrap(),
+        "backup@nas:/vol/a",
+    ];
+
+    let output = harness.run("freight-migrate", &args, &[("PATH", &path)]).await;
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let args = harness.rsync_runs();
+    let ssh = args.iter().position(|arg| arg == "-e").expect("no -e passed to rsync");
+    assert_eq!(args[ssh + 1], "ssh -i /keys/migrate -p 2222");
+    let source = format!("{}/", source.display());
+    assert_eq!(args[args.len() - 2..], [source.as_str(), "backup@nas:/vol/a"]);
+    // Nothing local was made for the remote path
+    assert!(!Path::new("backup@nas:").exists());
+}
+
+#[tokio::test]
+async fn every_backend_copies_a_top_level_file() {
+    for backend in ["rsync", "tar", "cp"] {
+        let harness = Harness::new().await;
+        harness.dir("source");
+        let source = harness.root.path().join("source/notes.txt");
+        fs::write(&source, "notes").unwrap();
+        let dest = harness.root.path().join("dest/notes.txt");
+        fs::create_dir(dest.parent().unwrap()).unwrap();
+        let args = [
+            "--retry",
+            "1",
+            "--backend",
+            backend,
+            source.to_str().unwrap(),
+            dest.to_str().unwrap(),
+        ];
+
+        let output = harness.run("freight-migrate", &args, &[]).await;
+
+        assert!(output.status.success(), "{}: {}", backend, stderr(&output));
+        assert_eq!(harness.stopped("migrate", &source).await.status, "ok", "{}", backend);
+        // The file itself, not a directory named after it
+        if backend == "rsync" {
+            let runs = harness.rsync_runs();
+            assert_eq!(runs.len(), 1);
+            let expected = format!(" {} {}", source.display(), dest.display());
+            assert!(runs[0].ends_with(&expected), "{}", runs[0]);
+            assert!(!dest.exists());
+        } else {
+            assert_eq!(fs::read_to_string(&dest).unwrap(), "notes", "{}", backend);
+        }
+    }
+}
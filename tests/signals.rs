@@ -0,0 +1,127 @@
+//! Signals sent to the test process itself, so every test here holds
+//! `SIGNALS` to keep another run from catching them.
+
+mod common;
+
+use common::{install_fake_tools, Project, RUN_TIMEOUT};
+use freight::socket::{Transport, SOCKET_PATH};
+use freight::Freight;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+static SIGNALS: Mutex<()> = Mutex::const_new(());
+
+fn signal_self(signal: &str) {
+    let status = Command::new("kill")
+        .args([&format!("-{}", signal), &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn sigterm_drains_running_workers_and_removes_the_socket() {
+    let _signals = SIGNALS.lock().await;
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    for name in ["alpha", "beta"] {
+        // Over a Unix socket the fake tools can't report, so sleep first
+        project.control(name, "scan", r#"echo $$ > "$unit/.pid"; sleep 1"#);
+    }
+    let mut config = project.config();
+    config.transport = Transport::Unix;
+    config.thresholds.parallel_workers = 1;
+    config.drain_timeout_secs = 10;
+    let freight = Freight::new(config).handle_signals(true);
+
+    let run = tokio::spawn(async move { freight.run_migration().await });
+    let pid_file = tokio::time::timeout(RUN_TIMEOUT, async {
+        loop {
+            for name in ["alpha", "beta"] {
+                let pid_file = project.unit(name).join(".pid");
+                if pid_file.exists() {
+                    return pid_file;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("no scan started");
+    assert!(Path::new(SOCKET_PATH).exists());
+    signal_self("TERM");
+    let summary = tokio::time::timeout(RUN_TIMEOUT, run)
+        .await
+        .expect("daemon did not drain")
+        .unwrap()
+        .expect("migration failed");
+
+    assert!(!Path::new(SOCKET_PATH).exists(), "socket left behind");
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    assert!(
+        !Path::new("/proc").join(pid.trim()).exists(),
+        "scan {} was not reaped",
+        pid.trim()
+    );
+    // The running scan finished; nothing queued behind it started
+    assert_eq!(summary.directories.len(), 1);
+    assert_eq!(summary.directories[0].phase, "scan");
+    for name in ["alpha", "beta"] {
+        assert!(project.runs(name, "migrate").is_empty());
+    }
+}
+
+#[tokio::test]
+async fn sighup_reloads_valid_configs_and_keeps_the_last_one_otherwise() {
+    let _signals = SIGNALS.lock().await;
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    let go = project.root.path().join("go");
+    for name in ["alpha", "beta"] {
+        project.control(
+            name,
+            "scan",
+            &format!(r#"touch "$unit/.started"; until [[ -e {:?} ]]; do sleep 0.05; done"#, go),
+        );
+    }
+    let mut config = project.config();
+    config.rsync_flags = "-a --before".to_string();
+    let config_path = config.config_path();
+    config.save(&config_path).unwrap();
+    let freight = Freight::new(config.clone()).handle_signals(true);
+
+    let run = tokio::spawn(async move { freight.run_migration().await });
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while !["alpha", "beta"].iter().any(|name| project.unit(name).join(".started").exists()) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("no scan started");
+
+    config.rsync_flags = "-a --reloaded".to_string();
+    config.save(&config_path).unwrap();
+    signal_self("HUP");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    // Fails validation, so the daemon keeps the reloaded flags
+    config.rsync_flags = "-a --rejected".to_string();
+    config.thresholds.parallel_workers = 0;
+    config.save(&config_path).unwrap();
+    signal_self("HUP");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    std::fs::write(&go, "").unwrap();
+
+    let summary = tokio::time::timeout(RUN_TIMEOUT, run)
+        .await
+        .expect("migration did not finish")
+        .unwrap()
+        .expect("migration failed");
+
+    assert_eq!(summary.succeeded(), 2);
+    for name in ["alpha", "beta"] {
+        let args = common::migrate_args(&project, name);
+        assert!(args.contains("--rsync-flags -a --reloaded "), "{}", args);
+    }
+}
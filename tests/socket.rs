@@ -0,0 +1,392 @@
+//! The daemon's socket protocol, spoken over TCP to a real `SocketServer`.
+
+mod common;
+
+use common::{free_tcp_address, send_lines, serve, RUN_TIMEOUT};
+use freight::socket::{ConnectionLimits, MessageType, SUBSCRIBE_COMMAND};
+use freight::{SocketServer, WorkerMessage};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+#[tokio::test]
+async fn subscriber_receives_worker_messages() {
+    let (_server, addr) = serve(SocketServer::new(16).0).await;
+    let mut subscriber = freight::socket::connect(&addr).await.unwrap();
+    subscriber
+        .write_all(format!("{}\n", SUBSCRIBE_COMMAND).as_bytes())
+        .await
+        .unwrap();
+    let mut subscriber = BufReader::new(subscriber);
+
+    // Nothing confirms the subscription, so keep reporting until it's heard
+    let received = tokio::time::timeout(RUN_TIMEOUT, async {
+        let mut line = String::new();
+        loop {
+            send_lines(&addr, &["START tool=migrate dir=/src/a%20b"]).await;
+            tokio::select! {
+                read = subscriber.read_line(&mut line) => {
+                    read.unwrap();
+                    break line;
+                }
+                _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+            }
+        }
+    })
+    .await
+    .expect("subscriber heard nothing");
+
+    let message: WorkerMessage = serde_json::from_str(&received).unwrap();
+    assert!(matches!(message.message_type, MessageType::Start));
+    assert_eq!(message.tool, "migrate");
+    assert_eq!(message.directory.as_deref(), Some("/src/a b"));
+}
+
+/// Query `addr` until `done` holds for the snapshot, or fail after a while.
+async fn query_until(
+    addr: &freight::socket::ListenAddr,
+    token: Option<&str>,
+    done: impl Fn(&freight::socket::WorkersSnapshot) -> bool,
+) -> freight::socket::WorkersSnapshot {
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        loop {
+            if let Ok(snapshot) = freight::socket::query_workers(addr, token).await {
+                if done(&snapshot) {
+                    return snapshot;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("daemon never reached the expected state")
+}
+
+#[tokio::test]
+async fn tcp_worker_is_tracked_from_hello_to_stop() {
+    let (_server, addr) = serve(SocketServer::new(16).0).await;
+
+    send_lines(
+        &addr,
+        &[
+            "HELLO freight/0.1.0 host=nas2 pid=77 tool=migrate dir=/src/a",
+            "START tool=migrate dir=/src/a",
+            "PROGRESS tool=migrate dir=/src/a bytes=512 msg=copying",
+            "STOP tool=migrate dir=/src/a status=ok bytes=1024",
+        ],
+    )
+    .await;
+    let snapshot = query_until(&addr, None, |snapshot| {
+        snapshot.workers.values().any(|worker| worker.status == "ok")
+    })
+    .await;
+
+    assert_eq!(snapshot.workers.len(), 1);
+    let worker = snapshot.workers.values().next().unwrap();
+    assert_eq!(worker.tool, "migrate");
+    assert_eq!(worker.directory.as_deref(), Some("/src/a"));
+    assert_eq!(worker.host.as_deref(), Some("nas2"));
+    assert_eq!(worker.pid, Some(77));
+    assert_eq!(worker.bytes_transferred, Some(1024));
+    assert_eq!(worker.last_message.as_deref(), Some("copying"));
+    assert!(worker.started_at.is_some() && worker.finished_at.is_some());
+}
+
+#[tokio::test]
+async fn only_connections_with_the_right_token_are_served() {
+    let mut server = SocketServer::new(16).0;
+    server.set_auth_token(Some("s3cret".to_string()));
+    let (_server, addr) = serve(server).await;
+    let wait = Duration::from_secs(1);
+
+    assert!(freight::socket::ping(&addr, Some("s3cret"), wait).await);
+    assert!(!freight::socket::ping(&addr, None, wait).await);
+    assert!(!freight::socket::ping(&addr, Some("wrong"), wait).await);
+    assert!(freight::socket::query_workers(&addr, Some("wrong")).await.is_err());
+
+    send_lines(&addr, &["START tool=scan dir=/src/missing"]).await;
+    send_lines(&addr, &["AUTH wrong", "START tool=scan dir=/src/wrong"]).await;
+    send_lines(&addr, &["AUTH s3cret", "START tool=scan dir=/src/right"]).await;
+    let snapshot = query_until(&addr, Some("s3cret"), |snapshot| !snapshot.workers.is_empty()).await;
+
+    let directories: Vec<_> = snapshot
+        .workers
+        .values()
+        .filter_map(|worker| worker.directory.as_deref())
+        .collect();
+    assert_eq!(directories, ["/src/right"]);
+}
+
+#[tokio::test]
+async fn unix_socket_gets_the_configured_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("freight.sock");
+    let mut config = freight::Config::default_with_paths("/src", "/dst");
+    config.socket_mode = "0660".to_string();
+    let (mut server, _rx) = SocketServer::new(16);
+    server.set_listen_addr(freight::socket::ListenAddr::Unix(path.clone()));
+    server.set_socket_permissions(config.socket_permissions().unwrap());
+    let server = std::sync::Arc::new(server);
+    let listening = std::sync::Arc::clone(&server);
+    tokio::spawn(async move { listening.start().await });
+    tokio::time::timeout(RUN_TIMEOUT, server.wait_listening()).await.unwrap();
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o660);
+}
+
+#[tokio::test]
+async fn bare_hello_then_start_makes_one_entry() {
+    let (_server, addr) = serve(SocketServer::new(16).0).await;
+
+    send_lines(
+        &addr,
+        &["HELLO freight/0.1.0 host=nas1 pid=4242", "START tool=scan dir=/src/a"],
+    )
+    .await;
+    let snapshot = query_until(&addr, None, |snapshot| !snapshot.workers.is_empty()).await;
+
+    assert_eq!(snapshot.workers.len(), 1, "{:?}", snapshot.workers);
+    let worker = snapshot.workers.values().next().unwrap();
+    assert_eq!(worker.tool, "scan");
+    assert_eq!(worker.directory.as_deref(), Some("/src/a"));
+    assert_eq!(worker.status, "running");
+    assert_eq!(worker.host.as_deref(), Some("nas1"));
+    assert_eq!(worker.pid, Some(4242));
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn worker_reports_to_an_abstract_socket() {
+    let addr = freight::socket::ListenAddr::Abstract(format!("freight-test-{}", std::process::id()));
+    let (mut server, _) = SocketServer::new(16);
+    server.set_listen_addr(addr.clone());
+    let server = std::sync::Arc::new(server);
+    let listening = std::sync::Arc::clone(&server);
+    tokio::spawn(async move { listening.start().await });
+    tokio::time::timeout(RUN_TIMEOUT, server.wait_listening())
+        .await
+        .expect("server never started listening");
+
+    send_lines(&addr, &["START tool=scan dir=/src/a"]).await;
+    let snapshot = query_until(&addr, None, |snapshot| !snapshot.workers.is_empty()).await;
+
+    let worker = snapshot.workers.values().next().unwrap();
+    assert_eq!(worker.directory.as_deref(), Some("/src/a"));
+    assert_eq!(worker.status, "running");
+}
+
+#[tokio::test]
+async fn query_answers_one_json_snapshot_line() {
+    let (_server, addr) = serve(SocketServer::new(16).0).await;
+    send_lines(&addr, &["START tool=migrate dir=/src/a%20b"]).await;
+    query_until(&addr, None, |snapshot| !snapshot.workers.is_empty()).await;
+
+    let stream = freight::socket::connect(&addr).await.unwrap();
+    let (read_half, mut write_half) = tokio::io::split(stream);
+    let mut replies = BufReader::new(read_half).lines();
+    write_half.write_all(b"QUERY keep_open\n").await.unwrap();
+    let first = replies.next_line().await.unwrap().unwrap();
+    write_half.write_all(b"QUERY\n").await.unwrap();
+    let second = replies.next_line().await.unwrap().unwrap();
+
+    assert_eq!(first, second);
+    let snapshot: serde_json::Value = serde_json::from_str(&first).unwrap();
+    assert!(snapshot["started_at"].is_object(), "{}", snapshot);
+    let workers = snapshot["workers"].as_array().unwrap();
+    assert_eq!(workers.len(), 1);
+    assert_eq!(workers[0]["tool"], "migrate");
+    assert_eq!(workers[0]["directory"], "/src/a b");
+    assert_eq!(workers[0]["status"], "running");
+    // Without keep_open, the daemon hangs up after replying
+    assert_eq!(replies.next_line().await.unwrap(), None);
+}
+
+/// Report STARTs for four directories to a server whose channel holds
+/// `capacity` messages, then read them all only once every one was sent.
+async fn broadcast_through(capacity: usize) -> Vec<Result<String, u64>> {
+    let (server, mut receiver) = SocketServer::new(capacity);
+    let (_server, addr) = serve(server).await;
+    for name in ["a", "b", "c", "d"] {
+        send_lines(&addr, &[&format!("START tool=migrate dir=/src/{}", name)]).await;
+    }
+    // Counts what was sent, lost to lag or not
+    tokio::time::timeout(RUN_TIMEOUT, async {
+        while receiver.len() < 4 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("server broadcast too little");
+
+    let mut received = Vec::new();
+    while received.last() != Some(&Ok("/src/d".to_string())) {
+        match tokio::time::timeout(RUN_TIMEOUT, receiver.recv()).await.unwrap() {
+            Ok(message) => received.push(Ok(message.directory.unwrap())),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                received.push(Err(missed))
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+    received
+}
+
+#[tokio::test]
+async fn broadcast_capacity_bounds_what_a_slow_receiver_keeps() {
+    assert_eq!(
+        broadcast_through(16).await,
+        vec![Ok("/src/a".into()), Ok("/src/b".into()), Ok("/src/c".into()), Ok("/src/d".into())]
+    );
+    assert_eq!(
+        broadcast_through(2).await,
+        vec![Err(2), Ok("/src/c".into()), Ok("/src/d".into())]
+    );
+}
+
+#[tokio::test]
+async fn worker_that_says_bye_is_pruned_once_the_grace_period_passes() {
+    let (server, mut messages) = SocketServer::new(16);
+    let (server, addr) = serve(server).await;
+    let grace = Duration::from_millis(300);
+
+    send_lines(
+        &addr,
+        &[
+            "START tool=scan dir=/src/a",
+            "STOP tool=scan dir=/src/a status=ok",
+            "BYE tool=scan dir=/src/a",
+        ],
+    )
+    .await;
+    query_until(&addr, None, |snapshot| {
+        snapshot.workers.values().any(|worker| worker.finished_at.is_some() && !worker.connected)
+    })
+    .await;
+    assert_eq!(server.prune_finished(grace).await, 0);
+    assert_eq!(query_until(&addr, None, |_| true).await.workers.len(), 1);
+
+    tokio::time::sleep(grace).await;
+    assert_eq!(server.prune_finished(grace).await, 1);
+    assert!(query_until(&addr, None, |_| true).await.workers.is_empty());
+
+    // Subscribers hear the START and STOP, then a BYE from the pruning only
+    let types: Vec<_> = std::iter::from_fn(|| messages.try_recv().ok())
+        .map(|message| format!("{:?}", message.message_type))
+        .collect();
+    assert_eq!(types, ["Start", "Stop", "Bye"]);
+}
+
+#[tokio::test]
+async fn over_length_line_closes_only_its_connection() {
+    let (mut server, _rx) = SocketServer::new(16);
+    server.set_connection_limits(ConnectionLimits {
+        max_line_length: 1024,
+        ..ConnectionLimits::default()
+    });
+    let (_server, addr) = serve(server).await;
+
+    let mut stream = freight::socket::connect(&addr).await.unwrap();
+    stream.write_all(b"START tool=migrate dir=/src/a\n").await.unwrap();
+    stream.write_all(&[b'x'; 4096]).await.unwrap();
+    // The daemon hangs up, with a reset if it left bytes unread
+    let closed = tokio::time::timeout(RUN_TIMEOUT, stream.read(&mut [0; 16]))
+        .await
+        .expect("connection left open");
+    assert!(matches!(closed, Ok(0) | Err(_)), "{:?}", closed);
+
+    // Lines before the long one counted, and the daemon still serves others
+    send_lines(&addr, &["START tool=migrate dir=/src/b"]).await;
+    let snapshot = query_until(&addr, None, |snapshot| snapshot.workers.len() == 2).await;
+    assert!(snapshot.workers.values().all(|worker| worker.status == "running"));
+}
+
+#[tokio::test]
+async fn daemon_answers_ping_only_once_its_listener_is_bound() {
+    let addr = freight::socket::ListenAddr::Tcp(free_tcp_address());
+    let short = Duration::from_millis(200);
+    assert!(!freight::socket::ping(&addr, None, short).await);
+    assert!(!freight::socket::wait_until_ready(&addr, None, short).await);
+
+    let waiting = {
+        let addr = addr.clone();
+        tokio::spawn(async move {
+            freight::socket::wait_until_ready(&addr, None, RUN_TIMEOUT).await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!waiting.is_finished());
+
+    let (mut server, _rx) = SocketServer::new(16);
+    server.set_listen_addr(addr.clone());
+    let server = std::sync::Arc::new(server);
+    let listening = std::sync::Arc::clone(&server);
+    tokio::spawn(async move { listening.start().await });
+
+    assert!(waiting.await.unwrap());
+    assert!(freight::socket::ping(&addr, None, short).await);
+}
+
+#[tokio::test]
+async fn crlf_tabs_and_invalid_utf8_still_parse() {
+    let (_server, addr) = serve(SocketServer::new(16).0).await;
+
+    let mut stream = freight::socket::connect(&addr).await.unwrap();
+    stream.write_all(b"START\ttool=migrate  dir=/src/caf\xe9\r\n").await.unwrap();
+    stream.write_all(b"PROGRESS tool=migrate\t dir=/src/caf\xe9 msg=copying\r\n").await.unwrap();
+    stream.shutdown().await.unwrap();
+    let snapshot = query_until(&addr, None, |snapshot| {
+        snapshot.workers.values().any(|worker| worker.last_message.is_some())
+    })
+    .await;
+
+    let worker = snapshot.workers.values().next().unwrap();
+    assert_eq!(worker.directory.as_deref(), Some("/src/caf\u{fffd}"));
+    assert_eq!(worker.status, "running");
+    assert_eq!(worker.last_message.as_deref(), Some("copying"));
+}
+
+#[tokio::test]
+async fn strict_utf8_closes_a_connection_on_invalid_utf8() {
+    let (mut server, _rx) = SocketServer::new(16);
+    server.set_connection_limits(ConnectionLimits {
+        strict_utf8: true,
+        ..ConnectionLimits::default()
+    });
+    let (_server, addr) = serve(server).await;
+
+    let mut stream = freight::socket::connect(&addr).await.unwrap();
+    stream.write_all(b"START tool=migrate dir=/src/caf\xe9\n").await.unwrap();
+    let closed = tokio::time::timeout(RUN_TIMEOUT, stream.read(&mut [0; 16]))
+        .await
+        .expect("connection left open");
+    assert!(matches!(closed, Ok(0) | Err(_)), "{:?}", closed);
+
+    send_lines(&addr, &["START tool=migrate dir=/src/a"]).await;
+    let snapshot = query_until(&addr, None, |snapshot| !snapshot.workers.is_empty()).await;
+    assert_eq!(snapshot.workers.len(), 1);
+    assert_eq!(snapshot.workers.values().next().unwrap().directory.as_deref(), Some("/src/a"));
+}
+
+#[tokio::test]
+async fn uptime_in_the_snapshot_grows_from_a_fixed_start() {
+    let before = std::time::SystemTime::now();
+    let (_server, addr) = serve(SocketServer::new(16).0).await;
+
+    let first = freight::socket::query_workers(&addr, None).await.unwrap();
+    let first_uptime = first.uptime().expect("no start time in the snapshot");
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let second = freight::socket::query_workers(&addr, None).await.unwrap();
+    let second_uptime = second.uptime().unwrap();
+
+    let started_at = first.started_at.unwrap();
+    assert_eq!(second.started_at, Some(started_at));
+    assert!(started_at >= before && started_at <= std::time::SystemTime::now());
+    assert!(
+        second_uptime >= first_uptime + Duration::from_secs(1),
+        "{:?} then {:?}",
+        first_uptime,
+        second_uptime
+    );
+}
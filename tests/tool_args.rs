@@ -0,0 +1,220 @@
+//! What the migrate tool is spawned with for a given config, as recorded
+//! by the fake tools in `common`.
+
+mod common;
+
+use common::{install_fake_tools, migrate_args, run, Project};
+use freight::worker::{ExistingPolicy, MigrationBackend};
+
+#[tokio::test]
+async fn bandwidth_limit_reaches_the_migrate_tool_in_kbps() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.bandwidth_limit = Some("50MB".to_string());
+
+    run(config).await;
+
+    assert!(migrate_args(&project, "alpha").contains("--bwlimit 51200 "));
+}
+
+#[tokio::test]
+async fn no_bandwidth_limit_passes_no_bwlimit() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+
+    run(project.config()).await;
+
+    assert!(!migrate_args(&project, "alpha").contains("--bwlimit"));
+}
+
+#[tokio::test]
+async fn tool_flags_replace_rsync_flags_for_migrate() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.rsync_flags = "-a".to_string();
+    config.tool_flags.insert("migrate".to_string(), "-aHAX --delete".to_string());
+
+    run(config).await;
+
+    assert!(migrate_args(&project, "alpha").contains("--rsync-flags -aHAX --delete "));
+}
+
+#[tokio::test]
+async fn rsync_flags_apply_without_a_migrate_override() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.rsync_flags = "-a".to_string();
+    config.tool_flags.insert("scan".to_string(), "-aHAX --delete".to_string());
+
+    run(config).await;
+
+    assert!(migrate_args(&project, "alpha").contains("--rsync-flags -a "));
+}
+
+#[tokio::test]
+async fn existing_policy_picks_the_migrate_flags() {
+    install_fake_tools();
+    for (policy, ignore_existing, require_empty) in [
+        (ExistingPolicy::Overwrite, false, false),
+        (ExistingPolicy::SkipExisting, true, false),
+        (ExistingPolicy::ErrorIfPresent, false, true),
+    ] {
+        let project = Project::new(&["alpha"]);
+        let mut config = project.config();
+        config.existing_policy = policy;
+
+        run(config).await;
+
+        let args = migrate_args(&project, "alpha");
+        assert_eq!(args.contains("--ignore-existing"), ignore_existing, "{:?}: {}", policy, args);
+        assert_eq!(args.contains("--require-empty-dest"), require_empty, "{:?}: {}", policy, args);
+    }
+}
+
+#[tokio::test]
+async fn dest_map_sends_mapped_directories_elsewhere() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    let mut config = project.config();
+    config.dest_map.insert("alpha".to_string(), "archive/alpha".into());
+
+    run(config).await;
+
+    let alpha = project.dest.join("archive/alpha");
+    let beta = project.dest.join("beta");
+    assert!(migrate_args(&project, "alpha").ends_with(&format!(" {}", alpha.display())));
+    assert!(migrate_args(&project, "beta").ends_with(&format!(" {}", beta.display())));
+}
+
+#[tokio::test]
+async fn backend_is_passed_to_the_migrate_tool() {
+    install_fake_tools();
+    for backend in [MigrationBackend::Rsync, MigrationBackend::Tar, MigrationBackend::Cp] {
+        let project = Project::new(&["alpha"]);
+        let mut config = project.config();
+        config.backend = backend;
+
+        run(config).await;
+
+        let args = migrate_args(&project, "alpha");
+        assert!(args.contains(&format!("--backend {} ", backend.as_str())), "{}", args);
+    }
+}
+
+#[tokio::test]
+async fn dest_override_redirects_workers_without_touching_the_saved_config() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let scratch = project.root.path().join("scratch");
+    let mut config = project.config();
+    let config_path = config.config_path();
+    config.save(&config_path).unwrap();
+    let saved = std::fs::read_to_string(&config_path).unwrap();
+    config.dest_override = Some(scratch.clone());
+    config.validate().unwrap();
+    config.save(&config_path).unwrap();
+
+    run(config).await;
+
+    let args = migrate_args(&project, "alpha");
+    assert!(args.ends_with(&format!(" {}", scratch.join("alpha").display())), "{}", args);
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), saved);
+}
+
+#[test]
+fn dest_override_must_be_an_absolute_directory_path() {
+    let project = Project::new(&[]);
+    let file = project.root.path().join("file");
+    std::fs::write(&file, "").unwrap();
+    for (dest, problem) in [
+        ("relative/dest".into(), "must be an absolute path"),
+        (file, "is not a directory"),
+        (project.root.path().join("missing/dest"), "parent directory does not exist"),
+    ] {
+        let mut config = project.config();
+        config.dest_override = Some(dest);
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains(problem), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn partial_flags_are_passed_once_whatever_the_rsync_flags_say() {
+    install_fake_tools();
+    for (rsync_flags, resume_partial, partial, partial_dir) in [
+        ("-a", true, 1, 1),
+        ("-a --partial", true, 1, 1),
+        ("-a --partial-dir=.keep --partial", true, 1, 1),
+        // -P already means --partial
+        ("-aP", true, 0, 1),
+        ("-a", false, 0, 0),
+    ] {
+        let project = Project::new(&["alpha"]);
+        let mut config = project.config();
+        config.rsync_flags = rsync_flags.to_string();
+        config.resume_partial = resume_partial;
+
+        run(config).await;
+
+        let args = migrate_args(&project, "alpha");
+        let count = |matches: fn(&str) -> bool| {
+            args.split_whitespace().filter(|arg| matches(arg)).count()
+        };
+        assert_eq!(count(|arg| arg == "--partial"), partial, "{}", args);
+        assert_eq!(count(|arg| arg.starts_with("--partial-dir")), partial_dir, "{}", args);
+    }
+}
+
+#[tokio::test]
+async fn intra_dir_parallelism_asks_the_migrate_tool_for_shards() {
+    install_fake_tools();
+    for (parallelism, backend, shards) in [
+        (4, MigrationBackend::Rsync, true),
+        (1, MigrationBackend::Rsync, false),
+        (4, MigrationBackend::Tar, false),
+    ] {
+        let project = Project::new(&["alpha"]);
+        let mut config = project.config();
+        config.intra_dir_parallelism = parallelism;
+        config.backend = backend;
+
+        run(config).await;
+
+        let args = migrate_args(&project, "alpha");
+        assert_eq!(args.contains("--shards"), shards, "{}", args);
+        assert!(!shards || args.contains("--shards 4 "), "{}", args);
+    }
+}
+
+#[tokio::test]
+async fn remote_dest_reaches_the_migrate_tool_with_its_ssh_options() {
+    install_fake_tools();
+    let project = Project::new(&["alpha", "beta"]);
+    let mut config = project.config();
+    config.dest_path = "backup@nas:/vol/dest".into();
+    config.ssh_options = Some("-i /keys/migrate -p 2222".to_string());
+    config.dest_map.insert("beta".to_string(), "/vol/archive/beta".into());
+
+    run(config).await;
+
+    let alpha = migrate_args(&project, "alpha");
+    assert!(alpha.contains("--ssh-options -i /keys/migrate -p 2222 "), "{}", alpha);
+    assert!(alpha.ends_with(" backup@nas:/vol/dest/alpha"), "{}", alpha);
+    // An absolute mapping stays on the remote host
+    assert!(migrate_args(&project, "beta").ends_with(" backup@nas:/vol/archive/beta"));
+}
+
+#[tokio::test]
+async fn ssh_options_are_left_out_for_a_local_dest() {
+    install_fake_tools();
+    let project = Project::new(&["alpha"]);
+    let mut config = project.config();
+    config.ssh_options = Some("-p 2222".to_string());
+
+    run(config).await;
+
+    assert!(!migrate_args(&project, "alpha").contains("--ssh-options"));
+}